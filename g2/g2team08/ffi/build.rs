@@ -0,0 +1,28 @@
+//! Generates `g2.h`, the C header of the functions of `src/lib.rs`, into
+//! `OUT_DIR`; `tests/ffi.rs` checks that the copy in `include/` matches it.
+
+use std::path::Path;
+
+fn main() {
+    println!("cargo::rerun-if-changed=src/lib.rs");
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let out_dir = std::env::var_os("OUT_DIR").expect("cargo sets OUT_DIR for build scripts");
+    let config = cbindgen::Config {
+        language: cbindgen::Language::C,
+        include_guard: Some("G2_H".into()),
+        header: Some("/* Generated from src/lib.rs by build.rs, do not edit; tests/ffi.rs prints how to update it. */".into()),
+        cpp_compat: true,
+        usize_is_size_t: true,
+        enumeration: cbindgen::EnumConfig {
+            rename_variants: cbindgen::RenameRule::QualifiedScreamingSnakeCase,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(crate_dir.join("src/lib.rs"))
+        .generate()
+        .expect("cannot generate the C header")
+        .write_to_file(Path::new(&out_dir).join("g2.h"));
+}
@@ -0,0 +1,121 @@
+/* Generated from src/lib.rs by build.rs, do not edit; tests/ffi.rs prints how to update it. */
+
+#ifndef G2_H
+#define G2_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * result of every function of the interface
+ */
+typedef enum G2Status {
+  G2_STATUS_OK = 0,
+  /**
+   * no cipher of that name
+   */
+  G2_STATUS_UNKNOWN_CIPHER = 1,
+  /**
+   * the output is longer than the buffer, its length is stored
+   */
+  G2_STATUS_BUFFER_TOO_SMALL = 2,
+  /**
+   * the key is not of the size of the cipher, or not usable with it
+   */
+  G2_STATUS_INVALID_KEY = 3,
+  /**
+   * the ciphertext is malformed or not under this key
+   */
+  G2_STATUS_DECRYPT_FAILED = 4,
+  /**
+   * a required pointer is null
+   */
+  G2_STATUS_NULL_POINTER = 5,
+  /**
+   * a bug of the library, please report it
+   */
+  G2_STATUS_INTERNAL = 6,
+  /**
+   * the message runs past what the key can encrypt
+   */
+  G2_STATUS_ENCRYPT_FAILED = 7,
+} G2Status;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * number of ciphers, the indices of [`g2_cipher_name`]
+ */
+size_t g2_cipher_count(void);
+
+/**
+ * name of the cipher at `index`, a static string, or null past the last one
+ */
+const char *g2_cipher_name(size_t index);
+
+/**
+ * stores the key size of `cipher` in `key_size`
+ *
+ * # Safety
+ * `cipher` is a NUL-terminated string, `key_size` points to a writable `size_t`
+ */
+enum G2Status g2_key_size(const char *cipher, size_t *key_size);
+
+/**
+ * writes a fresh key of `cipher` to `key`, drawn from the thread-local
+ * generator seeded by the operating system
+ *
+ * # Safety
+ * `cipher` is a NUL-terminated string, `key` points to `capacity` writable
+ * bytes and `key_len` to a writable `size_t`
+ */
+enum G2Status g2_keygen(const char *cipher, uint8_t *key, size_t capacity, size_t *key_len);
+
+/**
+ * encrypts the `message_len` bytes of `message` under `key` into `out`
+ *
+ * # Safety
+ * `cipher` is a NUL-terminated string, `key` and `message` point to
+ * `key_len` and `message_len` readable bytes, `out` to `capacity` writable
+ * bytes and `out_len` to a writable `size_t`
+ */
+enum G2Status g2_encrypt(const char *cipher,
+                         const uint8_t *key,
+                         size_t key_len,
+                         const uint8_t *message,
+                         size_t message_len,
+                         uint8_t *out,
+                         size_t capacity,
+                         size_t *out_len);
+
+/**
+ * decrypts the `ciphertext_len` bytes of `ciphertext` under `key` into `out`
+ *
+ * # Safety
+ * as for [`g2_encrypt`]
+ */
+enum G2Status g2_decrypt(const char *cipher,
+                         const uint8_t *key,
+                         size_t key_len,
+                         const uint8_t *ciphertext,
+                         size_t ciphertext_len,
+                         uint8_t *out,
+                         size_t capacity,
+                         size_t *out_len);
+
+/**
+ * a static description of `status`, "unknown status" for a value that is no
+ * `G2Status`; it takes an int because C may pass any value for an enum
+ */
+const char *g2_status_message(int status);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* G2_H */
@@ -0,0 +1,231 @@
+//! C interface to the ciphers of the [registry](g2::registry), built as the
+//! `g2_ffi` cdylib; the build script generates `g2.h` from this file into `OUT_DIR`, and
+//! `include/g2.h` is the copy checked in for C programs.
+//!
+//! Ciphers are named as by their binaries, `"hill-caesar"`, `"chacha20"`...
+//! Every buffer belongs to the caller. A function that fills one takes its
+//! capacity and a pointer where it stores the length of the output; when the
+//! output does not fit, it stores the length needed and returns
+//! `G2_STATUS_BUFFER_TOO_SMALL`, so a first call with a null buffer and a
+//! capacity of 0 sizes the buffer.
+
+use g2::bytes::Bytes;
+use g2::registry::{self, RegisteredCipher, RegistryError};
+
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::panic::{self, UnwindSafe};
+use std::sync::OnceLock;
+
+/// result of every function of the interface
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum G2Status {
+    Ok = 0,
+    /// no cipher of that name
+    UnknownCipher = 1,
+    /// the output is longer than the buffer, its length is stored
+    BufferTooSmall = 2,
+    /// the key is not of the size of the cipher, or not usable with it
+    InvalidKey = 3,
+    /// the ciphertext is malformed or not under this key
+    DecryptFailed = 4,
+    /// a required pointer is null
+    NullPointer = 5,
+    /// a bug of the library, please report it
+    Internal = 6,
+    /// the message runs past what the key can encrypt
+    EncryptFailed = 7,
+}
+
+impl From<RegistryError> for G2Status {
+    fn from(e: RegistryError) -> Self {
+        match e {
+            RegistryError::KeyLength { .. } | RegistryError::InvalidKey(_) => G2Status::InvalidKey,
+            RegistryError::Encrypt(_) => G2Status::EncryptFailed,
+            RegistryError::Decrypt(_) => G2Status::DecryptFailed,
+        }
+    }
+}
+
+/// runs `f`, a panic must not unwind into C
+fn guard(f: impl FnOnce() -> Result<(), G2Status> + UnwindSafe) -> G2Status {
+    match panic::catch_unwind(f) {
+        Ok(Ok(())) => G2Status::Ok,
+        Ok(Err(status)) => status,
+        Err(_) => G2Status::Internal,
+    }
+}
+
+/// # Safety
+/// `name` is null or a NUL-terminated string
+unsafe fn cipher(name: *const c_char) -> Result<&'static RegisteredCipher, G2Status> {
+    if name.is_null() {
+        return Err(G2Status::NullPointer);
+    }
+    let name = unsafe { CStr::from_ptr(name) };
+    name.to_str()
+        .ok()
+        .and_then(registry::find)
+        .ok_or(G2Status::UnknownCipher)
+}
+
+/// # Safety
+/// `ptr` is null or points to `len` readable bytes
+unsafe fn input(ptr: *const u8, len: usize) -> Result<Bytes, G2Status> {
+    match (ptr.is_null(), len) {
+        (_, 0) => Ok(Bytes::new()),
+        (true, _) => Err(G2Status::NullPointer),
+        (false, _) => Ok(Bytes::copy_from_slice(unsafe { std::slice::from_raw_parts(ptr, len) })),
+    }
+}
+
+/// # Safety
+/// `out` is null or points to `capacity` writable bytes, `out_len` is null
+/// or points to a writable `size_t`
+unsafe fn output(bytes: &[u8], out: *mut u8, capacity: usize, out_len: *mut usize) -> Result<(), G2Status> {
+    if out_len.is_null() {
+        return Err(G2Status::NullPointer);
+    }
+    unsafe { *out_len = bytes.len() };
+    if bytes.len() > capacity {
+        return Err(G2Status::BufferTooSmall);
+    }
+    if !bytes.is_empty() {
+        if out.is_null() {
+            return Err(G2Status::NullPointer);
+        }
+        unsafe { std::ptr::copy_nonoverlapping(bytes.as_ptr(), out, bytes.len()) };
+    }
+    Ok(())
+}
+
+/// number of ciphers, the indices of [`g2_cipher_name`]
+#[unsafe(no_mangle)]
+pub extern "C" fn g2_cipher_count() -> usize {
+    registry::CIPHERS.len()
+}
+
+/// name of the cipher at `index`, a static string, or null past the last one
+#[unsafe(no_mangle)]
+pub extern "C" fn g2_cipher_name(index: usize) -> *const c_char {
+    static NAMES: OnceLock<Vec<CString>> = OnceLock::new();
+    NAMES
+        .get_or_init(|| registry::CIPHERS.iter().map(|c| CString::new(c.name).unwrap()).collect())
+        .get(index)
+        .map_or(std::ptr::null(), |name| name.as_ptr())
+}
+
+/// stores the key size of `cipher` in `key_size`
+///
+/// # Safety
+/// `cipher` is a NUL-terminated string, `key_size` points to a writable `size_t`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn g2_key_size(cipher: *const c_char, key_size: *mut usize) -> G2Status {
+    guard(|| {
+        let cipher = unsafe { self::cipher(cipher) }?;
+        if key_size.is_null() {
+            return Err(G2Status::NullPointer);
+        }
+        unsafe { *key_size = cipher.key_size() };
+        Ok(())
+    })
+}
+
+/// writes a fresh key of `cipher` to `key`, drawn from the thread-local
+/// generator seeded by the operating system
+///
+/// # Safety
+/// `cipher` is a NUL-terminated string, `key` points to `capacity` writable
+/// bytes and `key_len` to a writable `size_t`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn g2_keygen(cipher: *const c_char, key: *mut u8, capacity: usize, key_len: *mut usize) -> G2Status {
+    guard(|| {
+        let cipher = unsafe { self::cipher(cipher) }?;
+        unsafe { output(&cipher.gen_keys(), key, capacity, key_len) }
+    })
+}
+
+/// encrypts the `message_len` bytes of `message` under `key` into `out`
+///
+/// # Safety
+/// `cipher` is a NUL-terminated string, `key` and `message` point to
+/// `key_len` and `message_len` readable bytes, `out` to `capacity` writable
+/// bytes and `out_len` to a writable `size_t`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn g2_encrypt(
+    cipher: *const c_char,
+    key: *const u8,
+    key_len: usize,
+    message: *const u8,
+    message_len: usize,
+    out: *mut u8,
+    capacity: usize,
+    out_len: *mut usize,
+) -> G2Status {
+    guard(|| {
+        let cipher = unsafe { self::cipher(cipher) }?;
+        let (key, message) = unsafe { (input(key, key_len)?, input(message, message_len)?) };
+        let ciphertext = cipher.encrypt(&key, message)?;
+        unsafe { output(&ciphertext, out, capacity, out_len) }
+    })
+}
+
+/// decrypts the `ciphertext_len` bytes of `ciphertext` under `key` into `out`
+///
+/// # Safety
+/// as for [`g2_encrypt`]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn g2_decrypt(
+    cipher: *const c_char,
+    key: *const u8,
+    key_len: usize,
+    ciphertext: *const u8,
+    ciphertext_len: usize,
+    out: *mut u8,
+    capacity: usize,
+    out_len: *mut usize,
+) -> G2Status {
+    guard(|| {
+        let cipher = unsafe { self::cipher(cipher) }?;
+        let (key, ciphertext) = unsafe { (input(key, key_len)?, input(ciphertext, ciphertext_len)?) };
+        let message = cipher.decrypt(&key, ciphertext)?;
+        unsafe { output(&message, out, capacity, out_len) }
+    })
+}
+
+impl G2Status {
+    const ALL: [G2Status; 8] = [
+        G2Status::Ok,
+        G2Status::UnknownCipher,
+        G2Status::BufferTooSmall,
+        G2Status::InvalidKey,
+        G2Status::DecryptFailed,
+        G2Status::NullPointer,
+        G2Status::Internal,
+        G2Status::EncryptFailed,
+    ];
+
+    fn message(self) -> &'static CStr {
+        match self {
+            G2Status::Ok => c"ok",
+            G2Status::UnknownCipher => c"unknown cipher",
+            G2Status::BufferTooSmall => c"output buffer too small",
+            G2Status::InvalidKey => c"invalid key",
+            G2Status::DecryptFailed => c"decryption failed",
+            G2Status::NullPointer => c"null pointer",
+            G2Status::Internal => c"internal error",
+            G2Status::EncryptFailed => c"encryption failed",
+        }
+    }
+}
+
+/// a static description of `status`, "unknown status" for a value that is no
+/// `G2Status`; it takes an int because C may pass any value for an enum
+#[unsafe(no_mangle)]
+pub extern "C" fn g2_status_message(status: c_int) -> *const c_char {
+    G2Status::ALL
+        .into_iter()
+        .find(|&known| known as c_int == status)
+        .map_or(c"unknown status", G2Status::message)
+        .as_ptr()
+}
@@ -0,0 +1,93 @@
+/* Exercises every cipher of libg2_ffi through include/g2.h; run by tests/ffi.rs. */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "g2.h"
+
+#define CHECK(call, expected)                                                        \
+    do {                                                                             \
+        G2Status status = (call);                                                    \
+        if (status != (expected)) {                                                  \
+            fprintf(stderr, "%s:%d: %s: %s\n", __FILE__, __LINE__, #call,            \
+                    g2_status_message(status));                                      \
+            exit(1);                                                                 \
+        }                                                                            \
+    } while (0)
+
+static const char MESSAGE[] = "The quick brown fox jumps over the lazy dog";
+
+static void round_trip(const char *cipher) {
+    size_t key_size, key_len, len, plain_len;
+    CHECK(g2_key_size(cipher, &key_size), G2_STATUS_OK);
+
+    /* a null buffer asks for the size */
+    CHECK(g2_keygen(cipher, NULL, 0, &key_len), G2_STATUS_BUFFER_TOO_SMALL);
+    if (key_len != key_size) {
+        fprintf(stderr, "%s: keygen needs %zu bytes, keys have %zu\n", cipher, key_len, key_size);
+        exit(1);
+    }
+    uint8_t *key = malloc(key_size);
+    CHECK(g2_keygen(cipher, key, key_size, &key_len), G2_STATUS_OK);
+
+    const uint8_t *message = (const uint8_t *)MESSAGE;
+    size_t message_len = strlen(MESSAGE);
+    CHECK(g2_encrypt(cipher, key, key_size, message, message_len, NULL, 0, &len),
+          G2_STATUS_BUFFER_TOO_SMALL);
+    uint8_t *ciphertext = malloc(len);
+    CHECK(g2_encrypt(cipher, key, key_size, message, message_len, ciphertext, len, &len),
+          G2_STATUS_OK);
+    if (len == message_len && memcmp(ciphertext, message, len) == 0) {
+        fprintf(stderr, "%s: the ciphertext is the message\n", cipher);
+        exit(1);
+    }
+
+    uint8_t plaintext[sizeof MESSAGE + 64];
+    CHECK(g2_decrypt(cipher, key, key_size, ciphertext, len, plaintext, sizeof plaintext, &plain_len),
+          G2_STATUS_OK);
+    if (plain_len != message_len || memcmp(plaintext, message, message_len) != 0) {
+        fprintf(stderr, "%s: the round trip changed the message\n", cipher);
+        exit(1);
+    }
+
+    CHECK(g2_encrypt(cipher, key, key_size - 1, message, message_len, ciphertext, len, &len),
+          G2_STATUS_INVALID_KEY);
+    CHECK(g2_decrypt(cipher, NULL, key_size, ciphertext, len, plaintext, sizeof plaintext, &plain_len),
+          G2_STATUS_NULL_POINTER);
+
+    printf("%s: %zu-byte key, %zu-byte ciphertext\n", cipher, key_size, len);
+    free(ciphertext);
+    free(key);
+}
+
+int main(void) {
+    size_t count = g2_cipher_count();
+    for (size_t i = 0; i < count; i++) {
+        round_trip(g2_cipher_name(i));
+    }
+    if (g2_cipher_name(count) != NULL) {
+        fprintf(stderr, "a cipher past the last one\n");
+        return 1;
+    }
+
+    size_t size;
+    CHECK(g2_key_size("enigma", &size), G2_STATUS_UNKNOWN_CIPHER);
+    CHECK(g2_key_size(NULL, &size), G2_STATUS_NULL_POINTER);
+
+    /* a singular Hill matrix has no inverse to decrypt with, so it is no key */
+    uint8_t key[256] = {0}, out[64];
+    size_t key_len, len;
+    CHECK(g2_key_size("hill-caesar", &key_len), G2_STATUS_OK);
+    CHECK(g2_decrypt("hill-caesar", key, key_len, (const uint8_t *)"abcdefgh", 8, out, sizeof out, &len),
+          G2_STATUS_INVALID_KEY);
+
+    /* any int has a message, not only the statuses */
+    if (strcmp(g2_status_message(-1), "unknown status") != 0) {
+        fprintf(stderr, "g2_status_message(-1): %s\n", g2_status_message(-1));
+        exit(1);
+    }
+
+    printf("%zu ciphers\n", count);
+    return 0;
+}
@@ -0,0 +1,73 @@
+use g2_ffi::*;
+use g2::registry::{self, RegistryError};
+
+use std::ffi::{c_int, CStr};
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn registry_covers_every_cipher() {
+    let names: Vec<&str> = registry::CIPHERS.iter().map(|cipher| cipher.name).collect();
+    let ids: Vec<&str> = g2::envelope::CIPHERS.iter().map(|&(_, name)| name).collect();
+    assert_eq!(names, ids);
+    let vigenere = registry::find("vigenere").unwrap();
+    assert_eq!(
+        vigenere.encrypt(&[0; 3], "Hello".into()),
+        Err(RegistryError::KeyLength { expected: 17, found: 3 })
+    );
+    assert!(registry::find("enigma").is_none());
+}
+
+#[test]
+fn buffers_are_sized_by_a_first_call() {
+    let mut len = 0;
+    let status = unsafe { g2_keygen(c"chacha20".as_ptr(), std::ptr::null_mut(), 0, &mut len) };
+    assert_eq!(status, G2Status::BufferTooSmall);
+    assert_eq!(len, 48);
+    let message = unsafe { CStr::from_ptr(g2_status_message(status as c_int)) };
+    assert_eq!(message, c"output buffer too small");
+    for unknown in [-1, 8, c_int::MAX] {
+        assert_eq!(unsafe { CStr::from_ptr(g2_status_message(unknown)) }, c"unknown status");
+    }
+    assert!(g2_cipher_name(g2_cipher_count()).is_null());
+}
+
+/// the header checked in must be the one the build script generates from src/lib.rs
+#[test]
+fn header_is_up_to_date() {
+    let generated = Path::new(env!("OUT_DIR")).join("g2.h");
+    let checked_in = Path::new(env!("CARGO_MANIFEST_DIR")).join("include/g2.h");
+    assert!(
+        std::fs::read(&generated).unwrap() == std::fs::read(&checked_in).unwrap_or_default(),
+        "include/g2.h is out of date, update it with: cp {} {}",
+        generated.display(),
+        checked_in.display()
+    );
+}
+
+/// compiles tests/c/ffi.c against the cdylib, which cargo test leaves in deps/ next to the test, and runs it
+#[test]
+fn c_program() {
+    let manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let lib_dir = std::env::current_exe().unwrap().parent().unwrap().to_path_buf();
+    let program = Path::new(env!("CARGO_TARGET_TMPDIR")).join("ffi");
+    let compiler = std::env::var("CC").unwrap_or_else(|_| "cc".into());
+    let status = Command::new(compiler)
+        .args(["-Wall", "-Wextra", "-Werror", "-std=c11"])
+        .arg("-I")
+        .arg(manifest.join("include"))
+        .arg(manifest.join("tests/c/ffi.c"))
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .args(["-lg2_ffi", "-o"])
+        .arg(&program)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let output = Command::new(&program).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("hill-caesar: 65-byte key, 48-byte ciphertext\n"), "{}", stdout);
+    assert!(stdout.ends_with("11 ciphers\n"));
+}
@@ -0,0 +1,400 @@
+//! A key agent, `g2 agent`: it holds decoded keys in memory that is wiped
+//! when they are removed, listens on a Unix domain socket only its user can
+//! open, and encrypts and decrypts for the cipher binaries by key id, so that
+//! keys stay out of command lines, shell history and `ps`.
+//!
+//! Like the [oracle](crate::server), every request is one line and gets one
+//! reply line, `OK <value>` or `ERR <reason>`:
+//!
+//! * `ADD <cipher> <base64 key>` -> `OK <key id>`
+//! * `ENCRYPT <cipher> <key id> <base64 message>` -> `OK <base64 ciphertext>`
+//! * `DECRYPT <cipher> <key id> <base64 ciphertext>` -> `OK <base64 message>`
+//! * `LIST` -> `OK <key id>:<cipher> ...`
+//! * `REMOVE <key id>` -> `OK <key id>`
+//! * `QUIT` closes the connection
+//!
+//! The cipher binaries send `encrypt` and `decrypt` with `--key-id` to the
+//! agent whose socket is named by [`SOCKET_ENV`]. Without `--key-id` or any
+//! other key, they use the one key the agent holds for their cipher, and the
+//! default key when it holds none.
+
+use crate::bytes::Bytes;
+use crate::cli::AgentCommand;
+use crate::registry::{self, RegisteredCipher};
+use crate::report::{key_id, ErrorKind, Failure, Report};
+use crate::server::Reply;
+use crate::traits::{ChallengeCipher, TRY_HARDER};
+
+use base64::prelude::*;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use zeroize::Zeroizing;
+
+/// the environment variable naming the socket of the running agent
+pub const SOCKET_ENV: &str = "G2_AGENT_SOCK";
+
+pub enum Request {
+    Add {
+        cipher: &'static RegisteredCipher,
+        key: Zeroizing<Vec<u8>>,
+    },
+    Encrypt {
+        cipher: &'static RegisteredCipher,
+        key_id: String,
+        message: Bytes,
+    },
+    Decrypt {
+        cipher: &'static RegisteredCipher,
+        key_id: String,
+        encrypted_message: Bytes,
+    },
+    List,
+    Remove {
+        key_id: String,
+    },
+    Quit,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseRequestError(String);
+
+impl fmt::Display for ParseRequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ParseRequestError {}
+
+impl FromStr for Request {
+    type Err = ParseRequestError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut words = line.split_whitespace();
+        let verb = words.next().unwrap_or_default().to_ascii_uppercase();
+        let arguments: Vec<&str> = words.collect();
+        let cipher = |name: &str| registry::find(name).ok_or_else(|| ParseRequestError(format!("no cipher named {:?}", name)));
+        let base64 = |value: &str| BASE64_STANDARD.decode(value).map_err(|e| ParseRequestError(e.to_string()));
+        match (verb.as_str(), arguments.as_slice()) {
+            ("ADD", [name, key]) => Ok(Request::Add {
+                cipher: cipher(name)?,
+                key: Zeroizing::new(base64(key)?),
+            }),
+            ("ENCRYPT", [name, key_id, message]) => Ok(Request::Encrypt {
+                cipher: cipher(name)?,
+                key_id: key_id.to_string(),
+                message: base64(message)?.into(),
+            }),
+            ("DECRYPT", [name, key_id, encrypted_message]) => Ok(Request::Decrypt {
+                cipher: cipher(name)?,
+                key_id: key_id.to_string(),
+                encrypted_message: base64(encrypted_message)?.into(),
+            }),
+            ("LIST", []) => Ok(Request::List),
+            ("REMOVE", [key_id]) => Ok(Request::Remove { key_id: key_id.to_string() }),
+            ("QUIT", []) => Ok(Request::Quit),
+            ("ADD" | "ENCRYPT" | "DECRYPT" | "LIST" | "REMOVE" | "QUIT", _) => {
+                Err(ParseRequestError(format!("wrong number of arguments to {}", verb)))
+            }
+            _ => Err(ParseRequestError(format!("unknown request '{}'", verb))),
+        }
+    }
+}
+
+struct StoredKey {
+    cipher: &'static RegisteredCipher,
+    key: Zeroizing<Vec<u8>>,
+}
+
+/// the keys of the agent by id
+#[derive(Default)]
+pub struct Agent {
+    keys: Mutex<BTreeMap<String, StoredKey>>,
+}
+
+impl Agent {
+    pub fn new() -> Self {
+        Agent::default()
+    }
+
+    /// runs `f` on the key `key_id` of `cipher`
+    fn with_key<T>(
+        &self,
+        cipher: &RegisteredCipher,
+        key_id: &str,
+        f: impl FnOnce(&[u8]) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let keys = self.keys.lock().unwrap_or_else(|e| e.into_inner());
+        match keys.get(key_id) {
+            None => Err(format!("no key {}", key_id)),
+            Some(stored) if stored.cipher.name != cipher.name => {
+                Err(format!("key {} is a {} key", key_id, stored.cipher.name))
+            }
+            Some(stored) => f(&stored.key),
+        }
+    }
+
+    pub fn answer(&self, request: Request) -> Reply {
+        let result = match request {
+            Request::Add { cipher, key } => {
+                if key.len() == cipher.key_size() {
+                    let id = key_id(Some(&key));
+                    let mut keys = self.keys.lock().unwrap_or_else(|e| e.into_inner());
+                    keys.insert(id.clone(), StoredKey { cipher, key });
+                    Ok(id)
+                } else {
+                    Err(format!("expected a {}-byte key, got {} bytes", cipher.key_size(), key.len()))
+                }
+            }
+            Request::Encrypt { cipher, key_id, message } => self.with_key(cipher, &key_id, |key| {
+                let ciphertext = cipher.encrypt(key, message).map_err(|e| e.to_string())?;
+                Ok(BASE64_STANDARD.encode(ciphertext))
+            }),
+            Request::Decrypt {
+                cipher,
+                key_id,
+                encrypted_message,
+            } => self.with_key(cipher, &key_id, |key| {
+                let message = cipher.decrypt(key, encrypted_message).map_err(|e| e.to_string())?;
+                Ok(BASE64_STANDARD.encode(message))
+            }),
+            Request::List => {
+                let keys = self.keys.lock().unwrap_or_else(|e| e.into_inner());
+                let ids: Vec<String> = keys.iter().map(|(id, stored)| format!("{}:{}", id, stored.cipher.name)).collect();
+                Ok(ids.join(" "))
+            }
+            Request::Remove { key_id } => {
+                let mut keys = self.keys.lock().unwrap_or_else(|e| e.into_inner());
+                // the key is wiped as it is dropped
+                match keys.remove(&key_id) {
+                    Some(_) => Ok(key_id),
+                    None => Err(format!("no key {}", key_id)),
+                }
+            }
+            Request::Quit => Ok(String::from("bye")),
+        };
+        match result {
+            Ok(value) => Reply::Ok(value),
+            Err(reason) => Reply::Err(reason),
+        }
+    }
+
+    /// answers the requests of one client; lines are read a byte at a time so
+    /// that no buffer but `line`, which is wiped, holds the keys they carry
+    fn handle(&self, mut stream: UnixStream) -> io::Result<()> {
+        let mut line = Zeroizing::new(Vec::new());
+        while read_line(&mut stream, &mut line)? {
+            let Ok(text) = std::str::from_utf8(&line) else {
+                writeln!(stream, "{}", Reply::Err("request is not UTF-8".into()))?;
+                continue;
+            };
+            if text.trim().is_empty() {
+                continue;
+            }
+            let reply = match text.parse::<Request>() {
+                Ok(Request::Quit) => break,
+                Ok(request) => self.answer(request),
+                Err(e) => Reply::Err(e.to_string()),
+            };
+            writeln!(stream, "{}", reply)?;
+            stream.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// the next line of `stream` into `line`, false at the end of the stream;
+/// `line` grows into fresh buffers so that the old ones are wiped, not freed
+fn read_line(stream: &mut impl Read, line: &mut Zeroizing<Vec<u8>>) -> io::Result<bool> {
+    line.clear();
+    let mut byte = [0u8];
+    loop {
+        if stream.read(&mut byte)? == 0 {
+            return Ok(!line.is_empty());
+        }
+        match byte[0] {
+            b'\n' => return Ok(true),
+            b'\r' => {}
+            b => {
+                if line.len() == line.capacity() {
+                    let mut grown = Zeroizing::new(Vec::with_capacity(2 * line.capacity().max(64)));
+                    grown.extend_from_slice(line);
+                    *line = grown;
+                }
+                line.push(b);
+            }
+        }
+    }
+}
+
+/// serves `agent` to the connections accepted by `listener` until it fails
+pub fn serve_unix(listener: UnixListener, agent: Arc<Agent>) -> io::Result<()> {
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let agent = Arc::clone(&agent);
+        thread::spawn(move || agent.handle(stream));
+    }
+    Ok(())
+}
+
+/// binds `path`, replacing the socket of an agent that is gone, and serves a new agent on it
+pub fn serve(path: &Path) -> io::Result<()> {
+    if std::fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+        if UnixStream::connect(path).is_ok() {
+            return Err(io::Error::new(io::ErrorKind::AddrInUse, format!("an agent already listens on {}", path.display())));
+        }
+        std::fs::remove_file(path)?;
+    }
+    serve_unix(bind_private(path)?, Arc::new(Agent::new()))
+}
+
+/// binds a socket at `path` that only its user can open: it is bound in a
+/// directory only that user can enter, made private there and then moved to
+/// `path`, so no other user can connect before its permissions are set
+fn bind_private(path: &Path) -> io::Result<UnixListener> {
+    if std::fs::symlink_metadata(path).is_ok() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists", path.display())));
+    }
+    let parent = path.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let dir = parent.join(format!(".g2-agent-{}", process::id()));
+    std::fs::DirBuilder::new().mode(0o700).create(&dir)?;
+    let socket = dir.join("socket");
+    let listener = UnixListener::bind(&socket).and_then(|listener| {
+        std::fs::set_permissions(&socket, std::fs::Permissions::from_mode(0o600))?;
+        std::fs::rename(&socket, path)?;
+        Ok(listener)
+    });
+    let _ = std::fs::remove_file(&socket);
+    std::fs::remove_dir(&dir)?;
+    listener
+}
+
+/// sends one request to the agent on `socket`, the value of an `OK` reply or the reason of an `ERR`
+pub fn call(socket: &Path, request: &str) -> Result<String, Failure> {
+    let failure = |message: String| Failure::new(ErrorKind::Agent, message);
+    let io_failure = |e: io::Error| failure(format!("{}: {}", socket.display(), e));
+    let mut stream = UnixStream::connect(socket).map_err(io_failure)?;
+    // written straight to the socket, a request with a key is not copied on the way
+    writeln!(stream, "{}", request).map_err(io_failure)?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply).map_err(io_failure)?;
+    let reply = reply.trim_end();
+    match (reply.strip_prefix("OK"), reply.strip_prefix("ERR ")) {
+        (Some(value), _) => Ok(value.trim_start().to_string()),
+        (_, Some(reason)) => Err(failure(reason.to_string())),
+        _ => Err(failure(format!("unexpected reply {:?}", reply))),
+    }
+}
+
+/// the socket named by [`SOCKET_ENV`]
+fn socket() -> Result<PathBuf, Failure> {
+    std::env::var_os(SOCKET_ENV).map(PathBuf::from).ok_or_else(|| {
+        Failure::new(ErrorKind::Agent, format!("--key-id needs a running agent, set {} to its socket", SOCKET_ENV))
+    })
+}
+
+/// the key id to use: `key_id` itself, or, when neither it nor another key is
+/// given and [`SOCKET_ENV`] is set, the one key the agent holds for `C`; none
+/// means the default key, and several keys of `C` need `--key-id` to choose
+pub fn held_key_id<C: ChallengeCipher>(key_id: Option<String>, other_key: bool) -> Result<Option<String>, Failure> {
+    if key_id.is_some() || other_key || std::env::var_os(SOCKET_ENV).is_none() {
+        return Ok(key_id);
+    }
+    let list = call(&socket()?, "LIST")?;
+    let held: Vec<&str> = list
+        .split_whitespace()
+        .filter_map(|entry| entry.split_once(':'))
+        .filter(|(_, name)| *name == cipher::<C>().name)
+        .map(|(id, _)| id)
+        .collect();
+    match held[..] {
+        [] => Ok(None),
+        [id] => Ok(Some(id.to_string())),
+        _ => Err(Failure::new(
+            ErrorKind::Agent,
+            format!("the agent holds {} {} keys, choose one with --key-id", held.len(), cipher::<C>().name),
+        )),
+    }
+}
+
+fn cipher<C: ChallengeCipher>() -> &'static RegisteredCipher {
+    registry::find(C::NAME).expect("every cipher binary is registered")
+}
+
+/// encrypt with `--key-id`
+pub fn encrypt<C: ChallengeCipher>(key_id: String, message: Bytes) -> Report {
+    let report = Report::new(C::NAME, "encrypt");
+    let request = format!("ENCRYPT {} {} {}", cipher::<C>().name, key_id, BASE64_STANDARD.encode(message));
+    match socket().and_then(|socket| call(&socket, &request)) {
+        Ok(ciphertext) => Report {
+            key_id: Some(key_id),
+            ciphertext: Some(ciphertext),
+            ..report
+        },
+        Err(failure) => report.failed(failure),
+    }
+}
+
+/// decrypt with `--key-id`; the agent never holds the challenge key, so there is nothing to refuse
+pub fn decrypt<C: ChallengeCipher>(key_id: String, encrypted_message: Bytes) -> Report {
+    let mut report = Report::new(C::NAME, "decrypt");
+    report.ciphertext = Some(BASE64_STANDARD.encode(&encrypted_message));
+    let request = format!("DECRYPT {} {} {}", cipher::<C>().name, key_id, BASE64_STANDARD.encode(encrypted_message));
+    report.key_id = Some(key_id);
+    let message = socket()
+        .and_then(|socket| call(&socket, &request))
+        .and_then(|message| BASE64_STANDARD.decode(message).map_err(|e| Failure::new(ErrorKind::Agent, e)));
+    match message.map(String::from_utf8) {
+        Ok(Ok(s)) if crate::cli::verified_message(&s).is_ok() => Report {
+            plaintext: Some(s),
+            ..report
+        },
+        Ok(_) => report.failed(Failure::new(ErrorKind::TryHarder, TRY_HARDER)),
+        Err(failure) => report.failed(failure),
+    }
+}
+
+/// the `g2` binary: the agent itself and the commands that manage its keys
+pub fn main() {
+    let cli = crate::cli::agent_cli();
+    let Some(socket) = cli.socket.or_else(|| std::env::var_os(SOCKET_ENV).map(PathBuf::from)) else {
+        eprintln!("error: no agent socket, pass --socket or set {}", SOCKET_ENV);
+        process::exit(1);
+    };
+    let result = match cli.command {
+        AgentCommand::Agent => serve(&socket).map_err(|e| e.to_string()),
+        AgentCommand::Add { cipher, key_file } => add(&socket, &cipher, key_file.as_deref()),
+        AgentCommand::List => call(&socket, "LIST")
+            .map(|list| list.split_whitespace().for_each(|entry| println!("{}", entry.replacen(':', " ", 1))))
+            .map_err(|e| e.to_string()),
+        AgentCommand::Remove { key_id } => {
+            call(&socket, &format!("REMOVE {}", key_id)).map(drop).map_err(|e| e.to_string())
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
+
+/// `g2 add`: the base64 key from `key_file` or standard input, never from the command line
+fn add(socket: &Path, cipher: &str, key_file: Option<&Path>) -> Result<(), String> {
+    let mut key = Zeroizing::new(String::new());
+    match key_file {
+        Some(path) => std::fs::File::open(path).and_then(|mut file| file.read_to_string(&mut key)),
+        None => io::stdin().read_to_string(&mut key),
+    }
+    .map_err(|e| e.to_string())?;
+    let request = Zeroizing::new(format!("ADD {} {}", cipher, key.trim()));
+    let id = call(socket, &request).map_err(|e| e.to_string())?;
+    println!("{}", id);
+    Ok(())
+}
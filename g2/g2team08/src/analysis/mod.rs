@@ -0,0 +1,69 @@
+//! Frequency analysis of classical ciphertexts: English n-gram scoring,
+//! chi-squared, index of coincidence, Kasiski and Friedman tests, and
+//! solvers built on top of them.
+
+pub mod solvers;
+pub mod stats;
+
+use crate::classical::vigenere::MAX_PERIOD;
+use solvers::Candidate;
+
+use base64::prelude::*;
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Solver {
+    Caesar,
+    Vigenere,
+    Substitution,
+}
+
+fn preview(plaintext: &[u8]) -> String {
+    String::from_utf8_lossy(&plaintext[..plaintext.len().min(60)]).into_owned()
+}
+
+fn print_candidates(name: &str, candidates: &[Candidate], top: usize) {
+    println!("{}:", name);
+    for (rank, candidate) in candidates.iter().take(top).enumerate() {
+        println!(
+            "  {}. score {:.3} key {} {}",
+            rank + 1,
+            candidate.average_score(),
+            BASE64_STANDARD.encode(&candidate.key),
+            preview(&candidate.plaintext)
+        );
+    }
+}
+
+/// prints the statistics of `ciphertext` and the `top` candidates of each solver
+pub fn run(ciphertext: &[u8], solvers: &[Solver], top: usize) {
+    let symbols = stats::symbols(ciphertext);
+    let model = stats::english();
+    println!("symbols: {}", symbols.len());
+    println!(
+        "index of coincidence: {:.4} (English {:.4}, random {:.4})",
+        stats::index_of_coincidence(&symbols),
+        model.coincidence,
+        1.0 / crate::classical::N as f64
+    );
+    println!("chi-squared against English letters: {:.1}", stats::chi_squared(ciphertext));
+    println!("Friedman period estimate: {:.2}", stats::friedman(&symbols));
+    let kasiski: Vec<String> = stats::kasiski(&symbols, MAX_PERIOD)
+        .iter()
+        .take(5)
+        .map(|(period, count)| format!("{} ({})", period, count))
+        .collect();
+    println!("Kasiski periods: {}", kasiski.join(", "));
+
+    for solver in solvers {
+        match solver {
+            Solver::Caesar => print_candidates("caesar", &solvers::solve_caesar(ciphertext), top),
+            Solver::Vigenere => print_candidates("vigenere", &solvers::solve_vigenere(ciphertext, top.max(3)), top),
+            Solver::Substitution => print_candidates(
+                "substitution",
+                &solvers::solve_substitution(ciphertext, top.max(3), 100_000, rand::rng()),
+                top,
+            ),
+        }
+    }
+}
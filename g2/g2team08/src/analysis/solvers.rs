@@ -0,0 +1,172 @@
+//! Automatic solvers for the Caesar, Vigenère and substitution ciphers of
+//! [`crate::classical`], ranking candidate keys by [`LanguageModel::score`].
+//!
+//! [`LanguageModel::score`]: super::stats::LanguageModel::score
+
+use super::stats::{english, index_of_coincidence, symbols};
+use crate::classical::vigenere::{self, MAX_PERIOD};
+use crate::classical::{index, invert_permutation, map_symbols, random_permutation, symbol, N};
+
+use rand::Rng;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    /// key in the format of the cipher, ready to be base64-encoded
+    pub key: Vec<u8>,
+    /// log-likelihood of the plaintext as English
+    pub score: f64,
+    pub plaintext: Vec<u8>,
+}
+
+impl Candidate {
+    fn new(key: Vec<u8>, plaintext: Vec<u8>) -> Self {
+        Candidate {
+            key,
+            score: english().score(&plaintext),
+            plaintext,
+        }
+    }
+
+    /// score per alphabet symbol, comparable across texts
+    pub fn average_score(&self) -> f64 {
+        self.score / symbols(&self.plaintext).len().max(1) as f64
+    }
+}
+
+fn best_first(mut candidates: Vec<Candidate>) -> Vec<Candidate> {
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    // equal plaintexts from equivalent keys, keep the first (shortest) key
+    let mut seen = vec![];
+    candidates.retain(|c| {
+        let new = !seen.contains(&c.plaintext);
+        if new {
+            seen.push(c.plaintext.clone());
+        }
+        new
+    });
+    candidates
+}
+
+/// every shift of the alphabet
+pub fn solve_caesar(ciphertext: &[u8]) -> Vec<Candidate> {
+    best_first(
+        (0..N)
+            .map(|k| Candidate::new(vec![k as u8], map_symbols(ciphertext, |y| y + N - k)))
+            .collect(),
+    )
+}
+
+/// the shift that makes `column` look most like English symbols
+fn best_shift(column: &[usize]) -> usize {
+    let model = english();
+    (0..N)
+        .max_by(|&a, &b| {
+            let likelihood = |k: usize| -> f64 { column.iter().map(|&y| model.symbol_log(symbol(y + N - k))).sum() };
+            likelihood(a).total_cmp(&likelihood(b))
+        })
+        .unwrap_or_default()
+}
+
+/// mean index of coincidence of the columns of `symbols` for `period`
+pub fn column_coincidence(symbols: &[usize], period: usize) -> f64 {
+    let total: f64 = (0..period)
+        .map(|i| {
+            let column: Vec<usize> = symbols.iter().skip(i).step_by(period).copied().collect();
+            index_of_coincidence(&column)
+        })
+        .sum();
+    total / period as f64
+}
+
+/// solves every column as a Caesar shift for the `periods` most likely periods,
+/// judged by the index of coincidence of their columns
+pub fn solve_vigenere(ciphertext: &[u8], periods: usize) -> Vec<Candidate> {
+    let ct = symbols(ciphertext);
+    let mut ranked: Vec<usize> = (1..=MAX_PERIOD.min(ct.len().max(1))).collect();
+    ranked.sort_by(|&a, &b| column_coincidence(&ct, b).total_cmp(&column_coincidence(&ct, a)));
+    ranked.truncate(periods);
+    ranked.sort();
+
+    let candidates = ranked
+        .into_iter()
+        .map(|period| {
+            let shifts: Vec<usize> = (0..period)
+                .map(|i| {
+                    let column: Vec<usize> = ct.iter().skip(i).step_by(period).copied().collect();
+                    best_shift(&column)
+                })
+                .collect();
+            let mut key = vec![(period - 1) as u8];
+            key.extend(shifts.iter().map(|&k| k as u8));
+            key.resize(1 + MAX_PERIOD, 0);
+            let inverse: Vec<usize> = shifts.iter().map(|k| N - k).collect();
+            Candidate::new(key, vigenere::apply(ciphertext, &inverse))
+        })
+        .collect();
+    best_first(candidates)
+}
+
+/// decryption table `d` applied to the ciphertext: symbol y becomes d[y]
+fn substitute(ciphertext: &[u8], decryption: &[u8]) -> Vec<u8> {
+    map_symbols(ciphertext, |y| decryption[y] as usize)
+}
+
+/// the candidate of decryption table `d`, whose cipher key maps plaintext to
+/// ciphertext symbols and so is the inverse of `d`
+fn substitution_candidate(ciphertext: &[u8], decryption: &[u8]) -> Candidate {
+    let key = invert_permutation(decryption).expect("decryption tables are permutations");
+    Candidate::new(key, substitute(ciphertext, decryption))
+}
+
+/// simulated annealing over decryption tables: swap two entries, keep the swap
+/// when the score improves and sometimes when it does not, less and less often
+/// over `iterations` steps; started from the table that matches symbol
+/// frequencies, then `restarts` more times from random tables, each run
+/// giving the best table it went through
+pub fn solve_substitution(ciphertext: &[u8], restarts: usize, iterations: usize, mut rng: impl Rng) -> Vec<Candidate> {
+    const TEMPERATURE: f64 = 8.0;
+    let model = english();
+    let ct = symbols(ciphertext);
+    let mut counts = [0usize; N];
+    for &y in &ct {
+        counts[y] += 1;
+    }
+    let mut by_frequency: Vec<usize> = (0..N).collect();
+    by_frequency.sort_by(|&a, &b| counts[b].cmp(&counts[a]).then(a.cmp(&b)));
+    let mut initial = vec![0u8; N];
+    for (&y, &x) in by_frequency.iter().zip(&model.symbols_by_frequency()) {
+        initial[y] = index(x).unwrap() as u8;
+    }
+    // swapping two entries only matters when one of them occurs in the ciphertext
+    let present: Vec<usize> = (0..N).filter(|&y| counts[y] > 0).collect();
+    if present.is_empty() {
+        return vec![substitution_candidate(ciphertext, &initial)];
+    }
+
+    let mut candidates: Vec<Candidate> = vec![];
+    for restart in 0..=restarts {
+        let mut decryption = if restart == 0 { initial.clone() } else { random_permutation(&mut rng, N) };
+        let mut current = model.score(&substitute(ciphertext, &decryption));
+        let (mut best_table, mut best) = (decryption.clone(), current);
+        for step in 0..iterations {
+            let (a, b) = (present[rng.random_range(0..present.len())], rng.random_range(0..N));
+            if a == b {
+                continue;
+            }
+            decryption.swap(a, b);
+            let score = model.score(&substitute(ciphertext, &decryption));
+            let temperature = TEMPERATURE * (1.0 - step as f64 / iterations as f64);
+            if score >= current || rng.random::<f64>() < ((score - current) / temperature).exp() {
+                current = score;
+                if score > best {
+                    best = score;
+                    best_table.clone_from(&decryption);
+                }
+            } else {
+                decryption.swap(a, b);
+            }
+        }
+        candidates.push(substitution_candidate(ciphertext, &best_table));
+    }
+    best_first(candidates)
+}
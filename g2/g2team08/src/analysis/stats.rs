@@ -0,0 +1,176 @@
+//! Statistics of English text over the message alphabet.
+
+use crate::classical::{index, N};
+use crate::cli::ALPHABET;
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+const CORPUS: &str = include_str!("corpus.txt");
+const TRIGRAMS: usize = 26 * 26 * 26;
+
+/// symbol log-probabilities and letter trigram statistics learned from `corpus.txt`
+pub struct LanguageModel {
+    symbol_log: [f64; 256],
+    /// log P(c | a b) - log P(c) for the letters a, b, c
+    trigram_log: Vec<f64>,
+    letter_frequencies: [f64; 26],
+    /// probability that two random symbols of English text are equal
+    pub coincidence: f64,
+}
+
+fn letter(b: u8) -> Option<usize> {
+    b.is_ascii_alphabetic().then(|| (b.to_ascii_lowercase() - b'a') as usize)
+}
+
+fn trigram_index(a: usize, b: usize, c: usize) -> usize {
+    (a * 26 + b) * 26 + c
+}
+
+impl LanguageModel {
+    fn train(corpus: &str) -> Self {
+        let text: Vec<u8> = corpus.bytes().filter(|&b| index(b).is_some()).collect();
+
+        let mut symbol_counts = [0usize; 256];
+        for &b in &text {
+            symbol_counts[b as usize] += 1;
+        }
+        // add-half smoothing, bytes outside the alphabet are passed through and nearly never seen
+        let total = text.len() as f64 + 0.5 * N as f64;
+        let mut symbol_log = [(1e-6f64).ln(); 256];
+        let mut coincidence = 0.0;
+        for &b in ALPHABET {
+            let p = (symbol_counts[b as usize] as f64 + 0.5) / total;
+            symbol_log[b as usize] = p.ln();
+            coincidence += p * p;
+        }
+
+        let letters: Vec<usize> = text.iter().filter_map(|&b| letter(b)).collect();
+        let mut letter_counts = [0usize; 26];
+        for &l in &letters {
+            letter_counts[l] += 1;
+        }
+        let letter_frequencies = letter_counts.map(|c| c as f64 / letters.len() as f64);
+
+        // how much more likely a letter is after the two letters before it,
+        // smoothed towards its plain frequency
+        let mut trigram_counts = vec![0usize; TRIGRAMS];
+        let mut bigram_counts = vec![0usize; 26 * 26];
+        for w in letters.windows(3) {
+            trigram_counts[trigram_index(w[0], w[1], w[2])] += 1;
+            bigram_counts[w[0] * 26 + w[1]] += 1;
+        }
+        const SMOOTHING: f64 = 2.0;
+        let mut trigram_log = vec![0.0; TRIGRAMS];
+        for (i, bonus) in trigram_log.iter_mut().enumerate() {
+            let p = (letter_frequencies[i % 26]).max(1e-4);
+            let conditional = (trigram_counts[i] as f64 + SMOOTHING * p) / (bigram_counts[i / 26] as f64 + SMOOTHING);
+            *bonus = (conditional / p).ln();
+        }
+
+        LanguageModel {
+            symbol_log,
+            trigram_log,
+            letter_frequencies,
+            coincidence,
+        }
+    }
+
+    pub fn symbol_log(&self, b: u8) -> f64 {
+        self.symbol_log[b as usize]
+    }
+
+    /// expected share of each letter, ignoring case
+    pub fn letter_frequencies(&self) -> &[f64; 26] {
+        &self.letter_frequencies
+    }
+
+    /// alphabet symbols from most to least frequent
+    pub fn symbols_by_frequency(&self) -> Vec<u8> {
+        let mut symbols = ALPHABET.to_vec();
+        symbols.sort_by(|&a, &b| self.symbol_log(b).total_cmp(&self.symbol_log(a)));
+        symbols
+    }
+
+    /// log-likelihood of `text`: every symbol on its own, corrected for each
+    /// letter by how likely it is after the two letters before it, case ignored
+    pub fn score(&self, text: &[u8]) -> f64 {
+        let symbols: f64 = text.iter().map(|&b| self.symbol_log(b)).sum();
+        let letters: Vec<usize> = text.iter().filter_map(|&b| letter(b)).collect();
+        let trigrams: f64 = letters
+            .windows(3)
+            .map(|w| self.trigram_log[trigram_index(w[0], w[1], w[2])])
+            .sum();
+        symbols + trigrams
+    }
+}
+
+pub fn english() -> &'static LanguageModel {
+    static MODEL: OnceLock<LanguageModel> = OnceLock::new();
+    MODEL.get_or_init(|| LanguageModel::train(CORPUS))
+}
+
+/// alphabet positions of the symbols of `text`, other bytes are dropped
+pub fn symbols(text: &[u8]) -> Vec<usize> {
+    text.iter().filter_map(|&b| index(b)).collect()
+}
+
+/// chi-squared distance of the letter counts of `text` from English, case ignored
+pub fn chi_squared(text: &[u8]) -> f64 {
+    let mut counts = [0usize; 26];
+    for l in text.iter().filter_map(|&b| letter(b)) {
+        counts[l] += 1;
+    }
+    let total: usize = counts.iter().sum();
+    counts
+        .iter()
+        .zip(english().letter_frequencies())
+        .map(|(&observed, &p)| {
+            let expected = (p * total as f64).max(f64::MIN_POSITIVE);
+            (observed as f64 - expected).powi(2) / expected
+        })
+        .sum()
+}
+
+/// probability that two symbols drawn from `symbols` without replacement are equal
+pub fn index_of_coincidence(symbols: &[usize]) -> f64 {
+    if symbols.len() < 2 {
+        return 0.0;
+    }
+    let mut counts = [0usize; N];
+    for &s in symbols {
+        counts[s] += 1;
+    }
+    let pairs: usize = counts.iter().map(|&c| c * c.saturating_sub(1)).sum();
+    pairs as f64 / (symbols.len() * (symbols.len() - 1)) as f64
+}
+
+/// Friedman's estimate of the Vigenère period from the index of coincidence
+pub fn friedman(symbols: &[usize]) -> f64 {
+    let n = symbols.len() as f64;
+    let (kp, kr) = (english().coincidence, 1.0 / N as f64);
+    let ko = index_of_coincidence(symbols);
+    let denominator = (n - 1.0) * ko - kr * n + kp;
+    if denominator <= 0.0 {
+        return f64::INFINITY;
+    }
+    (kp - kr) * n / denominator
+}
+
+/// Kasiski examination: how many distances between repeated trigrams each
+/// period from 2 to `max_period` divides, most supported periods first
+pub fn kasiski(symbols: &[usize], max_period: usize) -> Vec<(usize, usize)> {
+    let mut last_seen: HashMap<&[usize], usize> = HashMap::new();
+    let mut distances = vec![];
+    for (i, w) in symbols.windows(3).enumerate() {
+        if let Some(previous) = last_seen.insert(w, i) {
+            distances.push(i - previous);
+        }
+    }
+    let mut periods: Vec<(usize, usize)> = (2..=max_period)
+        .map(|p| (p, distances.iter().filter(|&&d| d % p == 0).count()))
+        .filter(|&(_, count)| count > 0)
+        .collect();
+    periods.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    periods
+}
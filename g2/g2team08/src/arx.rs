@@ -0,0 +1,69 @@
+//! Add-rotate-xor round framework shared by the ChaCha and Salsa20 stream ciphers.
+//!
+//! Both ciphers fill a 4x4 matrix of words with constants, key, nonce and
+//! counter, scramble a copy of it with double rounds, add the input back and
+//! serialize the words little-endian into a 64-byte keystream block. Only the
+//! matrix layout and the double round differ.
+
+use alloc::vec::Vec;
+
+/// "expand 32-byte k" as little-endian words
+pub const SIGMA: [u32; 4] = [0x61707865, 0x3320646e, 0x79622d32, 0x6b206574];
+
+pub const BLOCK_SIZE: usize = 64;
+
+pub type State = [u32; 16];
+
+pub trait ArxCore {
+    /// number of rounds, two for every double round
+    const ROUNDS: usize;
+
+    fn double_round(state: &mut State);
+}
+
+/// little-endian words of `bytes`, which is `4 * N` bytes long
+pub fn words<const N: usize>(bytes: &[u8]) -> [u32; N] {
+    let mut words = [0u32; N];
+    for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(4)) {
+        *word = u32::from_le_bytes(chunk.try_into().unwrap());
+    }
+    words
+}
+
+/// runs the rounds of `C` over `input` and adds `input` back
+pub fn permute<C: ArxCore>(input: &State) -> State {
+    let mut state = *input;
+    for _ in 0..C::ROUNDS / 2 {
+        C::double_round(&mut state);
+    }
+    for (word, &original) in state.iter_mut().zip(input) {
+        *word = word.wrapping_add(original);
+    }
+    state
+}
+
+/// the keystream block of `C` for the initial state `input`
+pub fn block<C: ArxCore>(input: &State) -> [u8; BLOCK_SIZE] {
+    let mut output = [0u8; BLOCK_SIZE];
+    for (chunk, word) in output.chunks_exact_mut(4).zip(permute::<C>(input)) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    output
+}
+
+/// whether `len` bytes of keystream starting at block `counter` end at block
+/// `max_counter` at the latest, rather than wrap around to blocks already used
+pub fn fits(counter: u64, len: usize, max_counter: u64) -> bool {
+    let blocks = len.div_ceil(BLOCK_SIZE) as u64;
+    blocks == 0 || counter.checked_add(blocks - 1).is_some_and(|last| last <= max_counter)
+}
+
+/// xors `message` with the blocks `keystream(0)`, `keystream(1)`, ...
+pub fn apply_keystream(message: &[u8], mut keystream: impl FnMut(u64) -> [u8; BLOCK_SIZE]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(message.len());
+    for (block_index, chunk) in message.chunks(BLOCK_SIZE).enumerate() {
+        let keystream = keystream(block_index as u64);
+        output.extend(chunk.iter().zip(keystream).map(|(&byte, k)| byte ^ k));
+    }
+    output
+}
@@ -0,0 +1,3 @@
+fn main() {
+    g2::cipher4::cipher::main12();
+}
@@ -0,0 +1,3 @@
+fn main() {
+    g2::classical::playfair::main();
+}
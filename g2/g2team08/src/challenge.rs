@@ -0,0 +1,475 @@
+//! The challenge every cipher binary runs: a secret message under a default
+//! key, and the subcommands of [`crate::cli`].
+
+use crate::bytes::Bytes;
+use crate::cipher::Key;
+use crate::analysis::Solver;
+use crate::cli::{Cli, Command};
+use crate::envelope::{self, Envelope, EnvelopeError, Mode};
+use crate::keycheck::{load_valid_key, KeyInspection};
+use crate::protection::ProtectionPolicy;
+use crate::report::{key_id, ErrorKind, Failure, Report};
+use crate::rng::CryptoRng;
+use crate::stream::{self, Pieces, StreamError};
+use crate::timing::DeterminantInverse;
+use crate::traits::{DecryptBytes, EncryptBytes};
+use crate::vectors::VectorFile;
+
+use base64::{prelude::*};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+pub const CHEATER: &str = "cheater: it is forbidden to decrypt the challenge ciphertext";
+pub const TRY_HARDER: &str = "You should try harder :(";
+
+#[derive(Debug, Clone)]
+pub struct Secret<T: DecryptBytes> {
+    pub key: Key<T>,
+    pub encrypted_message: Bytes,
+}
+
+impl<T: DecryptBytes> Secret<T> {
+    pub fn secret_message(&self) -> String {
+        let message = T::decrypt_bytes(&self.key, self.encrypted_message.clone()).unwrap();
+        String::from_utf8(message.into()).unwrap()
+    }
+}
+
+pub trait ChallengeCipher: EncryptBytes + DecryptBytes {
+    /// name reported by the command line interface
+    const NAME: &'static str;
+
+    /// decides which queries under the default key reveal the challenge
+    type Protection: ProtectionPolicy;
+
+    fn secret() -> Secret<Self>;
+
+    /// breakdown of `key` for inspect-key and validate-key, ciphers implementing
+    /// [`KeyCheck`](crate::keycheck::KeyCheck) return `Some(Self::check(key))`
+    fn inspect_key(_key: &Key<Self>) -> Option<KeyInspection> {
+        None
+    }
+
+    /// mode and IV recorded in [envelopes](crate::envelope) of ciphertexts under `key`
+    fn envelope_mode(_key: &Key<Self>) -> (Mode, Vec<u8>) {
+        (Mode::Codebook, Vec::new())
+    }
+
+    /// encrypts `message` under `key`, or fails where
+    /// [`encrypt_bytes`](EncryptBytes::encrypt_bytes) would panic; the
+    /// default never fails, the stream ciphers fail past the end of their keystream
+    fn try_encrypt(key: &Key<Self>, message: Bytes) -> Result<Bytes, Self::DecryptError> {
+        Ok(Self::encrypt_bytes(key, message))
+    }
+
+    /// how [`stream`](crate::stream) goes through long inputs under `key`;
+    /// `None`, the default, reads the whole input first
+    fn pieces(_key: &Key<Self>, _decrypt: bool) -> Option<Pieces> {
+        None
+    }
+
+    /// how the key's determinant is found and inverted, for ciphers with one;
+    /// [`Target::Modinv`](crate::timing::Target::Modinv) times the inversion
+    fn determinant_inverse() -> Option<DeterminantInverse<Self>> {
+        None
+    }
+
+    /// whether a decryption query must be refused to protect the challenge;
+    /// `own_key` is whether it comes with a key, from `--key` or `--key-file`
+    fn forbids_decryption(encrypted_message: &Bytes, own_key: bool) -> bool {
+        !own_key && Self::Protection::forbids(&Self::secret().encrypted_message, encrypted_message)
+    }
+
+    /// feel free to override this implementation
+    /// this one has side-channel vulnerabilities
+    fn execute(cli: Cli, rng: impl CryptoRng)
+    where
+        Self: 'static,
+    {
+        let report = match cli.command {
+            Command::Generate { seed } => {
+                let mut report = Report::new(Self::NAME, "generate");
+                let key = match seed {
+                    Some(seed) => Self::gen_keys(crate::rng::seeded(seed)),
+                    None => Self::gen_keys(rng),
+                };
+                report.key_id = Some(key_id(Some(&key)));
+                report.key = Some(BASE64_STANDARD.encode(key));
+                report
+            }
+            Command::Encrypt {
+                secret_message,
+                key_file,
+                key,
+                envelope,
+                authenticate,
+                key_id,
+                stream,
+            } => match (secret_message, stream.input) {
+                (Some(message), _) => {
+                    let other_key = key.is_some() || key_file.is_some() || envelope || authenticate;
+                    match crate::agent::held_key_id::<Self>(key_id, other_key) {
+                        Ok(Some(key_id)) => crate::agent::encrypt::<Self>(key_id, message),
+                        Ok(None) => encrypt::<Self>(message, key, key_file, envelope, authenticate),
+                        Err(failure) => Report::new(Self::NAME, "encrypt").failed(failure),
+                    }
+                }
+                (None, Some(input)) => {
+                    stream_command::<Self>(false, &input, stream.output.as_ref(), key, key_file, cli.json);
+                    return;
+                }
+                (None, None) => unreachable!("clap requires a message or --input"),
+            },
+            Command::Decrypt {
+                encrypted_message,
+                key_file,
+                key,
+                authenticate,
+                key_id,
+                stream,
+            } => match (encrypted_message, stream.input) {
+                (Some(encrypted_message), _) => {
+                    let other_key = key.is_some() || key_file.is_some() || authenticate;
+                    match crate::agent::held_key_id::<Self>(key_id, other_key) {
+                        Ok(Some(key_id)) => crate::agent::decrypt::<Self>(key_id, encrypted_message),
+                        Ok(None) => decrypt::<Self>(encrypted_message, key, key_file, authenticate),
+                        Err(failure) => Report::new(Self::NAME, "decrypt").failed(failure),
+                    }
+                }
+                (None, Some(input)) => {
+                    stream_command::<Self>(true, &input, stream.output.as_ref(), key, key_file, cli.json);
+                    return;
+                }
+                (None, None) => unreachable!("clap requires a ciphertext or --input"),
+            },
+            Command::Batch {
+                decrypt,
+                input,
+                key,
+                key_file,
+            } => {
+                batch::<Self>(decrypt, input.as_ref(), key, key_file, cli.json);
+                return;
+            }
+            Command::InspectKey { key, key_file } => crate::keycheck::report::<Self>("inspect-key", key, key_file),
+            Command::ValidateKey { key, key_file } => crate::keycheck::report::<Self>("validate-key", key, key_file),
+            Command::Serve {
+                endpoint,
+                budget,
+                audit_log,
+            } => {
+                let config = crate::server::OracleConfig { budget, audit_log };
+                if let Err(e) = crate::server::serve::<Self>(&endpoint.endpoint(), config) {
+                    eprintln!("error: {}", e);
+                    process::exit(1);
+                }
+                return;
+            }
+            Command::Analyze {
+                ciphertext,
+                text,
+                solver,
+                top,
+            } => {
+                let ciphertext = if text {
+                    ciphertext.into_bytes()
+                } else {
+                    match BASE64_STANDARD.decode(ciphertext.trim()) {
+                        Ok(ciphertext) => ciphertext,
+                        Err(e) => {
+                            eprintln!("error: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                };
+                let solvers = if solver.is_empty() {
+                    vec![Solver::Caesar, Solver::Vigenere, Solver::Substitution]
+                } else {
+                    solver
+                };
+                crate::analysis::run(&ciphertext, &solvers, top);
+                return;
+            }
+            Command::Randomness {
+                bytes,
+                plaintext,
+                key,
+                key_file,
+                seed,
+            } => {
+                // never the challenge key: its keystream is not to be handed out, even as statistics
+                let key_content = match load_valid_key::<Self>(key, key_file) {
+                    Ok(Some(key_content)) => key_content,
+                    Ok(None) => {
+                        let key = match seed {
+                            Some(seed) => Self::gen_keys(crate::rng::seeded(seed)),
+                            None => Self::gen_keys(rng),
+                        };
+                        Key::<Self>::clone_from_slice(&key)
+                    }
+                    Err(failure) => {
+                        eprintln!("error: {}", failure);
+                        std::process::exit(1);
+                    }
+                };
+                let len = bytes as usize;
+                let output = Self::encrypt_bytes(&key_content, plaintext.bytes(len).into());
+                crate::randomness::run(&output[..len.min(output.len())]);
+                return;
+            }
+            Command::Timing { target, samples, seed } => {
+                crate::timing::run::<Self>(target, samples, seed.unwrap_or_default());
+                return;
+            }
+            Command::ExportVectors { count, seed, output } => {
+                let vectors = crate::vectors::export::<Self>(count, seed.unwrap_or_default());
+                let json = serde_json::to_string_pretty(&vectors).unwrap() + "\n";
+                let written = match &output {
+                    Some(path) => std::fs::write(path, json),
+                    None => io::stdout().write_all(json.as_bytes()),
+                };
+                if let Err(e) = written {
+                    eprintln!("error: {}", e);
+                    process::exit(1);
+                }
+                return;
+            }
+            Command::CheckVectors { files } => {
+                if !check_vectors(&files) {
+                    process::exit(1);
+                }
+                return;
+            }
+            Command::Repl => {
+                crate::repl::main(crate::registry::find(Self::NAME).expect("every cipher binary is registered"));
+                return;
+            }
+            Command::Client { endpoint, requests } => {
+                match crate::server::request(&endpoint.endpoint(), &requests) {
+                    Ok(replies) => replies.iter().for_each(|reply| println!("{}", reply)),
+                    Err(e) => {
+                        eprintln!("error: {}", e);
+                        process::exit(1);
+                    }
+                }
+                return;
+            }
+        };
+        report.emit(cli.json);
+    }
+}
+
+fn encrypt<C: ChallengeCipher>(
+    message: Bytes,
+    key: Option<Bytes>,
+    key_file: Option<PathBuf>,
+    envelope: bool,
+    authenticate: bool,
+) -> Report {
+    let report = Report::new(C::NAME, "encrypt");
+    match load_valid_key::<C>(key, key_file) {
+        Ok(key_content) => {
+            let key_id = key_id(key_content.as_ref().map(|k| k.as_slice()));
+            let key_content = key_content.unwrap_or_else(|| C::secret().key);
+            let encrypted_message = if envelope {
+                Envelope::seal::<C>(&key_content, message, authenticate).map(|envelope| envelope.to_bytes().into())
+            } else {
+                C::try_encrypt(&key_content, message).map_err(|e| EnvelopeError::Cipher(e.to_string()))
+            };
+            match encrypted_message {
+                Ok(encrypted_message) => Report {
+                    key_id: Some(key_id),
+                    ciphertext: Some(BASE64_STANDARD.encode(encrypted_message)),
+                    ..report
+                },
+                Err(e) => report.failed(Failure::new(ErrorKind::EncryptFailed, e)),
+            }
+        }
+        Err(failure) => report.failed(failure),
+    }
+}
+
+fn decrypt<C: ChallengeCipher>(
+    encrypted_message: Bytes,
+    key: Option<Bytes>,
+    key_file: Option<PathBuf>,
+    authenticate: bool,
+) -> Report {
+    let mut report = Report::new(C::NAME, "decrypt");
+    report.ciphertext = Some(BASE64_STANDARD.encode(&encrypted_message));
+    // enveloped ciphertexts are told apart by their magic bytes
+    let envelope = if envelope::is_envelope(&encrypted_message) {
+        Envelope::parse(&encrypted_message).map(Some)
+    } else if authenticate {
+        Err(EnvelopeError::MissingTag)
+    } else {
+        Ok(None)
+    };
+    let payload = match &envelope {
+        Ok(Some(envelope)) => &envelope.ciphertext,
+        _ => &encrypted_message,
+    };
+    if C::forbids_decryption(payload, key.is_some() || key_file.is_some()) {
+        report.key_id = Some(key_id(None));
+        report.failed(Failure::new(ErrorKind::Cheater, CHEATER))
+    } else {
+        match (envelope, load_valid_key::<C>(key, key_file)) {
+            (Err(e), _) => report.failed(Failure::new(ErrorKind::Envelope, e)),
+            (_, Err(failure)) => report.failed(failure),
+            (Ok(envelope), Ok(key_content)) => {
+                report.key_id = Some(key_id(key_content.as_ref().map(|k| k.as_slice())));
+                let key_content = key_content.unwrap_or_else(|| C::secret().key);
+                let decrypted = match envelope {
+                    Some(envelope) => {
+                        let opened = if authenticate {
+                            envelope.open_authenticated::<C>(&key_content)
+                        } else {
+                            envelope.open::<C>(&key_content)
+                        };
+                        opened.map_err(|e| match e {
+                            EnvelopeError::Cipher(_) => Failure::new(ErrorKind::DecryptFailed, e),
+                            _ => Failure::new(ErrorKind::Envelope, e),
+                        })
+                    }
+                    None => C::decrypt_bytes(&key_content, encrypted_message.clone())
+                        .map_err(|e| Failure::new(ErrorKind::DecryptFailed, e)),
+                };
+                match decrypted {
+                    Ok(secret_message) => match String::from_utf8(secret_message.into()) {
+                        Ok(s) if crate::cli::verified_message(&s).is_ok() => Report {
+                            plaintext: Some(s),
+                            ..report
+                        },
+                        _ => report.failed(Failure::new(ErrorKind::TryHarder, TRY_HARDER)),
+                    },
+                    Err(failure) => report.failed(failure),
+                }
+            }
+        }
+    }
+}
+
+/// `path`, or standard input for `-`
+fn open_input(path: &Path) -> io::Result<Box<dyn Read>> {
+    if path == Path::new("-") {
+        Ok(Box::new(io::stdin().lock()))
+    } else {
+        Ok(Box::new(File::open(path)?))
+    }
+}
+
+/// encrypt or decrypt with `--input`: raw bytes from the input to the output
+/// file or standard output, and a report only on failure
+fn stream_command<C: ChallengeCipher>(
+    decrypt: bool,
+    input: &Path,
+    output: Option<&PathBuf>,
+    key: Option<Bytes>,
+    key_file: Option<PathBuf>,
+    json: bool,
+) {
+    let report = Report::new(C::NAME, if decrypt { "decrypt" } else { "encrypt" });
+    let io_failure = |e: io::Error, path: &Path| Failure::new(ErrorKind::Io, format!("{}: {}", path.display(), e));
+    let result = load_valid_key::<C>(key, key_file).and_then(|key_content| {
+        // the challenge cannot be checked for without reading the whole ciphertext
+        let key_content = match (key_content, decrypt) {
+            (Some(key_content), _) => key_content,
+            (None, false) => C::secret().key,
+            (None, true) => {
+                return Err(Failure::new(
+                    ErrorKind::InvalidKey,
+                    "decrypting a stream needs --key or --key-file, the default key is not streamed",
+                ));
+            }
+        };
+        let reader = open_input(input).map_err(|e| io_failure(e, input))?;
+        let writer: Box<dyn Write> = match output {
+            Some(path) => Box::new(File::create(path).map_err(|e| io_failure(e, path))?),
+            None => Box::new(io::stdout().lock()),
+        };
+        stream::run::<C>(&key_content, decrypt, reader, BufWriter::new(writer)).map_err(|e| match e {
+            StreamError::Io(e) => Failure::new(ErrorKind::Io, e),
+            StreamError::Cipher(e) => Failure::new(ErrorKind::DecryptFailed, e),
+        })
+    });
+    if let Err(failure) = result {
+        report.failed(failure).emit(json);
+    }
+}
+
+/// the batch subcommand: one encrypt or decrypt per line of the input, each
+/// answered by one line
+fn batch<C: ChallengeCipher>(decrypt: bool, input: Option<&PathBuf>, key: Option<Bytes>, key_file: Option<PathBuf>, json: bool) {
+    let input = input.map_or(Path::new("-"), |path| path.as_path());
+    let reader = match open_input(input) {
+        Ok(reader) => BufReader::new(reader),
+        Err(e) => {
+            let failure = Failure::new(ErrorKind::Io, format!("{}: {}", input.display(), e));
+            Report::new(C::NAME, "batch").failed(failure).emit(json);
+            return;
+        }
+    };
+    let mut stdout = io::stdout().lock();
+    let mut failed = false;
+    for line in reader.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                eprintln!("error: {}: {}", input.display(), e);
+                process::exit(1);
+            }
+        };
+        let line = line.trim_end_matches('\r');
+        let report = if decrypt {
+            match crate::cli::parse_base64(line.trim()) {
+                Ok(encrypted_message) => self::decrypt::<C>(encrypted_message, key.clone(), key_file.clone(), false),
+                Err(e) => Report::new(C::NAME, "decrypt").failed(Failure::new(ErrorKind::InvalidInput, e)),
+            }
+        } else {
+            match crate::cli::parse_message(line) {
+                Ok(message) => encrypt::<C>(message, key.clone(), key_file.clone(), false, false),
+                Err(e) => Report::new(C::NAME, "encrypt").failed(Failure::new(ErrorKind::InvalidInput, e)),
+            }
+        };
+        failed |= report.failed_status(json);
+        if writeln!(stdout, "{}", report.line(json)).is_err() {
+            process::exit(1);
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
+
+/// the check-vectors subcommand: one line per test, then a count per file;
+/// false if a test failed or a file could not be run
+fn check_vectors(files: &[PathBuf]) -> bool {
+    let mut ok = true;
+    for path in files {
+        let outcomes = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|json| serde_json::from_str::<VectorFile>(&json).map_err(|e| e.to_string()))
+            .and_then(|file| Ok((crate::vectors::check(&file).map_err(|e| e.to_string())?, file.algorithm)));
+        let (outcomes, algorithm) = match outcomes {
+            Ok(outcomes) => outcomes,
+            Err(e) => {
+                println!("{}: error: {}", path.display(), e);
+                ok = false;
+                continue;
+            }
+        };
+        println!("{}: {}", path.display(), algorithm);
+        for outcome in &outcomes {
+            match &outcome.failure {
+                None => println!("pass #{} {}", outcome.tc_id, outcome.comment),
+                Some(failure) => println!("FAIL #{} {}: {}", outcome.tc_id, outcome.comment, failure),
+            }
+        }
+        let failed = outcomes.iter().filter(|outcome| outcome.failure.is_some()).count();
+        println!("{} passed, {} failed", outcomes.len() - failed, failed);
+        ok &= failed == 0;
+    }
+    ok
+}
@@ -1,26 +1,49 @@
 use bytes::Bytes;
 use cipher::{KeyInit, KeySizeUser};
 use cipher::{typenum, Key};
-use rand;
-use rand::rand_core::{CryptoRng};
+use crate::rng::{CryptoRng, RngCore};
+use rand::Rng;
+#[cfg(feature = "cli")]
 use base64::{prelude::*};
 
 const HILL_SIZE: usize = 8;
 const KEY_SIZE: usize = HILL_SIZE * HILL_SIZE + 1; // 64+1=65
 
-use crate::traits::{ChallengeCipher, DecryptBytes, EncryptBytes};
+#[cfg(feature = "cli")]
+use crate::keycheck::{FieldValue, KeyCheck, KeyInspection};
+#[cfg(feature = "cli")]
+use crate::protection::Affine;
+#[cfg(feature = "cli")]
+use crate::stream::Pieces;
+#[cfg(feature = "cli")]
+use crate::timing::DeterminantInverse;
+#[cfg(feature = "cli")]
+use crate::traits::ChallengeCipher;
+use crate::traits::{DecryptBytes, EncryptBytes};
 
-#[derive(Debug, Clone)]
-struct HillCaesarError;
-impl std::fmt::Display for HillCaesarError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Hill+Caesar cipher error")
+use alloc::{vec, vec::Vec};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HillCaesarError {
+    /// the key matrix has no inverse
+    SingularKey,
+    /// the ciphertext is not a whole number of blocks, it has this many bytes
+    Length(usize),
+}
+impl core::fmt::Display for HillCaesarError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            HillCaesarError::SingularKey => write!(f, "Hill+Caesar cipher error: key matrix is not invertible mod 256"),
+            HillCaesarError::Length(len) => {
+                write!(f, "Hill+Caesar cipher error: ciphertext of {} bytes is not a multiple of {}", len, HILL_SIZE)
+            }
+        }
     }
 }
-impl std::error::Error for HillCaesarError {}
+impl core::error::Error for HillCaesarError {}
 
 #[derive(Clone)]
-struct HillCaesarCipher;
+pub struct HillCaesarCipher;
 
 impl KeySizeUser for HillCaesarCipher {
     type KeySize = typenum::U65;
@@ -71,13 +94,13 @@ fn matrix_det(mat: &[[u8; HILL_SIZE]; HILL_SIZE]) -> i32 {
     (d % 256 + 256) % 256
 }
 
+#[allow(clippy::needless_range_loop)]
 fn matrix_inv(mat: &[[u8; HILL_SIZE]; HILL_SIZE]) -> Option<[[u8; HILL_SIZE]; HILL_SIZE]> {
     let det = matrix_det(mat);
     if det == 0 { return None; }
     let inv_det = modinv(det, 256)?;
     let mut cof = [[0i32; HILL_SIZE]; HILL_SIZE];
     for i in 0..HILL_SIZE {
-        #[allow(clippy::needless_range_loop)]
         for j in 0..HILL_SIZE {
             let mut sub = vec![];
             for (r, row) in mat.iter().enumerate() {
@@ -104,6 +127,15 @@ fn matrix_inv(mat: &[[u8; HILL_SIZE]; HILL_SIZE]) -> Option<[[u8; HILL_SIZE]; HI
     Some(inv)
 }
 
+/// the Hill matrix of the first `HILL_SIZE * HILL_SIZE` bytes of `key`, row by row
+fn key_matrix(key: &[u8]) -> [[u8; HILL_SIZE]; HILL_SIZE] {
+    let mut m = [[0u8; HILL_SIZE]; HILL_SIZE];
+    for (row, bytes) in m.iter_mut().zip(key.chunks_exact(HILL_SIZE)) {
+        row.copy_from_slice(bytes);
+    }
+    m
+}
+
 fn pad_bytes(mut v: Vec<u8>) -> Vec<u8> {
     let pad = HILL_SIZE - (v.len() % HILL_SIZE);
     if pad != HILL_SIZE {
@@ -117,17 +149,74 @@ fn unpad_bytes(mut v: Vec<u8>) -> Vec<u8> {
     v
 }
 
+/// A uniformly random element of GL(N, F2), as 0/1 entries, built row by row.
+///
+/// Once rows 0..i are chosen and independent, row i must avoid their span S of
+/// 2^i vectors. Bring S to reduced echelon form with pivot columns P; every
+/// vector is uniquely s + f with s in S and f zero on P, and it lies outside S
+/// exactly when f != 0. Drawing s uniformly from S and f uniformly from the
+/// 2^(N-i) - 1 nonzero vectors on the other columns therefore draws row i
+/// uniformly from the 2^N - 2^i allowed rows, so every matrix of GL(N, F2)
+/// has probability 1 / prod(2^N - 2^i). No draw is ever rejected.
+pub fn random_invertible_mod2<const N: usize>(rng: &mut impl RngCore) -> [[u8; N]; N] {
+    assert!(N <= 8);
+    // rows as bit masks, bit j for column j; the basis is kept fully reduced
+    let mut basis: Vec<(u8, u8)> = Vec::with_capacity(N); // (pivot bit, vector)
+    let mut mat = [[0u8; N]; N];
+    for (i, row) in mat.iter_mut().enumerate() {
+        let pivots = basis.iter().fold(0u8, |acc, &(pivot, _)| acc | pivot);
+        let free: Vec<usize> = (0..N).filter(|&j| pivots & (1 << j) == 0).collect();
+
+        // f: a nonzero vector spread over the free columns
+        let f_index = rng.random_range(1..1u32 << free.len());
+        let mut f = 0u8;
+        for (k, &j) in free.iter().enumerate() {
+            f |= (((f_index >> k) & 1) as u8) << j;
+        }
+        // s: a subset sum of the basis
+        let s_index = rng.random_range(0..1u32 << i);
+        let mut s = 0u8;
+        for (k, &(_, b)) in basis.iter().enumerate() {
+            if (s_index >> k) & 1 == 1 {
+                s ^= b;
+            }
+        }
+        let v = s ^ f;
+
+        let pivot = f & f.wrapping_neg();
+        for (_, b) in basis.iter_mut() {
+            if *b & pivot != 0 {
+                *b ^= f;
+            }
+        }
+        basis.push((pivot, f));
+        for (j, x) in row.iter_mut().enumerate() {
+            *x = (v >> j) & 1;
+        }
+    }
+    mat
+}
+
+/// A uniformly random element of GL(8, Z/256). A matrix is invertible mod 256
+/// exactly when it is invertible mod 2, and each invertible matrix mod 2 lifts
+/// to the same number (128^64) of matrices mod 256, one per choice of the 7 high
+/// bits of every entry. So a uniform matrix mod 2 with uniform high bits is uniform.
+pub fn random_invertible_matrix(rng: &mut impl RngCore) -> [[u8; HILL_SIZE]; HILL_SIZE] {
+    let low = random_invertible_mod2::<HILL_SIZE>(rng);
+    let mut high = [0u8; HILL_SIZE * HILL_SIZE];
+    rng.fill_bytes(&mut high);
+    let mut mat = [[0u8; HILL_SIZE]; HILL_SIZE];
+    for (i, row) in mat.iter_mut().enumerate() {
+        for (j, x) in row.iter_mut().enumerate() {
+            *x = (high[i * HILL_SIZE + j] & 0xfe) | low[i][j];
+        }
+    }
+    mat
+}
+
 impl EncryptBytes for HillCaesarCipher {
     fn encrypt_bytes(key: &Key<Self>, message: Bytes) -> Bytes {
-        let mat: [[u8; HILL_SIZE]; HILL_SIZE] = {
-            let mut m = [[0u8; HILL_SIZE]; HILL_SIZE];
-            for i in 0..HILL_SIZE {
-                for j in 0..HILL_SIZE {
-                    m[i][j] = key[i * HILL_SIZE + j];
-                }
-            }
-            m
-        };
+        let mat = key_matrix(key);
         let caesar = key[KEY_SIZE-1];
         let input = pad_bytes(message.to_vec());
         let mut out = vec![];
@@ -150,39 +239,23 @@ impl EncryptBytes for HillCaesarCipher {
         Bytes::from(encrypted)
     }
     fn gen_keys(mut rng: impl CryptoRng) -> Vec<u8> {
-        loop {
-            let mut mat = [0u8; HILL_SIZE*HILL_SIZE];
-            rng.fill_bytes(&mut mat);
-            let mut m = [[0u8; HILL_SIZE]; HILL_SIZE];
-            for i in 0..HILL_SIZE {
-                for j in 0..HILL_SIZE {
-                    m[i][j] = mat[i*HILL_SIZE+j];
-                }
-            }
-            if matrix_inv(&m).is_some() {
-                let caesar = (rng.next_u32() % 256) as u8;
-                let mut key = mat.to_vec();
-                key.push(caesar);
-                return key;
-            }
-        }
+        let mat = random_invertible_matrix(&mut rng);
+        let caesar = (rng.next_u32() % 256) as u8;
+        let mut key: Vec<u8> = mat.iter().flatten().copied().collect();
+        key.push(caesar);
+        key
     }
 }
 
 impl DecryptBytes for HillCaesarCipher {
     type DecryptError = HillCaesarError;
     fn decrypt_bytes(key: &Key<Self>, message: Bytes) -> Result<Bytes, Self::DecryptError> {
-        let mat: [[u8; HILL_SIZE]; HILL_SIZE] = {
-            let mut m = [[0u8; HILL_SIZE]; HILL_SIZE];
-            for i in 0..HILL_SIZE {
-                for j in 0..HILL_SIZE {
-                    m[i][j] = key[i * HILL_SIZE + j];
-                }
-            }
-            m
-        };
+        let mat = key_matrix(key);
         let caesar = key[KEY_SIZE-1];
-        let inv = matrix_inv(&mat).ok_or(HillCaesarError)?;
+        let inv = matrix_inv(&mat).ok_or(HillCaesarError::SingularKey)?;
+        if !message.len().is_multiple_of(HILL_SIZE) {
+            return Err(HillCaesarError::Length(message.len()));
+        }
         let caesar_dec: Vec<u8> = message.iter().map(|&b| b.wrapping_sub(caesar)).collect();
         let mut out = vec![];
         for chunk in caesar_dec.chunks(HILL_SIZE) {
@@ -204,7 +277,61 @@ impl DecryptBytes for HillCaesarCipher {
     }
 }
 
+#[cfg(feature = "cli")]
+impl KeyCheck for HillCaesarCipher {
+    fn check(key: &Key<Self>) -> KeyInspection {
+        let mut mat = [[0u8; HILL_SIZE]; HILL_SIZE];
+        for (i, row) in mat.iter_mut().enumerate() {
+            row.copy_from_slice(&key[i * HILL_SIZE..(i + 1) * HILL_SIZE]);
+        }
+        let det = matrix_det(&mat);
+        let inv = matrix_inv(&mat);
+        let caesar = key[KEY_SIZE-1];
+
+        let mut inspection = KeyInspection::default();
+        inspection
+            .field("matrix", FieldValue::Matrix(mat.iter().map(|row| row.to_vec()).collect()))
+            .field("determinant mod 256", FieldValue::Number(det as u64))
+            .field("invertible", FieldValue::Flag(inv.is_some()));
+        if let Some(inv) = inv {
+            inspection.field("inverse", FieldValue::Matrix(inv.iter().map(|row| row.to_vec()).collect()));
+        } else {
+            inspection.error(format!("determinant {} is even, so the matrix has no inverse mod 256", det));
+        }
+        inspection.field("caesar shift", FieldValue::Number(caesar as u64));
+
+        let identity = mat.iter().enumerate().all(|(i, row)| row.iter().enumerate().all(|(j, &x)| x == (i == j) as u8));
+        if identity {
+            inspection.warning("the matrix is the identity, only the Caesar shift encrypts");
+        }
+        if caesar == 0 {
+            inspection.warning("the Caesar shift is 0");
+        }
+        inspection
+    }
+}
+
+#[cfg(feature = "cli")]
 impl ChallengeCipher for HillCaesarCipher {
+    const NAME: &'static str = "hill-caesar";
+
+    type Protection = Affine;
+
+    fn inspect_key(key: &Key<Self>) -> Option<KeyInspection> {
+        Some(Self::check(key))
+    }
+
+    fn pieces(key: &Key<Self>, decrypt: bool) -> Option<Pieces> {
+        Some(Pieces::blockwise::<Self>(key, HILL_SIZE, decrypt, |key, _| Some(*key)))
+    }
+
+    fn determinant_inverse() -> Option<DeterminantInverse<Self>> {
+        Some(DeterminantInverse {
+            determinant: |key| matrix_det(&key_matrix(key)),
+            invert: |det| modinv(det, 256),
+        })
+    }
+
     fn secret() -> crate::traits::Secret<Self> {
         let key_b64 = "NzXLB/cPThBLYN3j/r9aV8/SIWuREranu1glYCilLBwm+WAcNDhIhZRn4sgkyk9kEaPUrcLGmSEn/STBziOvZvs=";
         let key_vec = base64::engine::general_purpose::STANDARD.decode(key_b64).unwrap();
@@ -217,7 +344,8 @@ impl ChallengeCipher for HillCaesarCipher {
     }
 }
 
+#[cfg(feature = "cli")]
 pub fn main() {
-    let cmd = crate::cli::command();
-    HillCaesarCipher::execute(cmd, rand::rng());
+    let cli = crate::cli::cli();
+    HillCaesarCipher::execute(cli, rand::rng());
 }
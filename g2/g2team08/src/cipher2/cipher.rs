@@ -1,11 +1,24 @@
 use bytes::Bytes;
 use cipher::{KeyInit, KeySizeUser};
 use cipher::{typenum, Key};
-use rand;
-use rand::rand_core::CryptoRng;
+use crate::rng::CryptoRng;
+#[cfg(feature = "cli")]
 use base64::{prelude::*};
 
-use crate::traits::{ChallengeCipher, DecryptBytes, EncryptBytes};
+use crate::arx::{self, ArxCore, State, SIGMA, words};
+#[cfg(feature = "cli")]
+use crate::envelope::Mode;
+#[cfg(feature = "cli")]
+use crate::keycheck::{check_stream_key, KeyCheck, KeyInspection};
+#[cfg(feature = "cli")]
+use crate::protection::SharedKeystream;
+#[cfg(feature = "cli")]
+use crate::stream::Pieces;
+#[cfg(feature = "cli")]
+use crate::traits::ChallengeCipher;
+use crate::traits::{DecryptBytes, EncryptBytes};
+
+use alloc::{vec, vec::Vec};
 
 const CHACHA20_KEY_SIZE: usize = 32;
 const CHACHA20_NONCE_SIZE: usize = 12;
@@ -13,16 +26,16 @@ const CHACHA20_COUNTER_SIZE: usize = 4;
 const CHACHA20_TOTAL_KEY_SIZE: usize = CHACHA20_KEY_SIZE + CHACHA20_NONCE_SIZE + CHACHA20_COUNTER_SIZE;
 
 #[derive(Debug, Clone)]
-struct ChaCha20Error;
-impl std::fmt::Display for ChaCha20Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "ChaCha20 cipher error")
+pub struct ChaCha20Error;
+impl core::fmt::Display for ChaCha20Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "ChaCha20 cipher error: the message runs past the end of the keystream")
     }
 }
-impl std::error::Error for ChaCha20Error {}
+impl core::error::Error for ChaCha20Error {}
 
 #[derive(Clone)]
-struct ChaCha20Cipher;
+pub struct ChaCha20Cipher;
 
 impl KeySizeUser for ChaCha20Cipher {
     type KeySize = typenum::U48;
@@ -33,160 +46,78 @@ impl KeyInit for ChaCha20Cipher {
     fn new(_: &Key<Self>) -> Self { ChaCha20Cipher{} }
 }
 
-fn quarter_round(a: &mut u32, b: &mut u32, c: &mut u32, d: &mut u32) {
-    *a = a.wrapping_add(*b);
-    *d ^= *a;
-    *d = d.rotate_left(16);
-    
-    *c = c.wrapping_add(*d);
-    *b ^= *c;
-    *b = b.rotate_left(12);
-    
-    *a = a.wrapping_add(*b);
-    *d ^= *a;
-    *d = d.rotate_left(8);
-    
-    *c = c.wrapping_add(*d);
-    *b ^= *c;
-    *b = b.rotate_left(7);
+fn quarter_round(state: &mut State, a: usize, b: usize, c: usize, d: usize) {
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] ^= state[a];
+    state[d] = state[d].rotate_left(16);
+
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] ^= state[c];
+    state[b] = state[b].rotate_left(12);
+
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] ^= state[a];
+    state[d] = state[d].rotate_left(8);
+
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] ^= state[c];
+    state[b] = state[b].rotate_left(7);
 }
 
-fn chacha20_block(key: &[u8; CHACHA20_KEY_SIZE], nonce: &[u8; CHACHA20_NONCE_SIZE], counter: u32) -> [u8; 64] {
-    let mut state = [
-        0x61707865, 0x3320646e, 0x79622d32, 0x6b206574,
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0
-    ];
-    
-    // little-endian
-    for i in 0..8 {
-        state[i + 4] = u32::from_le_bytes([
-            key[i * 4], key[i * 4 + 1], key[i * 4 + 2], key[i * 4 + 3]
-        ]);
-    }
-    
-    for i in 0..3 {
-        state[i + 13] = u32::from_le_bytes([
-            nonce[i * 4], nonce[i * 4 + 1], nonce[i * 4 + 2], nonce[i * 4 + 3]
-        ]);
+impl ArxCore for ChaCha20Cipher {
+    const ROUNDS: usize = 20;
+
+    fn double_round(state: &mut State) {
+        // columns
+        quarter_round(state, 0, 4, 8, 12);
+        quarter_round(state, 1, 5, 9, 13);
+        quarter_round(state, 2, 6, 10, 14);
+        quarter_round(state, 3, 7, 11, 15);
+        // diagonals
+        quarter_round(state, 0, 5, 10, 15);
+        quarter_round(state, 1, 6, 11, 12);
+        quarter_round(state, 2, 7, 8, 13);
+        quarter_round(state, 3, 4, 9, 14);
     }
-    
+}
+
+pub fn chacha20_block(key: &[u8; CHACHA20_KEY_SIZE], nonce: &[u8; CHACHA20_NONCE_SIZE], counter: u32) -> [u8; 64] {
+    // constants, key, counter, nonce; all little-endian
+    let mut state = [0u32; 16];
+    state[..4].copy_from_slice(&SIGMA);
+    state[4..12].copy_from_slice(&words::<8>(key));
     state[12] = counter;
-    
-    let mut working_state = state;
-    
-    for _ in 0..10 {
-        {
-            let (a, b, c, d) = (working_state[0], working_state[4], working_state[8], working_state[12]);
-            let (mut a, mut b, mut c, mut d) = (a, b, c, d);
-            quarter_round(&mut a, &mut b, &mut c, &mut d);
-            working_state[0] = a;
-            working_state[4] = b;
-            working_state[8] = c;
-            working_state[12] = d;
-        }
-        {
-            let (a, b, c, d) = (working_state[1], working_state[5], working_state[9], working_state[13]);
-            let (mut a, mut b, mut c, mut d) = (a, b, c, d);
-            quarter_round(&mut a, &mut b, &mut c, &mut d);
-            working_state[1] = a;
-            working_state[5] = b;
-            working_state[9] = c;
-            working_state[13] = d;
-        }
-        {
-            let (a, b, c, d) = (working_state[2], working_state[6], working_state[10], working_state[14]);
-            let (mut a, mut b, mut c, mut d) = (a, b, c, d);
-            quarter_round(&mut a, &mut b, &mut c, &mut d);
-            working_state[2] = a;
-            working_state[6] = b;
-            working_state[10] = c;
-            working_state[14] = d;
-        }
-        {
-            let (a, b, c, d) = (working_state[3], working_state[7], working_state[11], working_state[15]);
-            let (mut a, mut b, mut c, mut d) = (a, b, c, d);
-            quarter_round(&mut a, &mut b, &mut c, &mut d);
-            working_state[3] = a;
-            working_state[7] = b;
-            working_state[11] = c;
-            working_state[15] = d;
-        }
-        
-        {
-            let (a, b, c, d) = (working_state[0], working_state[5], working_state[10], working_state[15]);
-            let (mut a, mut b, mut c, mut d) = (a, b, c, d);
-            quarter_round(&mut a, &mut b, &mut c, &mut d);
-            working_state[0] = a;
-            working_state[5] = b;
-            working_state[10] = c;
-            working_state[15] = d;
-        }
-        {
-            let (a, b, c, d) = (working_state[1], working_state[6], working_state[11], working_state[12]);
-            let (mut a, mut b, mut c, mut d) = (a, b, c, d);
-            quarter_round(&mut a, &mut b, &mut c, &mut d);
-            working_state[1] = a;
-            working_state[6] = b;
-            working_state[11] = c;
-            working_state[12] = d;
-        }
-        {
-            let (a, b, c, d) = (working_state[2], working_state[7], working_state[8], working_state[13]);
-            let (mut a, mut b, mut c, mut d) = (a, b, c, d);
-            quarter_round(&mut a, &mut b, &mut c, &mut d);
-            working_state[2] = a;
-            working_state[7] = b;
-            working_state[8] = c;
-            working_state[13] = d;
-        }
-        {
-            let (a, b, c, d) = (working_state[3], working_state[4], working_state[9], working_state[14]);
-            let (mut a, mut b, mut c, mut d) = (a, b, c, d);
-            quarter_round(&mut a, &mut b, &mut c, &mut d);
-            working_state[3] = a;
-            working_state[4] = b;
-            working_state[9] = c;
-            working_state[14] = d;
-        }
-    }
-    
-    for i in 0..16 {
-        working_state[i] = working_state[i].wrapping_add(state[i]);
-    }
-    
-    let mut output = [0u8; 64];
-    for i in 0..16 {
-        let bytes = working_state[i].to_le_bytes();
-        output[i * 4..i * 4 + 4].copy_from_slice(&bytes);
+    state[13..].copy_from_slice(&words::<3>(nonce));
+    arx::block::<ChaCha20Cipher>(&state)
+}
+
+/// `message` xored with the keystream of `key`, an error where the block
+/// counter would wrap around past 2^32 - 1
+fn apply_keystream(key: &Key<ChaCha20Cipher>, message: &[u8]) -> Result<Bytes, ChaCha20Error> {
+    let key_bytes = &key[..CHACHA20_KEY_SIZE];
+    let nonce_bytes = &key[CHACHA20_KEY_SIZE..CHACHA20_KEY_SIZE + CHACHA20_NONCE_SIZE];
+    let counter_bytes = &key[CHACHA20_KEY_SIZE + CHACHA20_NONCE_SIZE..];
+
+    let key_array: [u8; CHACHA20_KEY_SIZE] = key_bytes.try_into().unwrap();
+    let nonce_array: [u8; CHACHA20_NONCE_SIZE] = nonce_bytes.try_into().unwrap();
+    let counter = u32::from_le_bytes(counter_bytes.try_into().unwrap());
+
+    if !arx::fits(counter as u64, message.len(), u32::MAX as u64) {
+        return Err(ChaCha20Error);
     }
-    
-    output
+    let output = arx::apply_keystream(message, |block_index| {
+        chacha20_block(&key_array, &nonce_array, counter + block_index as u32)
+    });
+    Ok(Bytes::from(output))
 }
 
 impl EncryptBytes for ChaCha20Cipher {
+    /// panics where the block counter would wrap around, which
+    /// `decrypt_bytes` and `try_encrypt` report as an error
     fn encrypt_bytes(key: &Key<Self>, message: Bytes) -> Bytes {
-        let key_bytes = &key[..CHACHA20_KEY_SIZE];
-        let nonce_bytes = &key[CHACHA20_KEY_SIZE..CHACHA20_KEY_SIZE + CHACHA20_NONCE_SIZE];
-        let counter_bytes = &key[CHACHA20_KEY_SIZE + CHACHA20_NONCE_SIZE..];
-        
-        let key_array: [u8; CHACHA20_KEY_SIZE] = key_bytes.try_into().unwrap();
-        let nonce_array: [u8; CHACHA20_NONCE_SIZE] = nonce_bytes.try_into().unwrap();
-        let counter = u32::from_le_bytes(counter_bytes.try_into().unwrap());
-        
-        let mut output = Vec::new();
-        let message_bytes = message.to_vec();
-        
-        for (block_index, chunk) in message_bytes.chunks(64).enumerate() {
-            let keystream = chacha20_block(&key_array, &nonce_array, counter + block_index as u32);
-            
-            for (i, &byte) in chunk.iter().enumerate() {
-                output.push(byte ^ keystream[i]);
-            }
-        }
-        
-        Bytes::from(output)
+        apply_keystream(key, &message).unwrap_or_else(|e| panic!("{}", e))
     }
-    
+
     fn gen_keys(mut rng: impl CryptoRng) -> Vec<u8> {
         let mut key = vec![0u8; CHACHA20_TOTAL_KEY_SIZE];
         rng.fill_bytes(&mut key);
@@ -198,11 +129,56 @@ impl DecryptBytes for ChaCha20Cipher {
     type DecryptError = ChaCha20Error;
     
     fn decrypt_bytes(key: &Key<Self>, message: Bytes) -> Result<Bytes, Self::DecryptError> {
-        Ok(Self::encrypt_bytes(key, message))
+        apply_keystream(key, &message)
     }
 }
 
+#[cfg(feature = "cli")]
+impl KeyCheck for ChaCha20Cipher {
+    fn check(key: &Key<Self>) -> KeyInspection {
+        let counter = u32::from_le_bytes(key[CHACHA20_KEY_SIZE + CHACHA20_NONCE_SIZE..].try_into().unwrap());
+        let mut inspection = KeyInspection::default();
+        check_stream_key(
+            &mut inspection,
+            &key[..CHACHA20_KEY_SIZE],
+            &key[CHACHA20_KEY_SIZE..CHACHA20_KEY_SIZE + CHACHA20_NONCE_SIZE],
+            counter as u64,
+            (1u128 << 32) - counter as u128,
+        );
+        inspection
+    }
+}
+
+#[cfg(feature = "cli")]
 impl ChallengeCipher for ChaCha20Cipher {
+    const NAME: &'static str = "chacha20";
+
+    type Protection = SharedKeystream;
+
+    fn envelope_mode(key: &Key<Self>) -> (Mode, Vec<u8>) {
+        (Mode::Stream, key[CHACHA20_KEY_SIZE..].to_vec())
+    }
+
+    fn inspect_key(key: &Key<Self>) -> Option<KeyInspection> {
+        Some(Self::check(key))
+    }
+
+    fn try_encrypt(key: &Key<Self>, message: Bytes) -> Result<Bytes, ChaCha20Error> {
+        apply_keystream(key, &message)
+    }
+
+    fn pieces(key: &Key<Self>, decrypt: bool) -> Option<Pieces> {
+        // the piece at `offset` starts `offset / 64` blocks into the keystream
+        Some(Pieces::blockwise::<Self>(key, 64, decrypt, |key, offset| {
+            let mut key = *key;
+            let counter_bytes = &mut key[CHACHA20_KEY_SIZE + CHACHA20_NONCE_SIZE..];
+            let counter = u32::from_le_bytes((&*counter_bytes).try_into().unwrap());
+            let counter = u32::try_from(offset / 64).ok().and_then(|blocks| counter.checked_add(blocks))?;
+            counter_bytes.copy_from_slice(&counter.to_le_bytes());
+            Some(key)
+        }))
+    }
+
     fn secret() -> crate::traits::Secret<Self> {
         let mut key_vec = vec![0u8; CHACHA20_TOTAL_KEY_SIZE];
         for (i, v) in key_vec.iter_mut().take(CHACHA20_KEY_SIZE).enumerate() {
@@ -224,7 +200,8 @@ impl ChallengeCipher for ChaCha20Cipher {
     }
 }
 
+#[cfg(feature = "cli")]
 pub fn main() {
-    let cmd = crate::cli::command();
-    ChaCha20Cipher::execute(cmd, rand::rng());
+    let cli = crate::cli::cli();
+    ChaCha20Cipher::execute(cli, rand::rng());
 }
@@ -0,0 +1,254 @@
+use bytes::Bytes;
+use cipher::{KeyInit, KeySizeUser};
+use cipher::{typenum, Key};
+use crate::rng::CryptoRng;
+#[cfg(feature = "cli")]
+use base64::{prelude::*};
+
+const HILL_SIZE: usize = 8;
+const KEY_SIZE: usize = HILL_SIZE * HILL_SIZE + 1; // 64+1=65
+/// x^8 + x^4 + x^3 + x + 1, the AES field polynomial
+const GF_POLY: u16 = 0x11b;
+
+#[cfg(feature = "cli")]
+use crate::keycheck::{FieldValue, KeyCheck, KeyInspection};
+#[cfg(feature = "cli")]
+use crate::protection::Affine;
+#[cfg(feature = "cli")]
+use crate::stream::Pieces;
+#[cfg(feature = "cli")]
+use crate::traits::ChallengeCipher;
+use crate::traits::{DecryptBytes, EncryptBytes};
+
+use alloc::{vec, vec::Vec};
+
+type Matrix = [[u8; HILL_SIZE]; HILL_SIZE];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HillGf256Error {
+    /// the key matrix has no inverse
+    SingularKey,
+    /// the ciphertext is not a whole number of blocks, it has this many bytes
+    Length(usize),
+}
+impl core::fmt::Display for HillGf256Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            HillGf256Error::SingularKey => write!(f, "Hill(GF(2^8))+Caesar cipher error: singular matrix"),
+            HillGf256Error::Length(len) => {
+                write!(f, "Hill(GF(2^8))+Caesar cipher error: ciphertext of {} bytes is not a multiple of {}", len, HILL_SIZE)
+            }
+        }
+    }
+}
+impl core::error::Error for HillGf256Error {}
+
+#[derive(Clone)]
+pub struct HillGf256Cipher;
+
+impl KeySizeUser for HillGf256Cipher {
+    type KeySize = typenum::U65;
+    fn key_size() -> usize { KEY_SIZE }
+}
+
+impl KeyInit for HillGf256Cipher {
+    fn new(_: &Key<Self>) -> Self { HillGf256Cipher{} }
+}
+
+/// carry-less multiplication reduced by `GF_POLY`
+pub fn gf_mul(a: u8, b: u8) -> u8 {
+    let (mut a, mut b) = (a as u16, b);
+    let mut res = 0u16;
+    while b != 0 {
+        if b & 1 != 0 { res ^= a; }
+        a <<= 1;
+        if a & 0x100 != 0 { a ^= GF_POLY; }
+        b >>= 1;
+    }
+    res as u8
+}
+
+/// a^254 = a^-1 for every nonzero a, since the multiplicative group has order 255
+pub fn gf_inv(a: u8) -> Option<u8> {
+    if a == 0 { return None; }
+    let (mut base, mut exp, mut res) = (a, 254u32, 1u8);
+    while exp > 0 {
+        if exp & 1 != 0 { res = gf_mul(res, base); }
+        base = gf_mul(base, base);
+        exp >>= 1;
+    }
+    Some(res)
+}
+
+/// Gaussian elimination, returns the determinant and the row echelon form
+fn eliminate(mat: &Matrix, mut aug: Option<&mut Matrix>) -> (u8, Matrix) {
+    let mut m = *mat;
+    let mut det = 1u8;
+    for col in 0..HILL_SIZE {
+        let Some(pivot) = (col..HILL_SIZE).find(|&r| m[r][col] != 0) else { return (0, m) };
+        if pivot != col {
+            // row swaps only flip the sign, which is a no-op in characteristic 2
+            m.swap(pivot, col);
+            if let Some(aug) = aug.as_deref_mut() { aug.swap(pivot, col); }
+        }
+        det = gf_mul(det, m[col][col]);
+        let inv = gf_inv(m[col][col]).unwrap();
+        for j in 0..HILL_SIZE {
+            m[col][j] = gf_mul(m[col][j], inv);
+            if let Some(aug) = aug.as_deref_mut() { aug[col][j] = gf_mul(aug[col][j], inv); }
+        }
+        for r in 0..HILL_SIZE {
+            let factor = m[r][col];
+            if r == col || factor == 0 { continue; }
+            for j in 0..HILL_SIZE {
+                m[r][j] ^= gf_mul(factor, m[col][j]);
+                if let Some(aug) = aug.as_deref_mut() { aug[r][j] ^= gf_mul(factor, aug[col][j]); }
+            }
+        }
+    }
+    (det, m)
+}
+
+pub fn matrix_det(mat: &Matrix) -> u8 {
+    eliminate(mat, None).0
+}
+
+pub fn matrix_inv(mat: &Matrix) -> Option<Matrix> {
+    let mut inv = [[0u8; HILL_SIZE]; HILL_SIZE];
+    for (i, row) in inv.iter_mut().enumerate() {
+        row[i] = 1;
+    }
+    match eliminate(mat, Some(&mut inv)) {
+        (0, _) => None,
+        _ => Some(inv),
+    }
+}
+
+fn matrix_mul_vec(mat: &Matrix, v: &[u8; HILL_SIZE]) -> [u8; HILL_SIZE] {
+    let mut res = [0u8; HILL_SIZE];
+    for (r, row) in res.iter_mut().zip(mat) {
+        *r = row.iter().zip(v).fold(0, |sum, (&m, &x)| sum ^ gf_mul(m, x));
+    }
+    res
+}
+
+fn key_matrix(key: &[u8]) -> Matrix {
+    let mut m = [[0u8; HILL_SIZE]; HILL_SIZE];
+    for (i, row) in m.iter_mut().enumerate() {
+        row.copy_from_slice(&key[i * HILL_SIZE..(i + 1) * HILL_SIZE]);
+    }
+    m
+}
+
+fn pad_bytes(mut v: Vec<u8>) -> Vec<u8> {
+    let pad = HILL_SIZE - (v.len() % HILL_SIZE);
+    if pad != HILL_SIZE {
+        v.extend(vec![0u8; pad]);
+    }
+    v
+}
+
+fn unpad_bytes(mut v: Vec<u8>) -> Vec<u8> {
+    while let Some(&0) = v.last() { v.pop(); }
+    v
+}
+
+fn apply_blocks(mat: &Matrix, input: &[u8]) -> Vec<u8> {
+    let mut out = vec![];
+    for chunk in input.chunks(HILL_SIZE) {
+        let mut v = [0u8; HILL_SIZE];
+        v[..chunk.len()].copy_from_slice(chunk);
+        out.extend_from_slice(&matrix_mul_vec(mat, &v));
+    }
+    out
+}
+
+impl EncryptBytes for HillGf256Cipher {
+    fn encrypt_bytes(key: &Key<Self>, message: Bytes) -> Bytes {
+        let mat = key_matrix(key);
+        let caesar = key[KEY_SIZE-1];
+        let out = apply_blocks(&mat, &pad_bytes(message.to_vec()));
+        let encrypted: Vec<u8> = out.iter().map(|&b| b.wrapping_add(caesar)).collect();
+        Bytes::from(encrypted)
+    }
+    fn gen_keys(mut rng: impl CryptoRng) -> Vec<u8> {
+        // every nonzero determinant is invertible, so this rarely loops
+        loop {
+            let mut key = vec![0u8; KEY_SIZE];
+            rng.fill_bytes(&mut key);
+            if matrix_det(&key_matrix(&key)) != 0 {
+                return key;
+            }
+        }
+    }
+}
+
+impl DecryptBytes for HillGf256Cipher {
+    type DecryptError = HillGf256Error;
+    fn decrypt_bytes(key: &Key<Self>, message: Bytes) -> Result<Bytes, Self::DecryptError> {
+        let inv = matrix_inv(&key_matrix(key)).ok_or(HillGf256Error::SingularKey)?;
+        if !message.len().is_multiple_of(HILL_SIZE) {
+            return Err(HillGf256Error::Length(message.len()));
+        }
+        let caesar = key[KEY_SIZE-1];
+        let caesar_dec: Vec<u8> = message.iter().map(|&b| b.wrapping_sub(caesar)).collect();
+        Ok(Bytes::from(unpad_bytes(apply_blocks(&inv, &caesar_dec))))
+    }
+}
+
+#[cfg(feature = "cli")]
+impl KeyCheck for HillGf256Cipher {
+    fn check(key: &Key<Self>) -> KeyInspection {
+        let mat = key_matrix(key);
+        let det = matrix_det(&mat);
+        let inv = matrix_inv(&mat);
+        let caesar = key[KEY_SIZE-1];
+
+        let mut inspection = KeyInspection::default();
+        inspection
+            .field("matrix", FieldValue::Matrix(mat.iter().map(|row| row.to_vec()).collect()))
+            .field("determinant in GF(2^8)", FieldValue::Number(det as u64))
+            .field("invertible", FieldValue::Flag(inv.is_some()));
+        match inv {
+            Some(inv) => inspection.field("inverse", FieldValue::Matrix(inv.iter().map(|row| row.to_vec()).collect())),
+            None => inspection.error("determinant is 0, so the matrix has no inverse"),
+        };
+        inspection.field("caesar shift", FieldValue::Number(caesar as u64));
+        if caesar == 0 {
+            inspection.warning("the Caesar shift is 0");
+        }
+        inspection
+    }
+}
+
+#[cfg(feature = "cli")]
+impl ChallengeCipher for HillGf256Cipher {
+    const NAME: &'static str = "hill-gf256";
+
+    type Protection = Affine;
+
+    fn inspect_key(key: &Key<Self>) -> Option<KeyInspection> {
+        Some(Self::check(key))
+    }
+
+    fn pieces(key: &Key<Self>, decrypt: bool) -> Option<Pieces> {
+        Some(Pieces::blockwise::<Self>(key, HILL_SIZE, decrypt, |key, _| Some(*key)))
+    }
+
+    fn secret() -> crate::traits::Secret<Self> {
+        let key_b64 = "GBiLDaSr2M0VH+7RCMUzSSnTezmmadbGEVkgwgDVCYd7xvPN39BSFkP/qai36CSknZ1D8h4x7Gk2/znHQoYdOlw=";
+        let key_vec = base64::engine::general_purpose::STANDARD.decode(key_b64).unwrap();
+        let key = *Key::<Self>::from_slice(&key_vec);
+        let encrypted_message = "ioHq1wabbnRgILaz9tmP7fc1F0s6SLlGw95J/URFRjNeFDc/EP4A020uH/JyEPD43mcq6XTvHcNw3XzGO7Grv5GOHGclYNJH+67wGHp4ipuAw0zVjDDKFUp4x526BzSL2OuxpeI7GFRkSVIYqm6H0CrcpA5wPr+GZqqoDCj89u24xX5rGfxwf6iPablwGh7NQ32ei/n0vE+BYhuvO/44M5x1CbnKRQr+6Xq6n/8x1rLx6PSyRmoc5LFnL7YE8kO2I00kvCeo/hqRaLNZIV5hLS1Opcc6TSbhOCNnxO8C3dhsP86ibrfaxJyj4yLylE3cewKeswWu7mVNMwQ9qIxH7Ei4ekEG11AyNK2lPiCgKU3LHSC4AOvuPxIV5Dvzgt1mr3lDBwsx1GufS142DhbeGsrI4lKUk/8+CLf0Mt6QX4CblrkSV9RoHMrORDVY+AzjUQrvpxWvx4E/fD5j2Yf9Woxdn9laMUsBbmpQ3u9G7NI3A5ZjvDgvY+W+8CReOuxcHTxMFjA9rS6tQzD2Fs4pdhCJpsUuECt5fDe+gZXNnMM4UI9WhnT6pR6Npai36NzyE4boDelKUFXhQ9ocrEJKf0x6WsfQSF48rlbLyLcAvH/0PLgmul2/iuixW+SJ+h+3";
+        crate::traits::Secret {
+            key,
+            encrypted_message: BASE64_STANDARD.decode(encrypted_message).map(Bytes::from).unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(feature = "cli")]
+pub fn main() {
+    let cli = crate::cli::cli();
+    HillGf256Cipher::execute(cli, rand::rng());
+}
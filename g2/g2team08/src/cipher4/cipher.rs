@@ -0,0 +1,234 @@
+use bytes::Bytes;
+use cipher::{KeyInit, KeySizeUser};
+use cipher::{typenum, Key};
+use crate::rng::CryptoRng;
+#[cfg(feature = "cli")]
+use base64::{prelude::*};
+
+use crate::arx::{self, ArxCore, State, SIGMA, words};
+#[cfg(feature = "cli")]
+use crate::envelope::Mode;
+#[cfg(feature = "cli")]
+use crate::keycheck::{check_stream_key, KeyCheck, KeyInspection};
+#[cfg(feature = "cli")]
+use crate::protection::SharedKeystream;
+#[cfg(feature = "cli")]
+use crate::stream::Pieces;
+#[cfg(feature = "cli")]
+use crate::traits::ChallengeCipher;
+use crate::traits::{DecryptBytes, EncryptBytes};
+
+use alloc::{vec, vec::Vec};
+
+const SALSA20_KEY_SIZE: usize = 32;
+const SALSA20_NONCE_SIZE: usize = 8;
+const SALSA20_COUNTER_SIZE: usize = 8;
+const SALSA20_TOTAL_KEY_SIZE: usize = SALSA20_KEY_SIZE + SALSA20_NONCE_SIZE + SALSA20_COUNTER_SIZE;
+
+#[derive(Debug, Clone)]
+pub struct Salsa20Error;
+impl core::fmt::Display for Salsa20Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Salsa20 cipher error: the message runs past the end of the keystream")
+    }
+}
+impl core::error::Error for Salsa20Error {}
+
+/// Salsa20 reduced to `ROUNDS` rounds
+#[derive(Clone)]
+pub struct SalsaCipher<const ROUNDS: usize>;
+
+pub type Salsa20Cipher = SalsaCipher<20>;
+pub type Salsa2012Cipher = SalsaCipher<12>;
+
+impl<const ROUNDS: usize> KeySizeUser for SalsaCipher<ROUNDS> {
+    type KeySize = typenum::U48;
+    fn key_size() -> usize { SALSA20_TOTAL_KEY_SIZE }
+}
+
+impl<const ROUNDS: usize> KeyInit for SalsaCipher<ROUNDS> {
+    fn new(_: &Key<Self>) -> Self { SalsaCipher{} }
+}
+
+fn quarter_round(state: &mut State, a: usize, b: usize, c: usize, d: usize) {
+    state[b] ^= state[a].wrapping_add(state[d]).rotate_left(7);
+    state[c] ^= state[b].wrapping_add(state[a]).rotate_left(9);
+    state[d] ^= state[c].wrapping_add(state[b]).rotate_left(13);
+    state[a] ^= state[d].wrapping_add(state[c]).rotate_left(18);
+}
+
+impl<const ROUNDS: usize> ArxCore for SalsaCipher<ROUNDS> {
+    const ROUNDS: usize = ROUNDS;
+
+    fn double_round(state: &mut State) {
+        // columns, starting from the diagonal
+        quarter_round(state, 0, 4, 8, 12);
+        quarter_round(state, 5, 9, 13, 1);
+        quarter_round(state, 10, 14, 2, 6);
+        quarter_round(state, 15, 3, 7, 11);
+        // rows
+        quarter_round(state, 0, 1, 2, 3);
+        quarter_round(state, 5, 6, 7, 4);
+        quarter_round(state, 10, 11, 8, 9);
+        quarter_round(state, 15, 12, 13, 14);
+    }
+}
+
+pub fn salsa_block<const ROUNDS: usize>(key: &[u8; SALSA20_KEY_SIZE], nonce: &[u8; SALSA20_NONCE_SIZE], counter: u64) -> [u8; 64] {
+    // constants on the diagonal, key around them, nonce and counter in the middle
+    let key = words::<8>(key);
+    let nonce = words::<2>(nonce);
+    let state = [
+        SIGMA[0], key[0], key[1], key[2],
+        key[3], SIGMA[1], nonce[0], nonce[1],
+        counter as u32, (counter >> 32) as u32, SIGMA[2], key[4],
+        key[5], key[6], key[7], SIGMA[3],
+    ];
+    arx::block::<SalsaCipher<ROUNDS>>(&state)
+}
+
+/// `message` xored with the keystream of `key`, an error where the block
+/// counter would wrap around past 2^64 - 1
+fn apply_keystream<const ROUNDS: usize>(key: &Key<SalsaCipher<ROUNDS>>, message: &[u8]) -> Result<Bytes, Salsa20Error> {
+    let key_array: [u8; SALSA20_KEY_SIZE] = key[..SALSA20_KEY_SIZE].try_into().unwrap();
+    let nonce_array: [u8; SALSA20_NONCE_SIZE] = key[SALSA20_KEY_SIZE..SALSA20_KEY_SIZE + SALSA20_NONCE_SIZE].try_into().unwrap();
+    let counter = u64::from_le_bytes(key[SALSA20_KEY_SIZE + SALSA20_NONCE_SIZE..].try_into().unwrap());
+
+    if !arx::fits(counter, message.len(), u64::MAX) {
+        return Err(Salsa20Error);
+    }
+    let output = arx::apply_keystream(message, |block_index| {
+        salsa_block::<ROUNDS>(&key_array, &nonce_array, counter + block_index)
+    });
+    Ok(Bytes::from(output))
+}
+
+impl<const ROUNDS: usize> EncryptBytes for SalsaCipher<ROUNDS> {
+    /// panics where the block counter would wrap around, which
+    /// `decrypt_bytes` and `try_encrypt` report as an error
+    fn encrypt_bytes(key: &Key<Self>, message: Bytes) -> Bytes {
+        apply_keystream::<ROUNDS>(key, &message).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn gen_keys(mut rng: impl CryptoRng) -> Vec<u8> {
+        let mut key = vec![0u8; SALSA20_TOTAL_KEY_SIZE];
+        rng.fill_bytes(&mut key);
+        key
+    }
+}
+
+impl<const ROUNDS: usize> DecryptBytes for SalsaCipher<ROUNDS> {
+    type DecryptError = Salsa20Error;
+
+    fn decrypt_bytes(key: &Key<Self>, message: Bytes) -> Result<Bytes, Self::DecryptError> {
+        apply_keystream::<ROUNDS>(key, &message)
+    }
+}
+
+#[cfg(feature = "cli")]
+impl<const ROUNDS: usize> KeyCheck for SalsaCipher<ROUNDS> {
+    fn check(key: &Key<Self>) -> KeyInspection {
+        let counter = u64::from_le_bytes(key[SALSA20_KEY_SIZE + SALSA20_NONCE_SIZE..].try_into().unwrap());
+        let mut inspection = KeyInspection::default();
+        check_stream_key(
+            &mut inspection,
+            &key[..SALSA20_KEY_SIZE],
+            &key[SALSA20_KEY_SIZE..SALSA20_KEY_SIZE + SALSA20_NONCE_SIZE],
+            counter,
+            (1u128 << 64) - counter as u128,
+        );
+        inspection
+    }
+}
+
+#[cfg(feature = "cli")]
+fn secret<const ROUNDS: usize>(key_b64: &str, encrypted_message: &str) -> crate::traits::Secret<SalsaCipher<ROUNDS>> {
+    let key_vec = BASE64_STANDARD.decode(key_b64).unwrap();
+    crate::traits::Secret {
+        key: *Key::<SalsaCipher<ROUNDS>>::from_slice(&key_vec),
+        encrypted_message: BASE64_STANDARD.decode(encrypted_message).map(Bytes::from).unwrap_or_default(),
+    }
+}
+
+/// the key whose keystream starts `offset` bytes, `offset / 64` blocks, into
+/// the one of `key`, none past the end of that keystream
+#[cfg(feature = "cli")]
+fn key_at<const ROUNDS: usize>(key: &Key<SalsaCipher<ROUNDS>>, offset: u64) -> Option<Key<SalsaCipher<ROUNDS>>> {
+    let mut key = *key;
+    let counter_bytes = &mut key[SALSA20_KEY_SIZE + SALSA20_NONCE_SIZE..];
+    let counter = u64::from_le_bytes((&*counter_bytes).try_into().unwrap());
+    counter_bytes.copy_from_slice(&counter.checked_add(offset / 64)?.to_le_bytes());
+    Some(key)
+}
+
+#[cfg(feature = "cli")]
+impl ChallengeCipher for Salsa20Cipher {
+    const NAME: &'static str = "salsa20";
+
+    type Protection = SharedKeystream;
+
+    fn envelope_mode(key: &Key<Self>) -> (Mode, Vec<u8>) {
+        (Mode::Stream, key[SALSA20_KEY_SIZE..].to_vec())
+    }
+
+    fn inspect_key(key: &Key<Self>) -> Option<KeyInspection> {
+        Some(Self::check(key))
+    }
+
+    fn try_encrypt(key: &Key<Self>, message: Bytes) -> Result<Bytes, Salsa20Error> {
+        apply_keystream::<20>(key, &message)
+    }
+
+    fn pieces(key: &Key<Self>, decrypt: bool) -> Option<Pieces> {
+        Some(Pieces::blockwise::<Self>(key, 64, decrypt, key_at::<20>))
+    }
+
+    fn secret() -> crate::traits::Secret<Self> {
+        secret(
+            "myXCFZ/iG7LQ2sZfHj4Y2v/a/rzFcExakqRYxC+B3fDYZDu7PMgzL/IpoyAWLghi",
+            "fb4VnJ4QxtKNXR4whJl7Ze7ElN8L3CbXtKHuW1ZHtH+Bp/9pW6duZYvR56hvwpMaVtz0z93ZFG88xyP6SnUE8B0uxsjeJTKH1ManqfGipVgz3BGXBrOEDkIwbdiRK2cKJ60tK9uOcUNHDpYRJlXMCCjeQC282uvxfOKCJ6EPc7NfgGjDazV+1mrvomVIYVHoq8raKrMiVeqh7vN5rqQxY1ahBkXHvT8aeJPGwOqZ+OjlErtZGlXcGNZbMxwN8rGTSBvxgsRwUFPnxZUR/54r+PoZJr4/lDzMAnA/IM8QEaU0ToiKWMXNsMW7EFuqCZpOwA13dsgTMIxCc0DVAkoc792hU5qRRFySoARIhux+gWNWDVN7JYA9XQ3nKueuh4igLAIZi/T2ZwN6zLlTlNw53CMl3X5dA3ynZUH4X2NXuHslOhHeBItqsksyIHOf5h0ocZ/PJcYUzgq7Qde+KxnA0BYZE98acY/ALBiwGxz3I+eQ1oCgbWtbodRxDhhdNApLt+EmzjVnQIiu1+zVF9z36QJFhv/3CkcB2E25OcZLJcLsHd4oupaCTb/+hitW8bLtdDqYefl/rZzkdEDYnNpNoHi/OvhBgyfWe4Hdx+IAOAkpjbvymdXx4i/P7chd",
+        )
+    }
+}
+
+#[cfg(feature = "cli")]
+impl ChallengeCipher for Salsa2012Cipher {
+    const NAME: &'static str = "salsa20/12";
+
+    type Protection = SharedKeystream;
+
+    fn envelope_mode(key: &Key<Self>) -> (Mode, Vec<u8>) {
+        (Mode::Stream, key[SALSA20_KEY_SIZE..].to_vec())
+    }
+
+    fn inspect_key(key: &Key<Self>) -> Option<KeyInspection> {
+        Some(Self::check(key))
+    }
+
+    fn try_encrypt(key: &Key<Self>, message: Bytes) -> Result<Bytes, Salsa20Error> {
+        apply_keystream::<12>(key, &message)
+    }
+
+    fn pieces(key: &Key<Self>, decrypt: bool) -> Option<Pieces> {
+        Some(Pieces::blockwise::<Self>(key, 64, decrypt, key_at::<12>))
+    }
+
+    fn secret() -> crate::traits::Secret<Self> {
+        secret(
+            "i1mPbCZvAfF/6N0t9rHyT+DOQ/0ys8gLAfMA2nc6rBTM6/2LvkQQmKnc1IfGQl+Y",
+            "DYzHnONBsTqiwiV79GP48DmZau6juxEnBPDbHCoMa2wdcEHvQNiPDAab84Cbu/5UB1NvkWafZxyDWbNiEKi9WYTpwraGqF0Oa3/z33pvGw5QxT8geBsqOpOsjJFBttxjHcAj1Mb0UPtfCk6MJZjVEMMnpuIOaex2Aus/ilgMwuYDYQyIUDWMhRKtAW2iFH2oW/xQHMRj/O3gkcamn4nGlIqIxLdxAJe6EiMONHwN3OBOkvwYcT0w3QZa3gI/tZ9n+OS52SnKlUbXhLC7LPnm6lbQrWcASCzNbJicRm/HvL2AEby06rQQvPaVCsGCGkM5e7ewaejuFjHKlUVhmX+w5npOApck27rpalzkZ202KmfznM7B3RNZyf7uDvekDFkw/7737kTjXcm97/HaXmENIrTH",
+        )
+    }
+}
+
+#[cfg(feature = "cli")]
+pub fn main() {
+    let cli = crate::cli::cli();
+    Salsa20Cipher::execute(cli, rand::rng());
+}
+
+#[cfg(feature = "cli")]
+pub fn main12() {
+    let cli = crate::cli::cli();
+    Salsa2012Cipher::execute(cli, rand::rng());
+}
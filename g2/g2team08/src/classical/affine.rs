@@ -0,0 +1,66 @@
+use bytes::Bytes;
+use cipher::{typenum, Key};
+use crate::rng::CryptoRng;
+use rand::Rng;
+
+use super::{bytewise, classical_cipher, map_symbols, modinv, N};
+use crate::keycheck::{FieldValue, KeyCheck, KeyInspection};
+use crate::traits::{DecryptBytes, EncryptBytes};
+
+classical_cipher!(
+    AffineCipher, AffineError, typenum::U2, "affine",
+    key: "Ajc=",
+    challenge: "Q0gyRm1IREpIMEo7Mkx1REZtODhSbXE2OyFOOHVzeXVzcEgwbUhtRjI7eTh1LG07MjspIUZGdUZGMiE7IXdteSEhc3chREhKO3VwLEpGSG91MjtObTtIIXdtTjJ3dXJBIU51THVEODJISDh1NjshTjtIMHV3dXU4Mjt5RiFETDJ1TkYhd0ZKcTBtLG07LG1Sb3UhOzAyRncyREZIdTtIdUQyO3ltO3UyeTBvIUpEMCEhc3BIMDJGSERKSDAyRkYhTnU4OHcyUHVzMjtIMHUsMjtzRiF3SDB1RkpERCFKO3MyO3l3bSwyODJ1RnBIMG1IMHUyRnEhO0Yyc3VEdXNIMHVEMnkwSHdKOClEISl1REhSIXdGISx1ITt1IUQhSDB1RCF3SDB1MkRzbUp5MEh1REZy",
+    protection: crate::protection::SharedSymbols,
+    pieces: bytewise::<AffineCipher>,
+    inspect: AffineCipher::check
+);
+
+/// x -> a * x + b, where `a` must be coprime to the alphabet size
+fn coefficients(key: &[u8]) -> (usize, usize) {
+    (key[0] as usize % N, key[1] as usize % N)
+}
+
+impl EncryptBytes for AffineCipher {
+    fn encrypt_bytes(key: &Key<Self>, message: Bytes) -> Bytes {
+        let (a, b) = coefficients(key);
+        map_symbols(&message, |x| a * x + b).into()
+    }
+
+    fn gen_keys(mut rng: impl CryptoRng) -> Vec<u8> {
+        loop {
+            let a = rng.random_range(2..N);
+            if modinv(a).is_some() {
+                return vec![a as u8, rng.random_range(0..N) as u8];
+            }
+        }
+    }
+}
+
+impl DecryptBytes for AffineCipher {
+    type DecryptError = AffineError;
+
+    fn decrypt_bytes(key: &Key<Self>, message: Bytes) -> Result<Bytes, Self::DecryptError> {
+        let (a, b) = coefficients(key);
+        let a_inv = modinv(a).ok_or(AffineError)?;
+        Ok(map_symbols(&message, |y| (y + N - b) * a_inv).into())
+    }
+}
+
+impl KeyCheck for AffineCipher {
+    fn check(key: &Key<Self>) -> KeyInspection {
+        let (a, b) = coefficients(key);
+        let mut inspection = KeyInspection::default();
+        inspection
+            .field("a", FieldValue::Number(a as u64))
+            .field("b", FieldValue::Number(b as u64))
+            .field("invertible", FieldValue::Flag(modinv(a).is_some()));
+        if modinv(a).is_none() {
+            inspection.error(format!("a = {} is not coprime to {}, so it has no inverse", a, N));
+        }
+        if a == 1 {
+            inspection.warning("a is 1, only the shift b encrypts");
+        }
+        inspection
+    }
+}
@@ -0,0 +1,40 @@
+use bytes::Bytes;
+use cipher::{typenum, Key};
+use crate::rng::CryptoRng;
+use rand::Rng;
+
+use super::{bytewise, classical_cipher, map_symbols, N};
+use crate::traits::{DecryptBytes, EncryptBytes};
+
+classical_cipher!(
+    CaesarCipher, CaesarError, typenum::U1, "caesar",
+    key: "Cw==",
+    challenge: "Tmx3d3hwVDNzeGxwd0ZkenhwOXBsMjNscnpFeXA2cDJ4dHlvc3o3d3p5cjAycG50M3B3OUVzbDZ0eXJ3dDQ0d3B6Mnl6eHp5cDl0eXg5MDUyM3BFbHlveXo0c3R5cjBsMjR0bjV3bDI0enR5NHAycDM0eHB6eTNzejJwRVQ0c3o1cnM0VDd6NXdvM2x0d2xtejU0bHd0NDR3cGx5bzNwcDRzcDdsNHAyOTBsMjR6cTRzcDd6MndvRg==",
+    protection: crate::protection::SharedSymbols,
+    pieces: bytewise::<CaesarCipher>
+);
+
+/// the key byte is the shift, taken modulo the alphabet size
+fn shift(key: &[u8]) -> usize {
+    key[0] as usize % N
+}
+
+impl EncryptBytes for CaesarCipher {
+    fn encrypt_bytes(key: &Key<Self>, message: Bytes) -> Bytes {
+        let k = shift(key);
+        map_symbols(&message, |x| x + k).into()
+    }
+
+    fn gen_keys(mut rng: impl CryptoRng) -> Vec<u8> {
+        vec![rng.random_range(1..N) as u8]
+    }
+}
+
+impl DecryptBytes for CaesarCipher {
+    type DecryptError = CaesarError;
+
+    fn decrypt_bytes(key: &Key<Self>, message: Bytes) -> Result<Bytes, Self::DecryptError> {
+        let k = shift(key);
+        Ok(map_symbols(&message, |x| x + N - k).into())
+    }
+}
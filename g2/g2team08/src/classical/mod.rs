@@ -0,0 +1,158 @@
+//! Classical ciphers over the message alphabet of [`crate::cli::ALPHABET`].
+//!
+//! Symbols are numbered by their position in the alphabet. Bytes outside the
+//! alphabet pass through unchanged, so every byte string round-trips.
+
+pub mod affine;
+pub mod caesar;
+pub mod playfair;
+pub mod substitution;
+pub mod transposition;
+pub mod vigenere;
+
+use crate::cli::ALPHABET;
+use crate::stream::Pieces;
+
+/// number of symbols in the alphabet
+pub const N: usize = ALPHABET.len();
+
+/// position of `byte` in the alphabet
+pub fn index(byte: u8) -> Option<usize> {
+    static INDEX: std::sync::OnceLock<[Option<u8>; 256]> = std::sync::OnceLock::new();
+    INDEX.get_or_init(|| {
+        let mut index = [None; 256];
+        for (i, &b) in ALPHABET.iter().enumerate() {
+            index[b as usize] = Some(i as u8);
+        }
+        index
+    })[byte as usize]
+        .map(usize::from)
+}
+
+pub fn symbol(index: usize) -> u8 {
+    ALPHABET[index % N]
+}
+
+/// replaces every alphabet symbol `x` of the message by `f(x)`
+pub fn map_symbols(message: &[u8], mut f: impl FnMut(usize) -> usize) -> Vec<u8> {
+    message
+        .iter()
+        .map(|&b| index(b).map_or(b, |i| symbol(f(i))))
+        .collect()
+}
+
+/// inverse of `a` modulo the alphabet size
+pub fn modinv(a: usize) -> Option<usize> {
+    (1..N).find(|&x| a % N * x % N == 1)
+}
+
+/// a uniformly random permutation of `0..n`, Fisher-Yates
+pub fn random_permutation(rng: &mut impl rand::RngCore, n: usize) -> Vec<u8> {
+    use rand::Rng;
+    let mut perm: Vec<u8> = (0..n as u8).collect();
+    for i in (1..n).rev() {
+        perm.swap(i, rng.random_range(0..=i));
+    }
+    perm
+}
+
+/// the rank of every byte of `key` when sorted by value, ties broken by
+/// position; this is `key` itself when it already is a permutation of `0..key.len()`
+pub fn ranks(key: &[u8]) -> Vec<u8> {
+    let mut order: Vec<usize> = (0..key.len()).collect();
+    order.sort_by_key(|&i| (key[i], i));
+    let mut ranks = vec![0u8; key.len()];
+    for (rank, i) in order.into_iter().enumerate() {
+        ranks[i] = rank as u8;
+    }
+    ranks
+}
+
+/// inverse of a permutation of `0..perm.len()` given as bytes
+pub fn invert_permutation(perm: &[u8]) -> Option<Vec<u8>> {
+    let mut inv = vec![u8::MAX; perm.len()];
+    for (i, &p) in perm.iter().enumerate() {
+        let slot = inv.get_mut(p as usize)?;
+        if *slot != u8::MAX {
+            return None;
+        }
+        *slot = i as u8;
+    }
+    Some(inv)
+}
+
+/// [`Pieces`] of one byte each for the ciphers that map every byte on its own
+pub fn bytewise<C: crate::traits::ChallengeCipher>(key: &cipher::Key<C>, decrypt: bool) -> Pieces {
+    Pieces::blockwise::<C>(key, 1, decrypt, |key, _| Some(key.clone()))
+}
+
+/// the error type, key size, `ChallengeCipher` impl and `main` shared by the
+/// classical ciphers; `EncryptBytes` and `DecryptBytes` are up to each cipher,
+/// and so are the [protection](crate::protection) that suits its structure,
+/// the `pieces` of the ciphers that can stream and the `inspect` of the
+/// ciphers whose keys can be invalid
+macro_rules! classical_cipher {
+    (
+        $cipher:ident, $error:ident, $key_size:ty, $name:literal,
+        key: $key:literal,
+        challenge: $challenge:literal,
+        protection: $protection:ty
+        $(, pieces: $pieces:expr)?
+        $(, inspect: $inspect:expr)?
+    ) => {
+        #[derive(Debug, Clone)]
+        pub struct $error;
+        impl std::fmt::Display for $error {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{} cipher error: invalid key", $name)
+            }
+        }
+        impl std::error::Error for $error {}
+
+        #[derive(Clone)]
+        pub struct $cipher;
+
+        impl cipher::KeySizeUser for $cipher {
+            type KeySize = $key_size;
+        }
+
+        impl cipher::KeyInit for $cipher {
+            fn new(_: &cipher::Key<Self>) -> Self { $cipher{} }
+        }
+
+        impl $crate::traits::ChallengeCipher for $cipher {
+            const NAME: &'static str = $name;
+
+            type Protection = $protection;
+
+            fn secret() -> $crate::traits::Secret<Self> {
+                use base64::prelude::*;
+                let key_vec = BASE64_STANDARD.decode($key).unwrap();
+                $crate::traits::Secret {
+                    key: *cipher::Key::<Self>::from_slice(&key_vec),
+                    encrypted_message: BASE64_STANDARD.decode($challenge).map(bytes::Bytes::from).unwrap_or_default(),
+                }
+            }
+
+            $(
+                fn pieces(key: &cipher::Key<Self>, decrypt: bool) -> Option<$crate::stream::Pieces> {
+                    Some($pieces(key, decrypt))
+                }
+            )?
+
+            $(
+                fn inspect_key(key: &cipher::Key<Self>) -> Option<$crate::keycheck::KeyInspection> {
+                    Some($inspect(key))
+                }
+            )?
+        }
+
+        pub fn main() {
+            use $crate::traits::ChallengeCipher;
+            let cli = $crate::cli::cli();
+            $cipher::execute(cli, rand::rng());
+        }
+    };
+}
+
+pub(crate) use classical_cipher;
@@ -0,0 +1,104 @@
+use bytes::Bytes;
+use cipher::{typenum, Key};
+use crate::rng::CryptoRng;
+
+use super::{classical_cipher, index, random_permutation, ranks, symbol, N};
+use crate::traits::{DecryptBytes, EncryptBytes};
+
+pub const ROWS: usize = 3;
+pub const COLUMNS: usize = N / ROWS; // 69 = 3 * 23
+
+classical_cipher!(
+    PlayfairCipher, PlayfairError, typenum::U69, "playfair",
+    key: "FCBBOjIEOCYQGSI9FysFEgofJCxCIxVAOwECNik+PwcONzUdHgA0LRsWESgGCzAJJy4NDEMhLzElGDkPPAgDGhwzEypE",
+    challenge: "LmNBYlAuU1BtemNZRDNQNlRCRENiR1dBLkQuUFBlcXFrUSEySnVpM3JiLkdVUFAuLkRQZS5ERGJLUkRiITN1SjNpYTMuUDNQZVdmTk4/V0cyWDNQclIuYlAuclJiTXI3IVF6NlJLVC5oOyFUV0E/OWg/aU45aztQSzNELlAuV0E/OTY2P2whN2tRaU45a0EuUFNXUGUhZWJ2YzBESmNmUmUhdWh6ZkR2Y3hBVzg4bD9hP2Uhci5iNTVCV0FpM0hQTFBCVDhmRjliNTQyRGI7RDNQUEhXVFQza1dEYmw/bUpiSA==",
+    protection: crate::protection::SharedDigraphs
+);
+
+/// the key lists the symbols of the grid row by row,
+/// any other key is read through its ranks
+struct Grid {
+    cells: Vec<u8>,
+    positions: Vec<u8>,
+}
+
+impl Grid {
+    fn new(key: &[u8]) -> Self {
+        let cells = ranks(key);
+        let mut positions = vec![0u8; N];
+        for (p, &x) in cells.iter().enumerate() {
+            positions[x as usize] = p as u8;
+        }
+        Grid { cells, positions }
+    }
+
+    fn position(&self, x: usize) -> (usize, usize) {
+        let p = self.positions[x] as usize;
+        (p / COLUMNS, p % COLUMNS)
+    }
+
+    fn at(&self, row: usize, column: usize) -> usize {
+        self.cells[row % ROWS * COLUMNS + column % COLUMNS] as usize
+    }
+
+    /// `step` is 1 to encrypt and -1 to decrypt
+    ///
+    /// Playfair on an arbitrary rectangle, kept length-preserving: a doubled
+    /// symbol and a trailing single symbol move one step diagonally instead
+    /// of being split or padded with a filler.
+    fn pair(&self, a: usize, b: usize, step: isize) -> (usize, usize) {
+        let shift = |v: usize, n: usize| (v as isize + step).rem_euclid(n as isize) as usize;
+        let ((ra, ca), (rb, cb)) = (self.position(a), self.position(b));
+        if a == b {
+            let x = self.at(shift(ra, ROWS), shift(ca, COLUMNS));
+            (x, x)
+        } else if ra == rb {
+            (self.at(ra, shift(ca, COLUMNS)), self.at(rb, shift(cb, COLUMNS)))
+        } else if ca == cb {
+            (self.at(shift(ra, ROWS), ca), self.at(shift(rb, ROWS), cb))
+        } else {
+            (self.at(ra, cb), self.at(rb, ca))
+        }
+    }
+
+    fn single(&self, a: usize, step: isize) -> usize {
+        self.pair(a, a, step).0
+    }
+
+    /// pairs up consecutive alphabet symbols, other bytes stay in place
+    fn apply(&self, message: &[u8], step: isize) -> Vec<u8> {
+        let mut out = message.to_vec();
+        let symbols: Vec<usize> = (0..message.len()).filter(|&i| index(message[i]).is_some()).collect();
+        let value = |i: usize| index(message[i]).unwrap();
+        for pair in symbols.chunks(2) {
+            match *pair {
+                [i, j] => {
+                    let (x, y) = self.pair(value(i), value(j), step);
+                    out[i] = symbol(x);
+                    out[j] = symbol(y);
+                }
+                [i] => out[i] = symbol(self.single(value(i), step)),
+                _ => unreachable!(),
+            }
+        }
+        out
+    }
+}
+
+impl EncryptBytes for PlayfairCipher {
+    fn encrypt_bytes(key: &Key<Self>, message: Bytes) -> Bytes {
+        Grid::new(key).apply(&message, 1).into()
+    }
+
+    fn gen_keys(mut rng: impl CryptoRng) -> Vec<u8> {
+        random_permutation(&mut rng, N)
+    }
+}
+
+impl DecryptBytes for PlayfairCipher {
+    type DecryptError = PlayfairError;
+
+    fn decrypt_bytes(key: &Key<Self>, message: Bytes) -> Result<Bytes, Self::DecryptError> {
+        Ok(Grid::new(key).apply(&message, -1).into())
+    }
+}
@@ -0,0 +1,37 @@
+use bytes::Bytes;
+use cipher::{typenum, Key};
+use crate::rng::CryptoRng;
+
+use super::{bytewise, classical_cipher, invert_permutation, map_symbols, random_permutation, ranks, N};
+use crate::traits::{DecryptBytes, EncryptBytes};
+
+classical_cipher!(
+    SubstitutionCipher, SubstitutionError, typenum::U69, "substitution",
+    key: "MRweBD4NJhkCFR8WCCsdLwobOgEaLT1DEg4UMDsqIiw2IDNBAzQHC0QhKSQMEBM1JSgFLjw/EUA4IycYBkIyAAkXOTcP",
+    challenge: "Q0xIRkYpVEwyaWtVTHFIKWtpMVQwTGlrVXcwaUZpVWtNSEZzVVFnaWsyVTFpSGlNKUhpVXExejdpUWdVUUNnVTFpd2lpTFFUa0x6TDIpMWlrekxIRkh6THFpMWlrTXpMN2lBOWdpTGkxaWtGKVRzaWkwMHpEaTdrelF6N3p1ekwyVUxGKUxpeWdpUSkwcUhpeT9UTVFraUhpSHdpa1FnVVFVMDBRZ2loaSloMGl6TFFnek1sKWswcWdVMWlMKVFnVXFRZ2lVcTFVTFFVMmlNUWdVUUYpVGdVMWlnVXFBWmlxenFMKVFNVUZVTEZIKWtpeXdUUWxpZ1UxaVUwbFVGTXdpaUxUTFRNVFUwMEY3KUhIVEx6N1VRejFpekxVa2lNaWsxaXFsVUZ5VUxxQ1RMcWlrTVEpKXFRZ1VRZ2lIaVVMUVUya2lVUXFpVTBIKWtpUWdVTFFnVVFBQ0w3KUxNaXBUaUw3aXlDVUh6TDcwekxpcVEpa2lNaWsxaVUwMD9UcTJpSGlMUU15VWdVd3pRUWdVUWdVTSloaUxpcVRoSFVMRjdUa3opVE1MVVFUa2lNUSlIaVVMcVUwTSlIVXFpSGlRZ2kxejdRekgpc0wpUVVzaWwxaVFpa1VMdylraU1B",
+    protection: crate::protection::SharedSymbols,
+    pieces: bytewise::<SubstitutionCipher>
+);
+
+// the key is a permutation of the alphabet: symbol x becomes symbol key[x],
+// any other key is read through its ranks
+
+impl EncryptBytes for SubstitutionCipher {
+    fn encrypt_bytes(key: &Key<Self>, message: Bytes) -> Bytes {
+        let perm = ranks(key);
+        map_symbols(&message, |x| perm[x] as usize).into()
+    }
+
+    fn gen_keys(mut rng: impl CryptoRng) -> Vec<u8> {
+        random_permutation(&mut rng, N)
+    }
+}
+
+impl DecryptBytes for SubstitutionCipher {
+    type DecryptError = SubstitutionError;
+
+    fn decrypt_bytes(key: &Key<Self>, message: Bytes) -> Result<Bytes, Self::DecryptError> {
+        let inv = invert_permutation(&ranks(key)).ok_or(SubstitutionError)?;
+        Ok(map_symbols(&message, |y| inv[y] as usize).into())
+    }
+}
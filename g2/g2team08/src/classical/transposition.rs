@@ -0,0 +1,63 @@
+use bytes::Bytes;
+use cipher::{typenum, Key};
+use crate::rng::CryptoRng;
+use rand::Rng;
+
+use super::classical_cipher;
+use crate::traits::{DecryptBytes, EncryptBytes};
+
+pub const MAX_COLUMNS: usize = 16;
+
+classical_cipher!(
+    TranspositionCipher, TranspositionError, typenum::U17, "transposition",
+    key: "DgMKCwkIAQcPAgwNAAYOBAU=",
+    challenge: "dGwsYWtoLGhlZHRzYWl5c3Zkc2dvbG91Q2JkZ29laWR0YXNyaHR0eWlmVG9mZWxtZGVhd29pZXZ5cG9uZWFyaHdvcmx2aWNob3RuZWVhaWNvaWRzZWF0aWVuLG5kdW5ob3Rka2FlYWluZXVyb2V5ZXVzd2xvdGltb2hyaG1wcmhydGQudXNzeWRma2xlLmV0ZXRJc29odGF0ZW9kdmNvY2xzYWV0aC5oc2llb25oaWV5ZGhlSWFtY2VydW5JYXNzcm9zZXNuYmhzYW9DZWVnZGFkaGU=",
+    protection: crate::protection::SharedSymbols
+);
+
+/// the first key byte selects the number of columns, the next bytes rank them:
+/// columns are read out by increasing rank, ties broken by position
+fn column_order(key: &[u8]) -> Vec<usize> {
+    let columns = key[0] as usize % MAX_COLUMNS + 1;
+    let mut order: Vec<usize> = (0..columns).collect();
+    order.sort_by_key(|&c| (key[1 + c], c));
+    order
+}
+
+/// positions of the message in the order they appear in the ciphertext,
+/// the message is written row by row and the last row may be incomplete
+fn reading_order(len: usize, order: &[usize]) -> Vec<usize> {
+    let columns = order.len();
+    order
+        .iter()
+        .flat_map(|&c| (c..len).step_by(columns))
+        .collect()
+}
+
+impl EncryptBytes for TranspositionCipher {
+    fn encrypt_bytes(key: &Key<Self>, message: Bytes) -> Bytes {
+        reading_order(message.len(), &column_order(key))
+            .into_iter()
+            .map(|i| message[i])
+            .collect()
+    }
+
+    fn gen_keys(mut rng: impl CryptoRng) -> Vec<u8> {
+        let columns = rng.random_range(4..=MAX_COLUMNS);
+        let mut key = vec![(columns - 1) as u8];
+        key.extend(super::random_permutation(&mut rng, MAX_COLUMNS));
+        key
+    }
+}
+
+impl DecryptBytes for TranspositionCipher {
+    type DecryptError = TranspositionError;
+
+    fn decrypt_bytes(key: &Key<Self>, message: Bytes) -> Result<Bytes, Self::DecryptError> {
+        let mut out = vec![0u8; message.len()];
+        for (&i, &b) in reading_order(message.len(), &column_order(key)).iter().zip(message.iter()) {
+            out[i] = b;
+        }
+        Ok(out.into())
+    }
+}
@@ -0,0 +1,81 @@
+use bytes::Bytes;
+use cipher::{typenum, Key};
+use crate::rng::CryptoRng;
+use rand::Rng;
+
+use super::{classical_cipher, index, symbol, N};
+use crate::stream::Pieces;
+use crate::traits::{DecryptBytes, EncryptBytes};
+
+pub const MAX_PERIOD: usize = 16;
+
+classical_cipher!(
+    VigenereCipher, VigenereError, typenum::U17, "vigenere",
+    key: "BjFDO0EnBxAjNjQtQkQOBDY=",
+    challenge: "NFlmbFVtcVNnYmVBenFYY1FoSGgxT2lVOEEydVh3a2pEaDVWd1ZXSXAxZWdpcUpvcVZub2VKcDlZbW1qU2ghckNsYU41OU5nZGNTaDhPbFNrSm0sWWdlakV1OU5jRVhIdjNZaW9vRHYsWWMxUERsO09kVWQ/a3RPcVNrUmw3S2JqZD8wOU5jWHFPaXFUYm1XT2pxWHBvZUpuNFRZZGVKMDdPZWthU3A5Tlk7bkF1c05lWW5IQTtObVhXKWl1S2xRY0sydVhsVW9PcDNaZlVlTm1xU2didWdoM0pxWGFEaHRHbGRrUXVzS2Jqa0RsN05zaVg/dXRaZlFwT291SW1raCl1NFplZWtKc3liZ2RjRXU5TmNpV0lseFVzaWFTcDlOZllpaGF4T3Fma09wOU9tZGtCaHZMWVluT29xSmxlc0hoOFpjVHBEeXVLYlF1T0FxVGJka1B2M1J3amRBbyxZWlFqKWgzSnVZYkEweEtraWFIMnVZOFJxUGgxUnJYYUlsMkhjaG9LbTlOY1luQmgyT2pvV0preFVzaWFEdjFKOG1hTmw1R2dkYlFzMWVhZWpPanlVc2lrQnA5cg==",
+    protection: crate::protection::AlignedSymbols,
+    pieces: pieces
+);
+
+/// the first key byte selects the period, the next `period` bytes are the shifts
+pub fn shifts(key: &[u8]) -> Vec<usize> {
+    let period = key[0] as usize % MAX_PERIOD + 1;
+    key[1..=period].iter().map(|&k| k as usize % N).collect()
+}
+
+/// shifts every alphabet symbol by the next shift of the key,
+/// other bytes neither change nor use up a shift
+pub fn apply(message: &[u8], shifts: &[usize]) -> Vec<u8> {
+    let mut position = 0;
+    message
+        .iter()
+        .map(|&b| match index(b) {
+            Some(x) => {
+                let y = symbol(x + shifts[position % shifts.len()]);
+                position += 1;
+                y
+            }
+            None => b,
+        })
+        .collect()
+}
+
+/// streams by carrying the position in the key from one piece to the next
+fn pieces(key: &Key<VigenereCipher>, decrypt: bool) -> Pieces {
+    let mut shifts = shifts(key);
+    if decrypt {
+        shifts.iter_mut().for_each(|k| *k = N - *k);
+    }
+    Pieces {
+        block: 1,
+        apply: Box::new(move |_, piece, _| {
+            let output = apply(piece, &shifts);
+            let used = piece.iter().filter(|&&b| index(b).is_some()).count();
+            let period = shifts.len();
+            shifts.rotate_left(used % period);
+            Ok(output)
+        }),
+    }
+}
+
+impl EncryptBytes for VigenereCipher {
+    fn encrypt_bytes(key: &Key<Self>, message: Bytes) -> Bytes {
+        apply(&message, &shifts(key)).into()
+    }
+
+    fn gen_keys(mut rng: impl CryptoRng) -> Vec<u8> {
+        let period = rng.random_range(3..=MAX_PERIOD);
+        let mut key = vec![(period - 1) as u8];
+        key.extend((0..MAX_PERIOD).map(|_| rng.random_range(0..N) as u8));
+        key
+    }
+}
+
+impl DecryptBytes for VigenereCipher {
+    type DecryptError = VigenereError;
+
+    fn decrypt_bytes(key: &Key<Self>, message: Bytes) -> Result<Bytes, Self::DecryptError> {
+        let inverse: Vec<usize> = shifts(key).iter().map(|k| N - k).collect();
+        Ok(apply(&message, &inverse).into())
+    }
+}
@@ -1,9 +1,9 @@
 use crate::bytes::Bytes;
 
 use base64::prelude::*;
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use regex::Regex;
-use std::{error::Error, fmt, path::PathBuf};
+use std::{error::Error, fmt, net::SocketAddr, path::PathBuf};
 
 #[derive(Debug)]
 pub struct IllegalCharacter(pub char);
@@ -16,6 +16,10 @@ impl fmt::Display for IllegalCharacter {
 
 impl Error for IllegalCharacter {}
 
+/// every character `verified_message` accepts, in the order classical ciphers number them
+pub const ALPHABET: &[u8; 69] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789,.;?!()";
+
 pub fn verified_message(message: &str) -> Result<&str, IllegalCharacter> {
     use once_cell::sync::Lazy;
     static ILLEGAL_CHARACTER: Lazy<Regex> =
@@ -35,17 +39,57 @@ pub fn parse_base64(value: &str) -> Result<Bytes, base64::DecodeError> {
     BASE64_STANDARD.decode(value).map(|bytes| bytes.into())
 }
 
+#[derive(Debug, PartialEq, Eq, Args)]
+pub struct EndpointArgs {
+    #[arg(
+        long,
+        conflicts_with = "unix",
+        default_value = "127.0.0.1:4750",
+        help = "Localhost TCP address of the oracle"
+    )]
+    pub tcp: SocketAddr,
+    #[arg(long, help = "Path of the oracle's Unix domain socket")]
+    pub unix: Option<PathBuf>,
+}
+
+impl EndpointArgs {
+    pub fn endpoint(&self) -> crate::server::Endpoint {
+        match &self.unix {
+            Some(path) => crate::server::Endpoint::Unix(path.clone()),
+            None => crate::server::Endpoint::Tcp(self.tcp),
+        }
+    }
+}
+
+/// where encrypt and decrypt stream raw bytes from and to instead of taking one argument
+#[derive(Debug, PartialEq, Eq, Args)]
+pub struct StreamArgs {
+    #[arg(long, help = "Read raw bytes from this file, or standard input for -, in constant memory")]
+    pub input: Option<PathBuf>,
+    #[arg(long, requires = "input", help = "Write the raw output to this file instead of standard output")]
+    pub output: Option<PathBuf>,
+}
+
 #[derive(Debug, PartialEq, Eq, Subcommand)]
 pub enum Command {
     /// Generate a new key for the cipher
-    Generate,
-    /// Encrypt a secret message with the key in the given file, or with default key if not specified
+    Generate {
+        #[arg(
+            long,
+            help = "Derive the key from this 32-byte hex seed instead of fresh randomness",
+            value_parser = crate::rng::parse_seed
+        )]
+        seed: Option<crate::rng::Seed>,
+    },
+    /// Encrypt a secret message with the key in the given file, or with default key if not specified;
+    /// with --input, encrypt a file or standard input of any size to raw bytes
     Encrypt {
         #[arg(
             help = "The secret message to encrypt",
-            value_parser = parse_message
+            value_parser = parse_message,
+            required_unless_present = "input"
         )]
-        secret_message: Bytes,
+        secret_message: Option<Bytes>,
         #[arg(
             long, short, 
             help = "The key to use for encryption in base64 format. Priority over --key-file."
@@ -56,11 +100,28 @@ pub enum Command {
             help = "Path to the file containing the key in base64 format. "
         )]
         key_file: Option<PathBuf>,
+        #[arg(
+            long,
+            conflicts_with = "input",
+            help = "Wrap the ciphertext in an envelope naming the cipher, key fingerprint and IV"
+        )]
+        envelope: bool,
+        #[arg(long, requires = "envelope", help = "Add an HMAC-SHA256 tag to the envelope")]
+        authenticate: bool,
+        #[arg(
+            long,
+            conflicts_with_all = ["key", "key_file", "envelope", "input"],
+            help = "Id of a key held by the agent whose socket is $G2_AGENT_SOCK, by default its only key of this cipher"
+        )]
+        key_id: Option<String>,
+        #[command(flatten)]
+        stream: StreamArgs,
     },
-    /// decrypt an encrypted message with the key in the given file, or with default key if not specified
+    /// decrypt an encrypted message with the key in the given file, or with default key if not specified;
+    /// enveloped ciphertexts are checked against the cipher and key first
     Decrypt {
-        #[arg(value_parser = parse_base64)]
-        encrypted_message: Bytes,
+        #[arg(value_parser = parse_base64, required_unless_present = "input")]
+        encrypted_message: Option<Bytes>,
         #[arg(
             long, short, 
             help = "The key to use for encryption in base64 format. Priority over --key-file."
@@ -71,17 +132,176 @@ pub enum Command {
             help = "Path to the file containing the key in base64 format. "
         )]
         key_file: Option<PathBuf>,
+        #[arg(long, conflicts_with = "input", help = "Only accept an envelope with a valid HMAC-SHA256 tag")]
+        authenticate: bool,
+        #[arg(
+            long,
+            conflicts_with_all = ["key", "key_file", "authenticate", "input"],
+            help = "Id of a key held by the agent whose socket is $G2_AGENT_SOCK, by default its only key of this cipher"
+        )]
+        key_id: Option<String>,
+        #[command(flatten)]
+        stream: StreamArgs,
+    },
+    /// Encrypt the messages, or decrypt the base64 ciphertexts, on every line of a file or
+    /// standard input, printing one result line per input line
+    Batch {
+        #[arg(long, help = "Decrypt every line instead of encrypting it")]
+        decrypt: bool,
+        #[arg(long, short, help = "File of messages or ciphertexts, one per line, standard input if not given or -")]
+        input: Option<PathBuf>,
+        #[arg(long, short, help = "The key in base64 format. Priority over --key-file.")]
+        key: Option<Bytes>,
+        #[arg(long, help = "Path to the file containing the key in base64 format. ")]
+        key_file: Option<PathBuf>,
+    },
+    /// Show the fields of a key and what is wrong or weak about it
+    InspectKey {
+        #[arg(long, short, help = "The key to inspect in base64 format. Priority over --key-file.")]
+        key: Option<Bytes>,
+        #[arg(long, help = "Path to the file containing the key in base64 format. ")]
+        key_file: Option<PathBuf>,
+    },
+    /// Check that a key can be used, exit with a non-zero status if it cannot
+    ValidateKey {
+        #[arg(long, short, help = "The key to validate in base64 format. Priority over --key-file.")]
+        key: Option<Bytes>,
+        #[arg(long, help = "Path to the file containing the key in base64 format. ")]
+        key_file: Option<PathBuf>,
+    },
+    /// Expose the cipher as an encryption/decryption oracle on a local socket
+    Serve {
+        #[command(flatten)]
+        endpoint: EndpointArgs,
+        #[arg(long, help = "Maximum number of queries answered per connection")]
+        budget: Option<usize>,
+        #[arg(long, help = "Append the verb, lengths and key id of every query to this file")]
+        audit_log: Option<PathBuf>,
+    },
+    /// Run frequency analysis on a classical ciphertext and report candidate keys
+    Analyze {
+        #[arg(help = "The ciphertext, in base64 unless --text is given")]
+        ciphertext: String,
+        #[arg(long, help = "Read the ciphertext as plain text instead of base64")]
+        text: bool,
+        #[arg(long, value_enum, help = "Solvers to run, all of them by default")]
+        solver: Vec<crate::analysis::Solver>,
+        #[arg(long, default_value_t = 3, help = "Number of candidates reported per solver")]
+        top: usize,
+    },
+    /// Run the NIST SP 800-22 randomness tests on the cipher's output under a fresh key
+    Randomness {
+        #[arg(
+            long,
+            default_value_t = 125_000,
+            value_parser = clap::value_parser!(u32).range(16..),
+            help = "Number of ciphertext bytes to test"
+        )]
+        bytes: u32,
+        #[arg(long, value_enum, default_value = "zeros", help = "What to encrypt")]
+        plaintext: crate::randomness::Plaintext,
+        #[arg(long, short, help = "Key in base64 format instead of a fresh one. Priority over --key-file.")]
+        key: Option<Bytes>,
+        #[arg(long, help = "Path to the file containing the key in base64 format. ")]
+        key_file: Option<PathBuf>,
+        #[arg(
+            long,
+            help = "Derive the fresh key from this 32-byte hex seed",
+            value_parser = crate::rng::parse_seed
+        )]
+        seed: Option<crate::rng::Seed>,
+    },
+    /// Look for timing leaks: Welch's t-test on the timings of fixed and random inputs, as dudect does
+    Timing {
+        #[arg(long, value_enum, default_value = "key", help = "What to time")]
+        target: crate::timing::Target,
+        #[arg(long, default_value_t = 10_000, help = "Number of timed operations")]
+        samples: usize,
+        #[arg(
+            long,
+            help = "Derive the key and inputs from this 32-byte hex seed instead of the zero seed",
+            value_parser = crate::rng::parse_seed
+        )]
+        seed: Option<crate::rng::Seed>,
+    },
+    /// Write deterministic test vectors of the cipher as Wycheproof-style JSON
+    ExportVectors {
+        #[arg(long, default_value_t = 16, help = "Number of vectors")]
+        count: usize,
+        #[arg(
+            long,
+            help = "Derive the keys and messages from this 32-byte hex seed instead of the zero seed",
+            value_parser = crate::rng::parse_seed
+        )]
+        seed: Option<crate::rng::Seed>,
+        #[arg(long, short, help = "Write the JSON to this file instead of standard output")]
+        output: Option<PathBuf>,
+    },
+    /// Run test-vector files, of any g2 cipher or Wycheproof ChaCha20-Poly1305 ones, and report every test
+    CheckVectors {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Explore the ciphers in one session: generate and name keys, switch ciphers, encrypt, decrypt,
+    /// inspect and compare, with variables and a history
+    Repl,
+    /// Send requests such as "ENCRYPT hello" to a running oracle and print the replies
+    Client {
+        #[command(flatten)]
+        endpoint: EndpointArgs,
+        requests: Vec<String>,
     },
 }
 
 #[derive(Debug, PartialEq, Eq, Parser)]
 #[command(author, version, about, long_about)]
 #[command(propagate_version = true)]
-struct Cli {
+pub struct Cli {
+    #[arg(
+        long,
+        global = true,
+        help = "Print one JSON object per command and exit with a non-zero status on failure"
+    )]
+    pub json: bool,
     #[command(subcommand)]
-    command: Command,
+    pub command: Command,
+}
+
+pub fn cli() -> Cli {
+    Cli::parse()
 }
 
 pub fn command() -> Command {
-    Cli::parse().command
+    cli().command
+}
+
+#[derive(Debug, PartialEq, Eq, Subcommand)]
+pub enum AgentCommand {
+    /// Hold keys in memory and encrypt and decrypt with them for the cipher binaries
+    Agent,
+    /// Hand a base64 key to the agent, from a file or standard input, and print its id
+    Add {
+        #[arg(help = "Name of the cipher of the key, such as hill-caesar or chacha20")]
+        cipher: String,
+        #[arg(long, help = "Path to the file containing the key, standard input if not given")]
+        key_file: Option<PathBuf>,
+    },
+    /// Print the id and cipher of every key the agent holds
+    List,
+    /// Make the agent forget a key and wipe it
+    Remove { key_id: String },
+}
+
+/// the `g2` binary of the key agent
+#[derive(Debug, PartialEq, Eq, Parser)]
+#[command(name = "g2", author, version, about = "Key agent of the g2 ciphers")]
+pub struct AgentCli {
+    #[arg(long, global = true, help = "Path of the agent's Unix domain socket, $G2_AGENT_SOCK if not given")]
+    pub socket: Option<PathBuf>,
+    #[command(subcommand)]
+    pub command: AgentCommand,
+}
+
+pub fn agent_cli() -> AgentCli {
+    AgentCli::parse()
 }
@@ -0,0 +1,310 @@
+//! Self-describing ciphertexts: the ciphertext of a cipher wrapped with what
+//! is needed to tell which cipher, key and format produced it.
+//!
+//! Layout, all integers big-endian:
+//!
+//! | bytes | content                                                        |
+//! |-------|----------------------------------------------------------------|
+//! | 4     | magic `G2EV`                                                   |
+//! | 1     | format version, 1                                              |
+//! | 1     | cipher id, see [`CIPHERS`]                                     |
+//! | 8     | key fingerprint, see [`fingerprint`]                           |
+//! | 1     | mode, see [`Mode`]                                             |
+//! | 1     | length `n` of the IV                                           |
+//! | n     | IV                                                             |
+//! | 1     | length `t` of the tag, 0 or 32                                 |
+//! | rest  | ciphertext                                                     |
+//! | t     | HMAC-SHA256 of all the bytes before it, see [`Envelope::seal`] |
+
+use crate::bytes::Bytes;
+use crate::cipher::Key;
+use crate::mac::{ct_eq, hmac_sha256};
+use crate::sha256::{Sha256, DIGEST_SIZE};
+use crate::traits::ChallengeCipher;
+
+use std::{error::Error, fmt};
+
+pub const MAGIC: &[u8; 4] = b"G2EV";
+pub const VERSION: u8 = 1;
+pub const FINGERPRINT_SIZE: usize = 8;
+pub const TAG_SIZE: usize = DIGEST_SIZE;
+
+/// envelope ids of the ciphers, by [`ChallengeCipher::NAME`]; ids are never reused
+pub const CIPHERS: [(u8, &str); 11] = [
+    (1, "hill-caesar"),
+    (2, "chacha20"),
+    (3, "hill-gf256"),
+    (4, "salsa20"),
+    (5, "salsa20/12"),
+    (16, "caesar"),
+    (17, "affine"),
+    (18, "vigenere"),
+    (19, "substitution"),
+    (20, "playfair"),
+    (21, "transposition"),
+];
+
+pub fn cipher_id(name: &str) -> Option<u8> {
+    CIPHERS.iter().find(|&&(_, n)| n == name).map(|&(id, _)| id)
+}
+
+pub fn cipher_name(id: u8) -> Option<&'static str> {
+    CIPHERS.iter().find(|&&(i, _)| i == id).map(|&(_, name)| name)
+}
+
+/// how the cipher consumes its input, and so what the IV holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// every block or character is enciphered on its own, there is no IV
+    Codebook = 0,
+    /// a keystream is added to the message, the IV is its nonce and initial counter
+    Stream = 1,
+}
+
+impl TryFrom<u8> for Mode {
+    type Error = EnvelopeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Mode::Codebook),
+            1 => Ok(Mode::Stream),
+            _ => Err(EnvelopeError::UnknownMode(value)),
+        }
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mode::Codebook => write!(f, "codebook"),
+            Mode::Stream => write!(f, "stream"),
+        }
+    }
+}
+
+/// public name of a key: the first bytes of a SHA-256 hash of it, so that an
+/// envelope can say which key it needs
+///
+/// It is unsalted and cheap, so it only hides keys too many to try: anyone
+/// can hash every key of a small key space and find the one that matches.
+/// Caesar and affine keys, of one and two bytes, fall at once, and so would
+/// a key of any cipher drawn from a short list, like a password.
+pub fn fingerprint(key: &[u8]) -> [u8; FINGERPRINT_SIZE] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"g2 key fingerprint").update(key);
+    hasher.finalize()[..FINGERPRINT_SIZE].try_into().unwrap()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvelopeError {
+    Truncated,
+    BadMagic,
+    UnsupportedVersion(u8),
+    UnknownCipher(u8),
+    UnknownMode(u8),
+    BadTagLength(u8),
+    /// the envelope holds a ciphertext of another cipher
+    WrongCipher { expected: &'static str, found: u8 },
+    /// the envelope was sealed under another key, with this fingerprint
+    WrongKey { expected: [u8; FINGERPRINT_SIZE], found: [u8; FINGERPRINT_SIZE] },
+    KeyLength { expected: usize, found: usize },
+    /// the mode or IV of the envelope is not the one of the key
+    WrongIv,
+    /// the tag does not match, the envelope was altered
+    BadTag,
+    /// a tag was required but the envelope has none
+    MissingTag,
+    /// the envelope is intact but the cipher could not decrypt it
+    Cipher(String),
+}
+
+impl fmt::Display for EnvelopeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvelopeError::Truncated => write!(f, "envelope is truncated"),
+            EnvelopeError::BadMagic => write!(f, "not a g2 envelope"),
+            EnvelopeError::UnsupportedVersion(v) => write!(f, "unsupported envelope version {}", v),
+            EnvelopeError::UnknownCipher(id) => write!(f, "unknown cipher id {} in envelope", id),
+            EnvelopeError::UnknownMode(mode) => write!(f, "unknown mode {} in envelope", mode),
+            EnvelopeError::BadTagLength(len) => write!(f, "tag length must be 0 or {}, not {}", TAG_SIZE, len),
+            EnvelopeError::WrongCipher { expected, found } => match cipher_name(*found) {
+                Some(name) => write!(f, "envelope holds a {} ciphertext, not {}", name, expected),
+                None => write!(f, "envelope holds a ciphertext of unknown cipher {}, not {}", found, expected),
+            },
+            EnvelopeError::WrongKey { expected, found } => write!(
+                f,
+                "envelope was sealed under the key with fingerprint {}, this key has fingerprint {}",
+                hex(expected),
+                hex(found)
+            ),
+            EnvelopeError::KeyLength { expected, found } => {
+                write!(f, "expected a {}-byte key, got {} bytes", expected, found)
+            }
+            EnvelopeError::WrongIv => write!(f, "envelope mode or IV is not the one of this key"),
+            EnvelopeError::BadTag => write!(f, "envelope tag mismatch, the envelope was altered"),
+            EnvelopeError::MissingTag => write!(f, "envelope has no tag, it cannot be authenticated"),
+            EnvelopeError::Cipher(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for EnvelopeError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Envelope {
+    pub cipher_id: u8,
+    pub fingerprint: [u8; FINGERPRINT_SIZE],
+    pub mode: Mode,
+    pub iv: Vec<u8>,
+    pub ciphertext: Bytes,
+    pub tag: Option<[u8; TAG_SIZE]>,
+}
+
+/// the HMAC key of envelope tags, derived from the cipher key so that
+/// authenticated envelopes need no second key
+fn tag_key(key: &[u8]) -> [u8; DIGEST_SIZE] {
+    hmac_sha256(key, b"g2 envelope tag")
+}
+
+pub fn is_envelope(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+impl Envelope {
+    /// encrypts `message` under `key` with `C`, with a tag if `authenticate`;
+    /// fails when `C` cannot encrypt that much under `key`
+    pub fn seal<C: ChallengeCipher>(key: &Key<C>, message: Bytes, authenticate: bool) -> Result<Self, EnvelopeError> {
+        let cipher_id = cipher_id(C::NAME).expect("every cipher has an envelope id");
+        let (mode, iv) = C::envelope_mode(key);
+        let mut envelope = Envelope {
+            cipher_id,
+            fingerprint: fingerprint(key),
+            mode,
+            iv,
+            ciphertext: C::try_encrypt(key, message).map_err(|e| EnvelopeError::Cipher(e.to_string()))?,
+            tag: None,
+        };
+        if authenticate {
+            // the signed bytes record the length of the tag, so it must be there first
+            envelope.tag = Some([0; TAG_SIZE]);
+            let tag = hmac_sha256(&tag_key(key), &envelope.signed_bytes());
+            envelope.tag = Some(tag);
+        }
+        Ok(envelope)
+    }
+
+    /// checks that the envelope is for `C` and `key`, down to its mode and IV,
+    /// and is intact if it has a tag, then decrypts it; use [`Envelope::open_authenticated`] when the
+    /// sender is known to add tags, or a forger can simply leave the tag out
+    pub fn open<C: ChallengeCipher>(&self, key: &Key<C>) -> Result<Bytes, EnvelopeError> {
+        if cipher_id(C::NAME) != Some(self.cipher_id) {
+            return Err(EnvelopeError::WrongCipher {
+                expected: C::NAME,
+                found: self.cipher_id,
+            });
+        }
+        let found = fingerprint(key);
+        if found != self.fingerprint {
+            return Err(EnvelopeError::WrongKey {
+                expected: self.fingerprint,
+                found,
+            });
+        }
+        if let Some(tag) = &self.tag
+            && !ct_eq(&hmac_sha256(&tag_key(key), &self.signed_bytes()), tag)
+        {
+            return Err(EnvelopeError::BadTag);
+        }
+        if C::envelope_mode(key) != (self.mode, self.iv.clone()) {
+            return Err(EnvelopeError::WrongIv);
+        }
+        C::decrypt_bytes(key, self.ciphertext.clone()).map_err(|e| EnvelopeError::Cipher(e.to_string()))
+    }
+
+    /// like [`Envelope::open`], but refuses envelopes without a tag
+    pub fn open_authenticated<C: ChallengeCipher>(&self, key: &Key<C>) -> Result<Bytes, EnvelopeError> {
+        if self.tag.is_none() {
+            return Err(EnvelopeError::MissingTag);
+        }
+        self.open::<C>(key)
+    }
+
+    /// everything but the tag itself
+    fn signed_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(MAGIC.len() + 13 + self.iv.len() + self.ciphertext.len() + TAG_SIZE);
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.push(self.cipher_id);
+        bytes.extend_from_slice(&self.fingerprint);
+        bytes.push(self.mode as u8);
+        bytes.push(self.iv.len() as u8);
+        bytes.extend_from_slice(&self.iv);
+        bytes.push(if self.tag.is_some() { TAG_SIZE as u8 } else { 0 });
+        bytes.extend_from_slice(&self.ciphertext);
+        bytes
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.signed_bytes();
+        if let Some(tag) = &self.tag {
+            bytes.extend_from_slice(tag);
+        }
+        bytes
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self, EnvelopeError> {
+        if !is_envelope(bytes) {
+            return Err(EnvelopeError::BadMagic);
+        }
+        let mut rest = &bytes[MAGIC.len()..];
+        let version = take(&mut rest, 1)?[0];
+        if version != VERSION {
+            return Err(EnvelopeError::UnsupportedVersion(version));
+        }
+        let cipher_id = take(&mut rest, 1)?[0];
+        if cipher_name(cipher_id).is_none() {
+            return Err(EnvelopeError::UnknownCipher(cipher_id));
+        }
+        let fingerprint = take(&mut rest, FINGERPRINT_SIZE)?.try_into().unwrap();
+        let mode = Mode::try_from(take(&mut rest, 1)?[0])?;
+        let iv_len = take(&mut rest, 1)?[0] as usize;
+        let iv = take(&mut rest, iv_len)?.to_vec();
+        let tag_len = take(&mut rest, 1)?[0] as usize;
+        if tag_len != 0 && tag_len != TAG_SIZE {
+            return Err(EnvelopeError::BadTagLength(tag_len as u8));
+        }
+        if rest.len() < tag_len {
+            return Err(EnvelopeError::Truncated);
+        }
+        let (ciphertext, tag) = rest.split_at(rest.len() - tag_len);
+        Ok(Envelope {
+            cipher_id,
+            fingerprint,
+            mode,
+            iv,
+            ciphertext: Bytes::copy_from_slice(ciphertext),
+            tag: (tag_len != 0).then(|| tag.try_into().unwrap()),
+        })
+    }
+}
+
+/// the next `n` bytes of `rest`
+fn take<'a>(rest: &mut &'a [u8], n: usize) -> Result<&'a [u8], EnvelopeError> {
+    let (head, tail) = rest.split_at_checked(n).ok_or(EnvelopeError::Truncated)?;
+    *rest = tail;
+    Ok(head)
+}
+
+/// decrypts an envelope of any cipher, chosen by its header, with
+/// [`Envelope::open`]; returns the name of the cipher with the message
+pub fn open_any(bytes: &[u8], key: &[u8]) -> Result<(&'static str, Bytes), EnvelopeError> {
+    let envelope = Envelope::parse(bytes)?;
+    let name = cipher_name(envelope.cipher_id).ok_or(EnvelopeError::UnknownCipher(envelope.cipher_id))?;
+    let cipher = crate::registry::find(name).expect("every id of CIPHERS is registered");
+    let message = cipher.open(&envelope, key)?;
+    Ok((name, message))
+}
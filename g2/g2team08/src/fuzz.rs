@@ -0,0 +1,101 @@
+//! The bodies of the cargo-fuzz targets in `fuzz/`, kept in the library so
+//! that `tests/fuzz.rs` replays their regression corpora without libFuzzer.
+//! Each takes any bytes and only panics on a bug: hostile input must come
+//! back as a typed error.
+
+use crate::bytes::Bytes;
+use crate::cipher::Key;
+use crate::cipher1::cipher::HillCaesarCipher;
+use crate::cipher2::cipher::ChaCha20Cipher;
+use crate::cipher3::cipher::HillGf256Cipher;
+use crate::cipher4::cipher::{Salsa2012Cipher, Salsa20Cipher};
+use crate::classical::*;
+use crate::cli::{parse_base64, parse_message, Cli};
+use crate::envelope::Envelope;
+use crate::registry::CIPHERS;
+use crate::report::load_key;
+use crate::rng::parse_seed;
+use crate::server::{answer, Query};
+use crate::traits::ChallengeCipher;
+
+use clap::Parser;
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+
+/// calls `$f::<C>($data)` for every cipher `C`
+macro_rules! each_cipher {
+    ($f:ident, $data:expr) => {
+        $f::<HillCaesarCipher>($data);
+        $f::<ChaCha20Cipher>($data);
+        $f::<HillGf256Cipher>($data);
+        $f::<Salsa20Cipher>($data);
+        $f::<Salsa2012Cipher>($data);
+        $f::<caesar::CaesarCipher>($data);
+        $f::<affine::AffineCipher>($data);
+        $f::<vigenere::VigenereCipher>($data);
+        $f::<substitution::SubstitutionCipher>($data);
+        $f::<playfair::PlayfairCipher>($data);
+        $f::<transposition::TranspositionCipher>($data);
+    };
+}
+
+/// `data` is a cipher index, a key and a ciphertext, raw or in an envelope
+///
+/// Decryption may fail, but what it gives encrypts back to the ciphertext,
+/// up to the blocks of zero padding that Hill decryption strips.
+pub fn decrypt(data: &[u8]) {
+    let Some((&index, rest)) = data.split_first() else {
+        return;
+    };
+    let cipher = &CIPHERS[index as usize % CIPHERS.len()];
+    let Some((key, ciphertext)) = rest.split_at_checked(cipher.key_size()) else {
+        return;
+    };
+    if let Ok(message) = cipher.decrypt(key, Bytes::copy_from_slice(ciphertext)) {
+        let encrypted = cipher.encrypt(key, message).unwrap();
+        assert!(ciphertext.starts_with(&encrypted), "{} does not decrypt to a preimage", cipher.name);
+    }
+    if let Ok(envelope) = Envelope::parse(ciphertext) {
+        let _ = cipher.open(&envelope, key);
+    }
+}
+
+fn key<C: ChallengeCipher>(data: &[u8]) {
+    if let Ok(Some(key)) = load_key::<C>(Some(Bytes::copy_from_slice(data)), None) {
+        C::inspect_key(&key);
+    }
+    if let Some((key, message)) = data.split_at_checked(C::key_size()) {
+        let key = Key::<C>::from_slice(key);
+        C::inspect_key(key);
+        let encrypted = C::encrypt_bytes(key, Bytes::copy_from_slice(message));
+        let _ = C::decrypt_bytes(key, encrypted);
+    }
+}
+
+/// `data` as the base64 text of a key for every cipher, and as raw key bytes
+/// followed by a message: keys load or fail, and any key can be inspected
+/// and used
+pub fn keys(data: &[u8]) {
+    each_cipher!(key, data);
+}
+
+/// `data` as a command line split at NUL bytes, as a message, ciphertext and
+/// seed argument, and as lines of oracle requests
+pub fn cli(data: &[u8]) {
+    let args = data.split(|&b| b == 0).map(OsStr::from_bytes);
+    let _ = Cli::try_parse_from(std::iter::once(OsStr::new("g2")).chain(args));
+
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let _ = parse_message(text);
+    let _ = parse_base64(text);
+    let _ = parse_seed(text);
+    for query in text.lines().filter_map(|line| line.parse::<Query>().ok()) {
+        // fresh keys would make the target nondeterministic
+        if query != Query::Generate {
+            answer::<HillCaesarCipher>(query.clone());
+            answer::<ChaCha20Cipher>(query);
+        }
+    }
+}
@@ -0,0 +1,230 @@
+//! IND-CPA and IND-CCA games: a challenger draws a key and a secret bit, the
+//! adversary picks two messages of the same length with the help of its
+//! oracles, gets the encryption of one of them and guesses which. Its
+//! advantage is how much better than a coin it guesses, `2 Pr[win] - 1`.
+//!
+//! The ciphers of this crate are deterministic under a fixed key, so none of
+//! them is IND-CPA secure; the reference adversaries show how each one falls.
+
+use crate::bytes::Bytes;
+use crate::cipher::Key;
+use crate::cipher1::cipher::HillCaesarCipher;
+use crate::cipher2::cipher::ChaCha20Cipher;
+use crate::rng::{seeded, ChaCha20Rng, RngCore, Seed};
+use crate::traits::ChallengeCipher;
+
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Game {
+    /// chosen plaintexts: an encryption oracle only
+    Cpa,
+    /// chosen ciphertexts too: a decryption oracle that refuses the challenge
+    Cca,
+}
+
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Game::Cpa => write!(f, "IND-CPA"),
+            Game::Cca => write!(f, "IND-CCA"),
+        }
+    }
+}
+
+/// what the challenger lets the adversary ask under the key of one trial
+pub struct Oracles<'a, C: ChallengeCipher> {
+    key: &'a Key<C>,
+    game: Game,
+    challenge: Option<Bytes>,
+    /// the adversary's coins
+    pub rng: &'a mut ChaCha20Rng,
+    pub encryptions: usize,
+    pub decryptions: usize,
+}
+
+impl<C: ChallengeCipher> Oracles<'_, C> {
+    pub fn encrypt(&mut self, message: &[u8]) -> Bytes {
+        self.encryptions += 1;
+        C::encrypt_bytes(self.key, Bytes::copy_from_slice(message))
+    }
+
+    /// the decryption of `ciphertext`, or nothing in the CPA game and for the challenge itself
+    pub fn decrypt(&mut self, ciphertext: &[u8]) -> Option<Result<Bytes, C::DecryptError>> {
+        if self.game == Game::Cpa || self.challenge.as_deref() == Some(ciphertext) {
+            return None;
+        }
+        self.decryptions += 1;
+        Some(C::decrypt_bytes(self.key, Bytes::copy_from_slice(ciphertext)))
+    }
+
+    pub fn game(&self) -> Game {
+        self.game
+    }
+
+    pub fn random_bytes(&mut self, len: usize) -> Vec<u8> {
+        let mut bytes = vec![0; len];
+        self.rng.fill_bytes(&mut bytes);
+        bytes
+    }
+}
+
+pub trait Adversary<C: ChallengeCipher> {
+    /// two messages of the same length, one of which will be encrypted;
+    /// messages of different lengths lose the trial
+    fn choose(&mut self, oracles: &mut Oracles<'_, C>) -> (Vec<u8>, Vec<u8>);
+
+    /// whether `challenge` encrypts the second message
+    fn guess(&mut self, oracles: &mut Oracles<'_, C>, challenge: &[u8]) -> bool;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    pub game: Game,
+    pub trials: usize,
+    pub wins: usize,
+    /// oracle queries over all trials
+    pub encryptions: usize,
+    pub decryptions: usize,
+}
+
+impl Estimate {
+    /// `2 Pr[win] - 1`, from -1 to 1; around 0 for a secure cipher
+    pub fn advantage(&self) -> f64 {
+        if self.trials == 0 {
+            return 0.0;
+        }
+        2.0 * self.wins as f64 / self.trials as f64 - 1.0
+    }
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} of {} trials won, advantage {:.3} ({} encryption and {} decryption queries)",
+            self.game,
+            self.wins,
+            self.trials,
+            self.advantage(),
+            self.encryptions,
+            self.decryptions
+        )
+    }
+}
+
+/// plays `trials` games of `adversary` against `C`, each under a fresh key and
+/// bit; the keys, bits and the adversary's coins all come from `seed`
+pub fn play<C: ChallengeCipher>(adversary: &mut impl Adversary<C>, game: Game, trials: usize, seed: Seed) -> Estimate {
+    let mut rng = seeded(seed);
+    let mut coins = seeded(seed);
+    coins.set_stream(1);
+    let mut estimate = Estimate {
+        game,
+        trials,
+        wins: 0,
+        encryptions: 0,
+        decryptions: 0,
+    };
+    for _ in 0..trials {
+        let key = C::gen_keys(&mut rng);
+        let key = Key::<C>::from_slice(&key);
+        let bit = rng.next_u32() & 1 == 1;
+        let mut oracles = Oracles {
+            key,
+            game,
+            challenge: None,
+            rng: &mut coins,
+            encryptions: 0,
+            decryptions: 0,
+        };
+        let (m0, m1) = adversary.choose(&mut oracles);
+        if m0.len() == m1.len() {
+            let challenge = C::encrypt_bytes(key, Bytes::from(if bit { m1 } else { m0 }));
+            oracles.challenge = Some(challenge.clone());
+            if adversary.guess(&mut oracles, &challenge) == bit {
+                estimate.wins += 1;
+            }
+        }
+        estimate.encryptions += oracles.encryptions;
+        estimate.decryptions += oracles.decryptions;
+    }
+    estimate
+}
+
+/// Hill+Caesar is affine blockwise, `E(x) = Kx + s`, so `E(a) + E(b) - E(0)`
+/// is the encryption of `a + b` without ever asking for it.
+#[derive(Debug, Default)]
+pub struct HillLinearity {
+    /// the forged encryption of the first message
+    forged: Vec<u8>,
+}
+
+/// two whole blocks
+const HILL_MESSAGE: usize = 16;
+
+impl Adversary<HillCaesarCipher> for HillLinearity {
+    fn choose(&mut self, oracles: &mut Oracles<'_, HillCaesarCipher>) -> (Vec<u8>, Vec<u8>) {
+        let (a, b) = (oracles.random_bytes(HILL_MESSAGE), oracles.random_bytes(HILL_MESSAGE));
+        let (ea, eb, e0) = (oracles.encrypt(&a), oracles.encrypt(&b), oracles.encrypt(&[0; HILL_MESSAGE]));
+        self.forged = (0..ea.len()).map(|i| ea[i].wrapping_add(eb[i]).wrapping_sub(e0[i])).collect();
+        let sum = a.iter().zip(&b).map(|(x, y)| x.wrapping_add(*y)).collect();
+        (sum, oracles.random_bytes(HILL_MESSAGE))
+    }
+
+    fn guess(&mut self, _oracles: &mut Oracles<'_, HillCaesarCipher>, challenge: &[u8]) -> bool {
+        challenge != self.forged
+    }
+}
+
+/// The nonce of a ChaCha20 key is part of it, so every encryption under the
+/// key reuses one keystream: a known message gives it away, and it unmasks
+/// the challenge.
+#[derive(Debug, Default)]
+pub struct NonceReuse {
+    keystream: Vec<u8>,
+    first: Vec<u8>,
+}
+
+const CHACHA_MESSAGE: usize = 32;
+
+impl Adversary<ChaCha20Cipher> for NonceReuse {
+    fn choose(&mut self, oracles: &mut Oracles<'_, ChaCha20Cipher>) -> (Vec<u8>, Vec<u8>) {
+        let known = oracles.random_bytes(CHACHA_MESSAGE);
+        let encrypted = oracles.encrypt(&known);
+        self.keystream = known.iter().zip(&encrypted).map(|(m, c)| m ^ c).collect();
+        self.first = oracles.random_bytes(CHACHA_MESSAGE);
+        (self.first.clone(), oracles.random_bytes(CHACHA_MESSAGE))
+    }
+
+    fn guess(&mut self, _oracles: &mut Oracles<'_, ChaCha20Cipher>, challenge: &[u8]) -> bool {
+        challenge.iter().zip(&self.keystream).zip(&self.first).any(|((c, k), m)| c ^ k != *m)
+    }
+}
+
+/// IND-CCA against any cipher that is malleable bytewise, as the stream
+/// ciphers are: the challenge with its first byte flipped is not the
+/// challenge, so the oracle decrypts it, and flipping the byte back gives the
+/// message. In the CPA game it only guesses.
+#[derive(Debug, Default)]
+pub struct BitFlip {
+    first: Vec<u8>,
+}
+
+impl<C: ChallengeCipher> Adversary<C> for BitFlip {
+    fn choose(&mut self, oracles: &mut Oracles<'_, C>) -> (Vec<u8>, Vec<u8>) {
+        self.first = oracles.random_bytes(CHACHA_MESSAGE);
+        (self.first.clone(), oracles.random_bytes(CHACHA_MESSAGE))
+    }
+
+    fn guess(&mut self, oracles: &mut Oracles<'_, C>, challenge: &[u8]) -> bool {
+        let mut flipped = challenge.to_vec();
+        if let Some(byte) = flipped.first_mut() {
+            *byte ^= 1;
+        }
+        match oracles.decrypt(&flipped) {
+            Some(Ok(message)) => message.get(1..) != self.first.get(1..),
+            _ => oracles.rng.next_u32() & 1 == 1,
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! Human-readable breakdown of keys for the inspect-key and validate-key
+//! commands, so that a key that fails shows why.
+
+use crate::bytes::Bytes;
+use crate::cipher::{Key, KeySizeUser};
+use crate::report::{key_id, load_key, ErrorKind, Failure, Report};
+use crate::traits::ChallengeCipher;
+
+use base64::prelude::*;
+use serde::Serialize;
+use std::{fmt, path::PathBuf};
+
+/// Ciphers whose keys have structure worth showing: fields, invertibility, weak values.
+pub trait KeyCheck: KeySizeUser {
+    fn check(key: &Key<Self>) -> KeyInspection;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum FieldValue {
+    Text(String),
+    Number(u64),
+    Flag(bool),
+    Matrix(Vec<Vec<u8>>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KeyField {
+    pub name: &'static str,
+    pub value: FieldValue,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct KeyInspection {
+    pub fields: Vec<KeyField>,
+    /// problems that make the key unusable
+    pub errors: Vec<String>,
+    /// the key works but should not be trusted
+    pub warnings: Vec<String>,
+}
+
+impl KeyInspection {
+    /// what is known about any key: its raw bytes
+    pub fn raw(key: &[u8]) -> Self {
+        let mut inspection = KeyInspection::default();
+        inspection.field("bytes", FieldValue::Number(key.len() as u64));
+        inspection.field("hex", FieldValue::Text(hex(key)));
+        inspection
+    }
+
+    pub fn field(&mut self, name: &'static str, value: FieldValue) -> &mut Self {
+        self.fields.push(KeyField { name, value });
+        self
+    }
+
+    pub fn error(&mut self, message: impl Into<String>) -> &mut Self {
+        self.errors.push(message.into());
+        self
+    }
+
+    pub fn warning(&mut self, message: impl Into<String>) -> &mut Self {
+        self.warnings.push(message.into());
+        self
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// blocks left before the counter of a stream cipher wraps that count as near overflow
+pub const COUNTER_MARGIN: u128 = 1 << 16;
+
+/// the fields and weak cases shared by the stream ciphers: a key, a nonce and
+/// a block counter that can only move `blocks_left` more blocks before it wraps
+pub fn check_stream_key(inspection: &mut KeyInspection, key: &[u8], nonce: &[u8], counter: u64, blocks_left: u128) {
+    inspection
+        .field("key", FieldValue::Text(hex(key)))
+        .field("nonce", FieldValue::Text(hex(nonce)))
+        .field("counter", FieldValue::Number(counter))
+        .field("blocks before the counter wraps", FieldValue::Text(blocks_left.to_string()));
+    if key.iter().all(|&b| b == 0) {
+        inspection.warning("the key is all zero");
+    } else if key.iter().all(|&b| b == key[0]) {
+        inspection.warning("every byte of the key is the same");
+    }
+    if nonce.iter().all(|&b| b == 0) {
+        inspection.warning("the nonce is all zero, which is easily reused with the same key");
+    }
+    if blocks_left < COUNTER_MARGIN {
+        inspection.warning(format!(
+            "the counter is near overflow, messages longer than {} bytes reuse the keystream",
+            blocks_left * 64
+        ));
+    }
+}
+
+impl fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldValue::Text(text) => f.write_str(text),
+            FieldValue::Number(n) => write!(f, "{}", n),
+            FieldValue::Flag(flag) => f.write_str(if *flag { "yes" } else { "no" }),
+            FieldValue::Matrix(rows) => {
+                for row in rows {
+                    writeln!(f)?;
+                    let row: Vec<String> = row.iter().map(|x| format!("{:3}", x)).collect();
+                    write!(f, "  {}", row.join(" "))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for KeyInspection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for field in &self.fields {
+            match field.value {
+                // the rows follow on their own lines
+                FieldValue::Matrix(_) => writeln!(f, "{}:{}", field.name, field.value)?,
+                _ => writeln!(f, "{}: {}", field.name, field.value)?,
+            }
+        }
+        for warning in &self.warnings {
+            writeln!(f, "warning: {}", warning)?;
+        }
+        for error in &self.errors {
+            writeln!(f, "error: {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+/// [`load_key`], failing on a key that [`ChallengeCipher::inspect_key`]
+/// finds errors in, so that no command runs under an unusable key
+pub fn load_valid_key<C: ChallengeCipher>(key: Option<Bytes>, key_file: Option<PathBuf>) -> Result<Option<Key<C>>, Failure> {
+    let key = load_key::<C>(key, key_file)?;
+    if let Some(inspection) = key.as_ref().and_then(C::inspect_key)
+        && !inspection.is_valid()
+    {
+        return Err(Failure::new(ErrorKind::InvalidKey, inspection.errors.join("; ")));
+    }
+    Ok(key)
+}
+
+/// the report of inspect-key or validate-key for the key given on the command
+/// line; validate-key fails when the key has errors, inspect-key only lists them
+pub fn report<C: ChallengeCipher>(command: &'static str, key: Option<Bytes>, key_file: Option<PathBuf>) -> Report {
+    let mut report = Report::new(C::NAME, command);
+    let key = match load_key::<C>(key, key_file) {
+        Ok(Some(key)) => key,
+        // the default key is the secret of the challenge
+        Ok(None) => return report.failed(Failure::new(ErrorKind::InvalidKey, "no key given, use --key or --key-file")),
+        Err(failure) => return report.failed(failure),
+    };
+    report.key_id = Some(key_id(Some(&key)));
+    report.key = Some(BASE64_STANDARD.encode(&key));
+    let inspection = C::inspect_key(&key).unwrap_or_else(|| KeyInspection::raw(&key));
+    let failure = (command == "validate-key" && !inspection.is_valid())
+        .then(|| Failure::new(ErrorKind::InvalidKey, inspection.errors.join("; ")));
+    report.inspection = Some(inspection);
+    match failure {
+        Some(failure) => report.failed(failure),
+        None => report,
+    }
+}
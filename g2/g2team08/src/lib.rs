@@ -1,9 +1,55 @@
+//! The g2 ciphers. Without the default `cli` feature only the cipher cores
+//! are built, Hill, ChaCha20, Salsa20, the MACs and the byte traits, and
+//! without `std` they need nothing but `core` and `alloc`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 pub use bytes;
 pub use cipher;
 pub use rand;
 
+#[cfg(feature = "cli")]
+pub mod agent;
+#[cfg(feature = "cli")]
+pub mod analysis;
+pub mod arx;
+#[cfg(feature = "cli")]
+pub mod challenge;
+#[cfg(feature = "cli")]
 pub mod cli;
+#[cfg(feature = "cli")]
+pub mod envelope;
+#[cfg(feature = "cli")]
+pub mod fuzz;
+#[cfg(feature = "cli")]
+pub mod games;
+#[cfg(feature = "cli")]
+pub mod keycheck;
+pub mod mac;
+#[cfg(feature = "cli")]
+pub mod protection;
+#[cfg(feature = "cli")]
+pub mod randomness;
+#[cfg(feature = "cli")]
+pub mod registry;
+#[cfg(feature = "cli")]
+pub mod repl;
+#[cfg(feature = "cli")]
+pub mod report;
+pub mod rng;
+#[cfg(feature = "cli")]
+pub mod server;
+pub mod sha256;
+#[cfg(feature = "cli")]
+pub mod stream;
+#[cfg(feature = "cli")]
+pub mod timing;
 pub mod traits;
+#[cfg(feature = "cli")]
+pub mod vectors;
+#[cfg(feature = "std")]
 pub mod test;
 pub mod cipher1 {
     pub mod cipher;
@@ -12,6 +58,17 @@ pub mod cipher1 {
 pub mod cipher2 {
     pub mod cipher;
 }
+
+pub mod cipher3 {
+    pub mod cipher;
+}
+
+pub mod cipher4 {
+    pub mod cipher;
+}
+
+#[cfg(feature = "cli")]
+pub mod classical;
 // #[cfg(feature = "dev")]
 // pub mod test;
 #[derive(Clone, Debug)]
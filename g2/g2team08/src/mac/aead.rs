@@ -0,0 +1,63 @@
+//! The ChaCha20-Poly1305 AEAD of RFC 8439, section 2.8: block 0 of the
+//! ChaCha20 keystream is the one-time Poly1305 key, the message is encrypted
+//! from block 1 on, and the tag covers the associated data and the
+//! ciphertext, each padded to 16 bytes, then both lengths.
+
+use crate::arx;
+use crate::cipher2::cipher::chacha20_block;
+use crate::mac::ct_eq;
+use crate::mac::poly1305::{poly1305, TAG_SIZE};
+
+use alloc::vec::Vec;
+use core::{error::Error, fmt};
+
+pub const KEY_SIZE: usize = 32;
+pub const NONCE_SIZE: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AeadError;
+
+impl fmt::Display for AeadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "authentication tag mismatch")
+    }
+}
+
+impl Error for AeadError {}
+
+fn keystream(key: &[u8; KEY_SIZE], nonce: &[u8; NONCE_SIZE], message: &[u8]) -> Vec<u8> {
+    arx::apply_keystream(message, |block_index| chacha20_block(key, nonce, 1u32.wrapping_add(block_index as u32)))
+}
+
+fn tag(key: &[u8; KEY_SIZE], nonce: &[u8; NONCE_SIZE], aad: &[u8], ciphertext: &[u8]) -> [u8; TAG_SIZE] {
+    let block = chacha20_block(key, nonce, 0);
+    let mut data = Vec::with_capacity(aad.len() + ciphertext.len() + 48);
+    for part in [aad, ciphertext] {
+        data.extend_from_slice(part);
+        data.resize(data.len().next_multiple_of(16), 0);
+    }
+    data.extend_from_slice(&(aad.len() as u64).to_le_bytes());
+    data.extend_from_slice(&(ciphertext.len() as u64).to_le_bytes());
+    poly1305(block[..32].try_into().unwrap(), &data)
+}
+
+/// the ciphertext of `message` and the tag of it and `aad`
+pub fn chacha20_poly1305_seal(key: &[u8; KEY_SIZE], nonce: &[u8; NONCE_SIZE], aad: &[u8], message: &[u8]) -> (Vec<u8>, [u8; TAG_SIZE]) {
+    let ciphertext = keystream(key, nonce, message);
+    let tag = tag(key, nonce, aad, &ciphertext);
+    (ciphertext, tag)
+}
+
+/// the message, if `tag` matches `aad` and `ciphertext`; nothing is decrypted otherwise
+pub fn chacha20_poly1305_open(
+    key: &[u8; KEY_SIZE],
+    nonce: &[u8; NONCE_SIZE],
+    aad: &[u8],
+    ciphertext: &[u8],
+    tag: &[u8],
+) -> Result<Vec<u8>, AeadError> {
+    if !ct_eq(&self::tag(key, nonce, aad, ciphertext), tag) {
+        return Err(AeadError);
+    }
+    Ok(keystream(key, nonce, ciphertext))
+}
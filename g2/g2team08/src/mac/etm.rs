@@ -0,0 +1,118 @@
+//! Encrypt-then-MAC: the ciphertext of `C` followed by the `M` tag of that
+//! ciphertext. The key is the key of `C` followed by the key of `M`, so the
+//! two are independent, and decryption refuses any ciphertext whose tag does
+//! not check before `C` sees it.
+//!
+//! `M` must be a MAC whose key can sign many messages; one-time MACs like
+//! Poly1305 are rejected at compile time.
+
+use bytes::{Bytes, BytesMut};
+use cipher::{Key, KeyInit, KeySizeUser};
+use cipher::generic_array::ArrayLength;
+use cipher::typenum::{Sum, Unsigned};
+
+use crate::rng::CryptoRng;
+use crate::traits::{DecryptBytes, EncryptBytes, MacBytes};
+
+use alloc::vec::Vec;
+use core::{error::Error, fmt, marker::PhantomData, ops::Add};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EtmError<E> {
+    /// shorter than a tag
+    Truncated,
+    /// the tag does not match, the ciphertext was forged or altered
+    BadTag,
+    /// authentic, but the inner cipher could not decrypt it
+    Cipher(E),
+}
+
+impl<E: fmt::Display> fmt::Display for EtmError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EtmError::Truncated => write!(f, "ciphertext is shorter than its tag"),
+            EtmError::BadTag => write!(f, "authentication tag mismatch"),
+            EtmError::Cipher(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl<E: Error> Error for EtmError<E> {}
+
+pub struct EncryptThenMac<C, M>(PhantomData<(C, M)>);
+
+impl<C, M> Clone for EncryptThenMac<C, M> {
+    fn clone(&self) -> Self {
+        EncryptThenMac(PhantomData)
+    }
+}
+
+impl<C, M> KeySizeUser for EncryptThenMac<C, M>
+where
+    C: KeySizeUser<KeySize: Add<M::KeySize>>,
+    M: KeySizeUser,
+    Sum<C::KeySize, M::KeySize>: ArrayLength<u8>,
+{
+    type KeySize = Sum<C::KeySize, M::KeySize>;
+}
+
+impl<C, M> KeyInit for EncryptThenMac<C, M>
+where
+    Self: KeySizeUser,
+{
+    fn new(_: &Key<Self>) -> Self {
+        EncryptThenMac(PhantomData)
+    }
+}
+
+impl<C: KeySizeUser, M: MacBytes> EncryptThenMac<C, M>
+where
+    Self: KeySizeUser,
+{
+    fn split(key: &Key<Self>) -> (&Key<C>, &Key<M>) {
+        let (cipher_key, mac_key) = key.split_at(C::key_size());
+        (Key::<C>::from_slice(cipher_key), Key::<M>::from_slice(mac_key))
+    }
+}
+
+impl<C: EncryptBytes, M: MacBytes> EncryptBytes for EncryptThenMac<C, M>
+where
+    Self: KeySizeUser,
+{
+    fn encrypt_bytes(key: &Key<Self>, message: Bytes) -> Bytes {
+        const { assert!(!M::ONE_TIME_KEY, "encrypt-then-MAC needs a MAC whose key signs many messages") };
+        let (cipher_key, mac_key) = Self::split(key);
+        let ciphertext = C::encrypt_bytes(cipher_key, message);
+        let tag = M::mac_bytes(mac_key, &ciphertext);
+        let mut sealed = BytesMut::with_capacity(ciphertext.len() + tag.len());
+        sealed.extend_from_slice(&ciphertext);
+        sealed.extend_from_slice(&tag);
+        sealed.freeze()
+    }
+
+    fn gen_keys(mut rng: impl CryptoRng) -> Vec<u8> {
+        let mut key = C::gen_keys(&mut rng);
+        key.extend(M::gen_keys(&mut rng));
+        key
+    }
+}
+
+impl<C: DecryptBytes, M: MacBytes> DecryptBytes for EncryptThenMac<C, M>
+where
+    Self: KeySizeUser,
+{
+    type DecryptError = EtmError<C::DecryptError>;
+
+    fn decrypt_bytes(key: &Key<Self>, message: Bytes) -> Result<Bytes, Self::DecryptError> {
+        let tag_size = M::TagSize::USIZE;
+        if message.len() < tag_size {
+            return Err(EtmError::Truncated);
+        }
+        let (cipher_key, mac_key) = Self::split(key);
+        let ciphertext = message.slice(..message.len() - tag_size);
+        if !M::verify_bytes(mac_key, &ciphertext, &message[message.len() - tag_size..]) {
+            return Err(EtmError::BadTag);
+        }
+        C::decrypt_bytes(cipher_key, ciphertext).map_err(EtmError::Cipher)
+    }
+}
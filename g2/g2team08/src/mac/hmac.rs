@@ -0,0 +1,47 @@
+//! HMAC with SHA-256 (RFC 2104, FIPS 198-1).
+
+use cipher::{typenum, Key, KeyInit, KeySizeUser};
+use cipher::generic_array::GenericArray;
+
+use crate::sha256::{sha256, Sha256, BLOCK_SIZE, DIGEST_SIZE};
+use crate::traits::MacBytes;
+
+const IPAD: u8 = 0x36;
+const OPAD: u8 = 0x5c;
+
+/// HMAC-SHA256 under a key of any length; keys longer than a block are hashed first
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; DIGEST_SIZE] {
+    let mut block = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block[..DIGEST_SIZE].copy_from_slice(&sha256(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = Sha256::new();
+    inner.update(&block.map(|b| b ^ IPAD)).update(message);
+    let inner = inner.finalize();
+    let mut outer = Sha256::new();
+    outer.update(&block.map(|b| b ^ OPAD)).update(&inner);
+    outer.finalize()
+}
+
+/// HMAC-SHA256 with 32-byte keys, as long as the digest
+#[derive(Clone)]
+pub struct HmacSha256;
+
+impl KeySizeUser for HmacSha256 {
+    type KeySize = typenum::U32;
+}
+
+impl KeyInit for HmacSha256 {
+    fn new(_: &Key<Self>) -> Self { HmacSha256 }
+}
+
+impl MacBytes for HmacSha256 {
+    type TagSize = typenum::U32;
+
+    fn mac_bytes(key: &Key<Self>, message: &[u8]) -> GenericArray<u8, Self::TagSize> {
+        hmac_sha256(key, message).into()
+    }
+}
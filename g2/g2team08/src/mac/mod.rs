@@ -0,0 +1,25 @@
+//! Message authentication: Poly1305, HMAC-SHA256, encrypt-then-MAC
+//! composition of any cipher with either, the ChaCha20-Poly1305 AEAD and
+//! deterministic ChaCha20-SIV.
+
+pub mod aead;
+pub mod etm;
+pub mod hmac;
+pub mod poly1305;
+pub mod siv;
+
+pub use aead::{chacha20_poly1305_open, chacha20_poly1305_seal, AeadError};
+pub use etm::{EncryptThenMac, EtmError};
+pub use hmac::{hmac_sha256, HmacSha256};
+pub use poly1305::{poly1305, Poly1305};
+pub use siv::{chacha20_siv_open, chacha20_siv_seal, ChaCha20Siv};
+
+/// equality of two byte strings in time that depends only on their lengths,
+/// so that a forger cannot learn how many leading bytes of a tag were right
+pub fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let diff = a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y));
+    core::hint::black_box(diff) == 0
+}
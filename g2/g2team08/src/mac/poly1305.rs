@@ -0,0 +1,127 @@
+//! Poly1305 (RFC 8439, section 2.5), the polynomial MAC paired with ChaCha20.
+//!
+//! The accumulator works in radix 2^26, five limbs of `u32`, so that every
+//! product fits a `u64`. A Poly1305 key authenticates a single message: two
+//! tags under the same key give away `r` and then any forgery.
+
+use cipher::{typenum, Key, KeyInit, KeySizeUser};
+use cipher::generic_array::GenericArray;
+
+use crate::traits::MacBytes;
+
+pub const KEY_SIZE: usize = 32;
+pub const TAG_SIZE: usize = 16;
+const BLOCK_SIZE: usize = 16;
+const LIMB: u32 = (1 << 26) - 1;
+
+fn le32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes(bytes[..4].try_into().unwrap())
+}
+
+pub fn poly1305(key: &[u8; KEY_SIZE], message: &[u8]) -> [u8; TAG_SIZE] {
+    // r, clamped, split into limbs
+    let r = [
+        le32(&key[0..]) & 0x3ffffff,
+        (le32(&key[3..]) >> 2) & 0x3ffff03,
+        (le32(&key[6..]) >> 4) & 0x3ffc0ff,
+        (le32(&key[9..]) >> 6) & 0x3f03fff,
+        (le32(&key[12..]) >> 8) & 0x00fffff,
+    ]
+    .map(u64::from);
+    // 2^130 = 5 mod p folds the high limbs of products back down
+    let s = [r[1] * 5, r[2] * 5, r[3] * 5, r[4] * 5];
+    let mut h = [0u32; 5];
+
+    for chunk in message.chunks(BLOCK_SIZE) {
+        // every block gets a 1 byte appended, past its end
+        let mut block = [0u8; BLOCK_SIZE + 1];
+        block[..chunk.len()].copy_from_slice(chunk);
+        block[chunk.len()] = 1;
+
+        h[0] += le32(&block[0..]) & LIMB;
+        h[1] += (le32(&block[3..]) >> 2) & LIMB;
+        h[2] += (le32(&block[6..]) >> 4) & LIMB;
+        h[3] += (le32(&block[9..]) >> 6) & LIMB;
+        h[4] += (le32(&block[12..]) >> 8) | (u32::from(block[16]) << 24);
+
+        let [h0, h1, h2, h3, h4] = h.map(u64::from);
+        let mut d = [
+            h0 * r[0] + h1 * s[3] + h2 * s[2] + h3 * s[1] + h4 * s[0],
+            h0 * r[1] + h1 * r[0] + h2 * s[3] + h3 * s[2] + h4 * s[1],
+            h0 * r[2] + h1 * r[1] + h2 * r[0] + h3 * s[3] + h4 * s[2],
+            h0 * r[3] + h1 * r[2] + h2 * r[1] + h3 * r[0] + h4 * s[3],
+            h0 * r[4] + h1 * r[3] + h2 * r[2] + h3 * r[1] + h4 * r[0],
+        ];
+        let mut carry = 0;
+        for (limb, dd) in h.iter_mut().zip(d.iter_mut()) {
+            *dd += carry;
+            carry = *dd >> 26;
+            *limb = (*dd as u32) & LIMB;
+        }
+        h[0] += (carry * 5) as u32;
+        h[1] += h[0] >> 26;
+        h[0] &= LIMB;
+    }
+
+    // full carry, then h mod p: subtract p if h >= p, without branching on h
+    let mut carry = 0;
+    for limb in h[1..].iter_mut() {
+        *limb += carry;
+        carry = *limb >> 26;
+        *limb &= LIMB;
+    }
+    h[0] += carry * 5;
+    h[1] += h[0] >> 26;
+    h[0] &= LIMB;
+
+    let mut g = [0u32; 5];
+    let mut carry = 5;
+    for (gl, &hl) in g.iter_mut().zip(&h) {
+        *gl = hl + carry;
+        carry = *gl >> 26;
+        *gl &= LIMB;
+    }
+    g[4] = g[4].wrapping_add(carry << 26).wrapping_sub(1 << 26);
+    // all ones when h + 5 - 2^130 did not go negative, that is when h >= p
+    let use_g = (g[4] >> 31).wrapping_sub(1);
+    for (hl, gl) in h.iter_mut().zip(g) {
+        *hl = (*hl & !use_g) | (gl & use_g);
+    }
+
+    // back to four 32-bit words, plus s, mod 2^128
+    let words = [
+        h[0] | (h[1] << 26),
+        (h[1] >> 6) | (h[2] << 20),
+        (h[2] >> 12) | (h[3] << 14),
+        (h[3] >> 18) | (h[4] << 8),
+    ];
+    let mut tag = [0u8; TAG_SIZE];
+    let mut carry = 0u64;
+    for (i, (out, word)) in tag.chunks_exact_mut(4).zip(words).enumerate() {
+        let sum = u64::from(word) + u64::from(le32(&key[16 + 4 * i..])) + carry;
+        out.copy_from_slice(&(sum as u32).to_le_bytes());
+        carry = sum >> 32;
+    }
+    tag
+}
+
+/// Poly1305 with its 32-byte one-time key `r || s`
+#[derive(Clone)]
+pub struct Poly1305;
+
+impl KeySizeUser for Poly1305 {
+    type KeySize = typenum::U32;
+}
+
+impl KeyInit for Poly1305 {
+    fn new(_: &Key<Self>) -> Self { Poly1305 }
+}
+
+impl MacBytes for Poly1305 {
+    type TagSize = typenum::U16;
+    const ONE_TIME_KEY: bool = true;
+
+    fn mac_bytes(key: &Key<Self>, message: &[u8]) -> GenericArray<u8, Self::TagSize> {
+        poly1305(key.as_slice().try_into().unwrap(), message).into()
+    }
+}
@@ -0,0 +1,101 @@
+//! Deterministic authenticated encryption in the manner of SIV (RFC 5297)
+//! with ChaCha20 and HMAC-SHA256: the synthetic IV is HMAC-SHA256 of the
+//! associated data and the message, cut to 16 bytes, and the IV is both the
+//! tag and the ChaCha20 nonce (12 bytes) and initial counter (4 bytes).
+//!
+//! The same key, data and message always give the same ciphertext, and there
+//! is no nonce to reuse: two ciphertexts only share a keystream when their
+//! messages and data are equal. The key is the HMAC key followed by the
+//! ChaCha20 key, and a sealed message is the IV followed by the ciphertext.
+
+use bytes::Bytes;
+use cipher::{typenum, Key, KeyInit, KeySizeUser};
+
+use crate::arx;
+use crate::cipher2::cipher::chacha20_block;
+use crate::mac::aead::AeadError;
+use crate::mac::{ct_eq, hmac_sha256};
+use crate::rng::CryptoRng;
+use crate::traits::{DecryptBytes, EncryptBytes};
+
+use alloc::{vec, vec::Vec};
+
+pub const KEY_SIZE: usize = 64;
+pub const IV_SIZE: usize = 16;
+
+/// HMAC-SHA256 of the length of `aad`, `aad` and `message`, so that no other
+/// split of the same bytes has the same IV
+fn synthetic_iv(mac_key: &[u8], aad: &[u8], message: &[u8]) -> [u8; IV_SIZE] {
+    let mut data = Vec::with_capacity(8 + aad.len() + message.len());
+    data.extend_from_slice(&(aad.len() as u64).to_le_bytes());
+    data.extend_from_slice(aad);
+    data.extend_from_slice(message);
+    hmac_sha256(mac_key, &data)[..IV_SIZE].try_into().unwrap()
+}
+
+fn keystream(key: &[u8; 32], iv: &[u8; IV_SIZE], message: &[u8]) -> Vec<u8> {
+    let nonce: [u8; 12] = iv[..12].try_into().unwrap();
+    let counter = u32::from_le_bytes(iv[12..].try_into().unwrap());
+    arx::apply_keystream(message, |block_index| chacha20_block(key, &nonce, counter.wrapping_add(block_index as u32)))
+}
+
+fn split(key: &[u8; KEY_SIZE]) -> (&[u8], &[u8; 32]) {
+    let (mac_key, cipher_key) = key.split_at(32);
+    (mac_key, cipher_key.try_into().unwrap())
+}
+
+/// the IV followed by the ciphertext of `message`
+pub fn chacha20_siv_seal(key: &[u8; KEY_SIZE], aad: &[u8], message: &[u8]) -> Vec<u8> {
+    let (mac_key, cipher_key) = split(key);
+    let iv = synthetic_iv(mac_key, aad, message);
+    let mut sealed = iv.to_vec();
+    sealed.extend(keystream(cipher_key, &iv, message));
+    sealed
+}
+
+/// the message, if the IV at the front of `sealed` is the one of it and `aad`
+pub fn chacha20_siv_open(key: &[u8; KEY_SIZE], aad: &[u8], sealed: &[u8]) -> Result<Vec<u8>, AeadError> {
+    let Some((iv, ciphertext)) = sealed.split_first_chunk::<IV_SIZE>() else {
+        return Err(AeadError);
+    };
+    let (mac_key, cipher_key) = split(key);
+    let message = keystream(cipher_key, iv, ciphertext);
+    if !ct_eq(&synthetic_iv(mac_key, aad, &message), iv) {
+        return Err(AeadError);
+    }
+    Ok(message)
+}
+
+/// ChaCha20-SIV without associated data, as a cipher of the crate
+#[derive(Clone)]
+pub struct ChaCha20Siv;
+
+impl KeySizeUser for ChaCha20Siv {
+    type KeySize = typenum::U64;
+}
+
+impl KeyInit for ChaCha20Siv {
+    fn new(_: &Key<Self>) -> Self {
+        ChaCha20Siv
+    }
+}
+
+impl EncryptBytes for ChaCha20Siv {
+    fn encrypt_bytes(key: &Key<Self>, message: Bytes) -> Bytes {
+        chacha20_siv_seal(key.as_slice().try_into().unwrap(), &[], &message).into()
+    }
+
+    fn gen_keys(mut rng: impl CryptoRng) -> Vec<u8> {
+        let mut key = vec![0u8; KEY_SIZE];
+        rng.fill_bytes(&mut key);
+        key
+    }
+}
+
+impl DecryptBytes for ChaCha20Siv {
+    type DecryptError = AeadError;
+
+    fn decrypt_bytes(key: &Key<Self>, message: Bytes) -> Result<Bytes, Self::DecryptError> {
+        chacha20_siv_open(key.as_slice().try_into().unwrap(), &[], &message).map(Bytes::from)
+    }
+}
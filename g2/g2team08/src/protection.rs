@@ -0,0 +1,142 @@
+//! Policies deciding which decryption queries under the default key would
+//! leak the challenge plaintext.
+//!
+//! A malleable or linear cipher cannot be protected by looking at how much of
+//! the challenge a query reuses: masking every byte, or adding the encryption
+//! of a difference, hides where the material came from. The policies of such
+//! ciphers refuse every query the challenge's key material could decrypt.
+//! The classical ciphers leak their key to a handful of chosen queries
+//! anyway, so their policies only refuse queries that rework the challenge
+//! in the ways their structure lets through.
+
+use crate::classical;
+
+use std::collections::HashMap;
+
+pub trait ProtectionPolicy {
+    /// whether decrypting `query` under the default key reveals part of `challenge`
+    fn forbids(challenge: &[u8], query: &[u8]) -> bool;
+}
+
+/// Only the challenge ciphertext itself is refused.
+pub struct ExactMatch;
+
+impl ProtectionPolicy for ExactMatch {
+    fn forbids(challenge: &[u8], query: &[u8]) -> bool {
+        challenge == query
+    }
+}
+
+/// For stream ciphers whose nonce is part of the key: byte `i` of every
+/// ciphertext under the default key is masked by the same keystream byte, so
+/// the challenge masked with any `m` decrypts to the plaintext masked with
+/// `m`. Every query that overlaps the challenge's keystream positions, which
+/// all start at 0, is refused.
+pub struct SharedKeystream;
+
+impl ProtectionPolicy for SharedKeystream {
+    fn forbids(challenge: &[u8], query: &[u8]) -> bool {
+        challenge.len().min(query.len()) > 0
+    }
+}
+
+/// For the Hill ciphers, `E(x) = Kx + s` blockwise. Over Z/256, as in
+/// cipher1, this is affine: the challenge plus `E(e) - E(0)` decrypts to the
+/// plaintext plus `e`, and decryptions alone suffice, `D(c + d) - D(d) + D(0)`
+/// is the plaintext of `c`, without any query sharing a block with the
+/// challenge. Over GF(2^8), as in cipher3, `Kx` is linear under XOR but the
+/// shift `s` is still added mod 256, so `E` is not affine; yet one encryption
+/// of a zero block gives `s` in every byte, and subtracting it leaves `Kx`,
+/// on which the same sums, with XOR, work. Every query is refused.
+pub struct Affine;
+
+impl ProtectionPolicy for Affine {
+    fn forbids(challenge: &[u8], query: &[u8]) -> bool {
+        !challenge.is_empty() && !query.is_empty()
+    }
+}
+
+/// the alphabet indices of the symbols of `bytes`, other bytes left out
+fn symbols(bytes: &[u8]) -> Vec<usize> {
+    bytes.iter().filter_map(|&b| classical::index(b)).collect()
+}
+
+/// whether `shared` symbols or pairs make up most of the query and at least
+/// half of the challenge; smaller pieces of the challenge stay allowed
+fn reuses(shared: usize, query: usize, challenge: usize) -> bool {
+    shared > 0 && shared * 4 >= query * 3 && shared * 2 >= challenge
+}
+
+/// For the monoalphabetic ciphers and transposition, which keep every symbol
+/// or shift every symbol alike wherever it stands: a query holding the
+/// challenge's symbols in another order, among a few others, or shifted by
+/// the same amount, decrypts to the plaintext reordered or shifted. The
+/// symbol counts of the query, under every shift, are compared with those
+/// of the challenge.
+pub struct SharedSymbols;
+
+impl ProtectionPolicy for SharedSymbols {
+    fn forbids(challenge: &[u8], query: &[u8]) -> bool {
+        let count = |symbols: Vec<usize>| {
+            let mut counts = [0usize; classical::N];
+            symbols.into_iter().for_each(|x| counts[x] += 1);
+            counts
+        };
+        let (challenge, query) = (count(symbols(challenge)), count(symbols(query)));
+        let (challenge_len, query_len) = (challenge.iter().sum(), query.iter().sum());
+        (0..classical::N).any(|shift| {
+            let shared = (0..classical::N).map(|x| challenge[x].min(query[(x + shift) % classical::N])).sum();
+            reuses(shared, query_len, challenge_len)
+        })
+    }
+}
+
+/// For Vigenère, whose shift depends on the position: dropping a whole
+/// period of symbols, or shifting them all, keeps the challenge aligned with
+/// the key. Every alignment of the query with the challenge is tried, and
+/// under it the most common difference of their symbols counts as shared.
+pub struct AlignedSymbols;
+
+impl ProtectionPolicy for AlignedSymbols {
+    fn forbids(challenge: &[u8], query: &[u8]) -> bool {
+        let (challenge, query) = (symbols(challenge), symbols(query));
+        let offsets = -(query.len() as isize)..challenge.len() as isize;
+        offsets.into_iter().any(|offset| {
+            let mut differences = [0usize; classical::N];
+            for (j, &q) in query.iter().enumerate() {
+                if let Some(&c) = usize::try_from(j as isize + offset).ok().and_then(|i| challenge.get(i)) {
+                    differences[(q + classical::N - c) % classical::N] += 1;
+                }
+            }
+            let shared = differences.into_iter().max().unwrap_or_default();
+            reuses(shared, query.len(), challenge.len())
+        })
+    }
+}
+
+/// For Playfair, which encrypts pairs of symbols: the challenge's pairs, in
+/// another order, paired up one symbol later or each written backwards,
+/// decrypt to the plaintext's pairs. The query's pairs are compared with
+/// every pair of adjacent symbols of the challenge, in either order.
+pub struct SharedDigraphs;
+
+impl ProtectionPolicy for SharedDigraphs {
+    fn forbids(challenge: &[u8], query: &[u8]) -> bool {
+        let pair = |a: usize, b: usize| (a.min(b), a.max(b));
+        let challenge = symbols(challenge);
+        let mut adjacent: HashMap<(usize, usize), usize> = HashMap::new();
+        for window in challenge.windows(2) {
+            *adjacent.entry(pair(window[0], window[1])).or_default() += 1;
+        }
+        let query = symbols(query);
+        let pairs: Vec<(usize, usize)> = query.chunks_exact(2).map(|p| pair(p[0], p[1])).collect();
+        let mut shared = 0;
+        for p in &pairs {
+            if let Some(left) = adjacent.get_mut(p).filter(|left| **left > 0) {
+                *left -= 1;
+                shared += 1;
+            }
+        }
+        reuses(shared, pairs.len(), challenge.len() / 2)
+    }
+}
@@ -0,0 +1,106 @@
+//! Statistical randomness tests of cipher output: the core tests of NIST
+//! SP 800-22, run on the bits of a ciphertext. A good stream cipher passes
+//! them; a codebook cipher like Hill+Caesar, whose equal plaintext blocks
+//! give equal ciphertext blocks, does not.
+
+pub mod sp800_22;
+pub mod special;
+
+use clap::ValueEnum;
+
+/// significance level of every test, as recommended by SP 800-22
+pub const ALPHA: f64 = 0.01;
+
+const CORPUS: &str = include_str!("../analysis/corpus.txt");
+
+/// what the cipher encrypts for the randomness subcommand
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Plaintext {
+    /// all zero bytes, so stream ciphers give their bare keystream
+    Zeros,
+    /// English text, repeated as often as needed
+    Text,
+}
+
+impl Plaintext {
+    pub fn bytes(self, len: usize) -> Vec<u8> {
+        match self {
+            Plaintext::Zeros => vec![0; len],
+            Plaintext::Text => CORPUS.bytes().cycle().take(len).collect(),
+        }
+    }
+}
+
+/// the bits of `bytes`, most significant first, one per byte
+pub fn bits(bytes: &[u8]) -> Vec<u8> {
+    bytes.iter().flat_map(|&byte| (0..8).rev().map(move |i| (byte >> i) & 1)).collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestResult {
+    pub name: String,
+    /// empty when the sequence is too short for the test
+    pub p_values: Vec<f64>,
+}
+
+impl TestResult {
+    fn new(name: impl Into<String>, p_values: Vec<f64>) -> Self {
+        TestResult {
+            name: name.into(),
+            p_values,
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.p_values.iter().all(|&p| p >= ALPHA)
+    }
+}
+
+/// all the tests on `bits`, with the parameters SP 800-22 recommends for its length
+pub fn suite(bits: &[u8]) -> Vec<TestResult> {
+    use sp800_22::*;
+
+    let n = bits.len();
+    let log2 = n.max(1).ilog2() as usize;
+    // M ≥ 20, M > n / 100 and fewer than 100 blocks
+    let block = 20.max(n / 100 + 1);
+    // m < log2(n) - 2 for the serial test, m < log2(n) - 5 for approximate entropy
+    let serial_m = log2.saturating_sub(3).clamp(2, 16);
+    let entropy_m = log2.saturating_sub(6).clamp(1, 10);
+    let (serial_1, serial_2) = serial(bits, serial_m);
+
+    vec![
+        TestResult::new("frequency", vec![frequency(bits)]),
+        TestResult::new(format!("block frequency (M = {})", block), vec![block_frequency(bits, block)]),
+        TestResult::new("runs", vec![runs(bits)]),
+        TestResult::new("longest run of ones", longest_run(bits).into_iter().collect()),
+        TestResult::new(format!("serial (m = {})", serial_m), vec![serial_1, serial_2]),
+        TestResult::new(
+            format!("approximate entropy (m = {})", entropy_m),
+            vec![approximate_entropy(bits, entropy_m)],
+        ),
+        TestResult::new(
+            "cumulative sums (forward, backward)",
+            vec![cumulative_sums(bits, false), cumulative_sums(bits, true)],
+        ),
+        TestResult::new("spectral DFT", vec![spectral(bits)]),
+    ]
+}
+
+/// prints the p-values of every test on the bits of `output`
+pub fn run(output: &[u8]) {
+    let bits = bits(output);
+    println!("bits: {}", bits.len());
+    let results = suite(&bits);
+    for result in &results {
+        let p_values: Vec<String> = result.p_values.iter().map(|p| format!("{:.6}", p)).collect();
+        let verdict = match (result.p_values.is_empty(), result.passed()) {
+            (true, _) => "too few bits",
+            (false, true) => "pass",
+            (false, false) => "FAIL",
+        };
+        println!("{:<38} {:<20} {}", result.name, p_values.join(" "), verdict);
+    }
+    let failed = results.iter().filter(|result| !result.passed()).count();
+    println!("{} of {} tests failed at alpha = {}", failed, results.len(), ALPHA);
+}
@@ -0,0 +1,257 @@
+//! Tests from NIST SP 800-22 rev. 1a, "A Statistical Test Suite for Random
+//! and Pseudorandom Number Generators for Cryptographic Applications". Each
+//! takes a sequence of bits, one per `u8` holding 0 or 1, and returns the
+//! p-value of the hypothesis that the sequence is random; section numbers
+//! refer to the specification.
+
+use super::special::{erfc, igamc, normal_cdf};
+
+use std::f64::consts::{LN_2, SQRT_2};
+
+/// 2.1, the proportion of ones
+pub fn frequency(bits: &[u8]) -> f64 {
+    let n = bits.len() as f64;
+    let sum: i64 = bits.iter().map(|&b| 2 * b as i64 - 1).sum();
+    erfc(sum.unsigned_abs() as f64 / n.sqrt() / SQRT_2)
+}
+
+/// 2.2, the proportion of ones in each of the n/M blocks of `m` bits
+pub fn block_frequency(bits: &[u8], m: usize) -> f64 {
+    let blocks = bits.len() / m;
+    let chi_squared: f64 = bits
+        .chunks_exact(m)
+        .map(|block| {
+            let pi = block.iter().map(|&b| b as f64).sum::<f64>() / m as f64;
+            (pi - 0.5) * (pi - 0.5)
+        })
+        .sum::<f64>()
+        * 4.0
+        * m as f64;
+    igamc(blocks as f64 / 2.0, chi_squared / 2.0)
+}
+
+/// 2.3, the number of runs of identical bits
+pub fn runs(bits: &[u8]) -> f64 {
+    let n = bits.len() as f64;
+    let pi = bits.iter().map(|&b| b as f64).sum::<f64>() / n;
+    // the frequency test must pass first
+    if (pi - 0.5).abs() >= 2.0 / n.sqrt() {
+        return 0.0;
+    }
+    let runs = 1 + bits.windows(2).filter(|pair| pair[0] != pair[1]).count();
+    let expected = 2.0 * n * pi * (1.0 - pi);
+    erfc((runs as f64 - expected).abs() / (2.0 * (2.0 * n).sqrt() * pi * (1.0 - pi)))
+}
+
+/// 2.4, the longest run of ones within blocks, with the block size and
+/// classes of table 2.4.4 for the length of `bits`; needs 128 bits
+pub fn longest_run(bits: &[u8]) -> Option<f64> {
+    // block size, the longest runs counted in the first and last classes, class probabilities
+    let (m, shortest, probabilities): (usize, usize, &[f64]) = match bits.len() {
+        0..128 => return None,
+        // exact for 8-bit blocks: 55, 94, 59 and 48 of the 256 bytes
+        128..6272 => (8, 1, &[55.0 / 256.0, 94.0 / 256.0, 59.0 / 256.0, 48.0 / 256.0]),
+        6272..750_000 => (128, 4, &[0.1174, 0.2430, 0.2493, 0.1752, 0.1027, 0.1124]),
+        _ => (10_000, 10, &[0.0882, 0.2092, 0.2483, 0.1933, 0.1208, 0.0675, 0.0727]),
+    };
+    let classes = probabilities.len();
+    let mut counts = vec![0usize; classes];
+    for block in bits.chunks_exact(m) {
+        let (mut longest, mut current) = (0, 0);
+        for &b in block {
+            current = if b == 1 { current + 1 } else { 0 };
+            longest = longest.max(current);
+        }
+        counts[longest.clamp(shortest, shortest + classes - 1) - shortest] += 1;
+    }
+    let blocks = (bits.len() / m) as f64;
+    let chi_squared: f64 = counts
+        .iter()
+        .zip(probabilities)
+        .map(|(&count, &p)| (count as f64 - blocks * p).powi(2) / (blocks * p))
+        .sum();
+    Some(igamc((classes - 1) as f64 / 2.0, chi_squared / 2.0))
+}
+
+/// counts of the overlapping `m`-bit patterns of `bits`, read cyclically
+fn pattern_counts(bits: &[u8], m: usize) -> Vec<usize> {
+    let mut counts = vec![0usize; 1 << m];
+    if m == 0 {
+        counts[0] = bits.len();
+        return counts;
+    }
+    let mask = (1usize << m) - 1;
+    let mut pattern = bits[..m - 1].iter().fold(0, |acc, &b| (acc << 1) | b as usize);
+    for &b in bits[m - 1..].iter().chain(&bits[..m - 1]) {
+        pattern = ((pattern << 1) | b as usize) & mask;
+        counts[pattern] += 1;
+    }
+    counts
+}
+
+/// ψ²_m of 2.11.4, zero for m ≤ 0
+fn psi_squared(bits: &[u8], m: isize) -> f64 {
+    if m <= 0 {
+        return 0.0;
+    }
+    let n = bits.len() as f64;
+    let sum: f64 = pattern_counts(bits, m as usize).iter().map(|&c| (c * c) as f64).sum();
+    sum * (1u64 << m) as f64 / n - n
+}
+
+/// 2.11, the frequencies of all overlapping `m`-bit patterns; two p-values
+pub fn serial(bits: &[u8], m: usize) -> (f64, f64) {
+    let m = m as isize;
+    let [psi_m, psi_m1, psi_m2] = [m, m - 1, m - 2].map(|m| psi_squared(bits, m));
+    let delta = psi_m - psi_m1;
+    let delta2 = psi_m - 2.0 * psi_m1 + psi_m2;
+    (
+        igamc(2f64.powi(m as i32 - 2), delta / 2.0),
+        igamc(2f64.powi(m as i32 - 3), delta2 / 2.0),
+    )
+}
+
+/// φ^(m) of 2.12.4
+fn phi(bits: &[u8], m: usize) -> f64 {
+    let n = bits.len() as f64;
+    pattern_counts(bits, m)
+        .iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f64 / n;
+            p * p.ln()
+        })
+        .sum()
+}
+
+/// 2.12, the frequencies of overlapping `m`- and `m`+1-bit patterns compared
+pub fn approximate_entropy(bits: &[u8], m: usize) -> f64 {
+    let n = bits.len() as f64;
+    let entropy = phi(bits, m) - phi(bits, m + 1);
+    let chi_squared = 2.0 * n * (LN_2 - entropy);
+    igamc(2f64.powi(m as i32 - 1), chi_squared / 2.0)
+}
+
+/// 2.13, the largest excursion of the random walk of ±1 steps, from the
+/// start, or from the end if `backward`
+pub fn cumulative_sums(bits: &[u8], backward: bool) -> f64 {
+    let n = bits.len() as f64;
+    let step = |&b: &u8| 2 * b as i64 - 1;
+    let walk = |steps: &mut dyn Iterator<Item = i64>| {
+        steps
+            .scan(0i64, |sum, x| {
+                *sum += x;
+                Some(sum.abs())
+            })
+            .max()
+            .unwrap_or(0)
+    };
+    let z = if backward {
+        walk(&mut bits.iter().rev().map(step))
+    } else {
+        walk(&mut bits.iter().map(step))
+    } as f64;
+    let sqrt_n = n.sqrt();
+    // summation bounds in integer division, as in the reference implementation
+    let ratio = bits.len() as i64 / z as i64;
+    let term = |k: i64, a: i64, b: i64| {
+        normal_cdf((4 * k + a) as f64 * z / sqrt_n) - normal_cdf((4 * k + b) as f64 * z / sqrt_n)
+    };
+    let first: f64 = ((-ratio + 1) / 4..=(ratio - 1) / 4).map(|k| term(k, 1, -1)).sum();
+    let second: f64 = ((-ratio - 3) / 4..=(ratio - 1) / 4).map(|k| term(k, 3, 1)).sum();
+    (1.0 - first + second).clamp(0.0, 1.0)
+}
+
+/// in-place radix-2 FFT, `re` and `im` of the same power-of-two length
+fn fft(re: &mut [f64], im: &mut [f64]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+    // exp(-2πik/n), the twiddle factors of the last stage; earlier stages take every few
+    let twiddles: Vec<(f64, f64)> = (0..n / 2)
+        .map(|k| (-2.0 * std::f64::consts::PI * k as f64 / n as f64).sin_cos())
+        .collect();
+    let mut len = 2;
+    while len <= n {
+        let stride = n / len;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (w_im, w_re) = twiddles[k * stride];
+                let (a, b) = (start + k, start + k + len / 2);
+                let t_re = re[b] * w_re - im[b] * w_im;
+                let t_im = re[b] * w_im + im[b] * w_re;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// moduli of the discrete Fourier transform of `x`, of any length, by
+/// Bluestein's algorithm: a DFT is a convolution with a chirp, and the
+/// convolution is done with power-of-two FFTs
+fn dft_moduli(x: &[f64]) -> Vec<f64> {
+    let n = x.len();
+    let size = (2 * n - 1).next_power_of_two();
+    // chirp w_k = exp(-πi k² / n), with k² reduced mod 2n to keep the angle accurate
+    let chirp: Vec<(f64, f64)> = (0..n)
+        .map(|k| {
+            let angle = std::f64::consts::PI * ((k as u64 * k as u64) % (2 * n as u64)) as f64 / n as f64;
+            let (sin, cos) = angle.sin_cos();
+            (cos, -sin)
+        })
+        .collect();
+
+    let (mut a_re, mut a_im) = (vec![0.0; size], vec![0.0; size]);
+    for (k, (&xk, &(c, s))) in x.iter().zip(&chirp).enumerate() {
+        a_re[k] = xk * c;
+        a_im[k] = xk * s;
+    }
+    let (mut b_re, mut b_im) = (vec![0.0; size], vec![0.0; size]);
+    for (k, &(c, s)) in chirp.iter().enumerate() {
+        b_re[k] = c;
+        b_im[k] = -s;
+        if k > 0 {
+            b_re[size - k] = c;
+            b_im[size - k] = -s;
+        }
+    }
+    fft(&mut a_re, &mut a_im);
+    fft(&mut b_re, &mut b_im);
+    // pointwise product, then the inverse transform as a conjugated forward one
+    for i in 0..size {
+        let re = a_re[i] * b_re[i] - a_im[i] * b_im[i];
+        let im = a_re[i] * b_im[i] + a_im[i] * b_re[i];
+        a_re[i] = re;
+        a_im[i] = -im;
+    }
+    fft(&mut a_re, &mut a_im);
+    // the chirp factor has modulus 1, so only the convolution's modulus is needed
+    (0..n).map(|k| a_re[k].hypot(a_im[k]) / size as f64).collect()
+}
+
+/// 2.6, the number of peaks in the Fourier transform of the sequence
+pub fn spectral(bits: &[u8]) -> f64 {
+    let n = bits.len() as f64;
+    let x: Vec<f64> = bits.iter().map(|&b| 2.0 * b as f64 - 1.0).collect();
+    let moduli = dft_moduli(&x);
+    let threshold = ((1.0f64 / 0.05).ln() * n).sqrt();
+    let expected = 0.95 * n / 2.0;
+    let below = moduli[..bits.len() / 2].iter().filter(|&&m| m < threshold).count() as f64;
+    let d = (below - expected) / (n * 0.95 * 0.05 / 4.0).sqrt();
+    erfc(d.abs() / SQRT_2)
+}
@@ -0,0 +1,149 @@
+//! Every cipher of the crate, looked up by its [`ChallengeCipher::NAME`], for
+//! callers that choose the cipher at run time: [envelopes](crate::envelope)
+//! and the C interface of the `g2-ffi` crate.
+
+use crate::bytes::Bytes;
+use crate::cipher::Key;
+use crate::classical::*;
+use crate::envelope::{Envelope, EnvelopeError};
+use crate::keycheck::KeyInspection;
+use crate::rng::{seeded, Seed};
+use crate::traits::ChallengeCipher;
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryError {
+    KeyLength { expected: usize, found: usize },
+    /// the errors [`ChallengeCipher::inspect_key`] finds in the key
+    InvalidKey(String),
+    Encrypt(String),
+    Decrypt(String),
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::KeyLength { expected, found } => {
+                write!(f, "key of {} bytes, the cipher takes {}", found, expected)
+            }
+            RegistryError::InvalidKey(e) => write!(f, "invalid key: {}", e),
+            RegistryError::Encrypt(e) => write!(f, "encryption failed: {}", e),
+            RegistryError::Decrypt(e) => write!(f, "decryption failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+/// a cipher with its key type erased, keys are byte slices checked against
+/// [`key_size`](Self::key_size)
+#[derive(Clone, Copy)]
+pub struct RegisteredCipher {
+    pub name: &'static str,
+    key_size: fn() -> usize,
+    gen_keys: fn() -> Vec<u8>,
+    gen_keys_from: fn(Seed) -> Vec<u8>,
+    encrypt: fn(&[u8], Bytes) -> Result<Bytes, String>,
+    decrypt: fn(&[u8], Bytes) -> Result<Bytes, String>,
+    open: fn(&Envelope, &[u8]) -> Result<Bytes, EnvelopeError>,
+    inspect_key: fn(&[u8]) -> Option<KeyInspection>,
+}
+
+impl fmt::Debug for RegisteredCipher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RegisteredCipher").field("name", &self.name).finish_non_exhaustive()
+    }
+}
+
+impl RegisteredCipher {
+    const fn of<C: ChallengeCipher>() -> Self {
+        RegisteredCipher {
+            name: C::NAME,
+            key_size: C::key_size,
+            gen_keys: || C::gen_keys(rand::rng()),
+            gen_keys_from: |seed| C::gen_keys(seeded(seed)),
+            encrypt: |key, message| C::try_encrypt(Key::<C>::from_slice(key), message).map_err(|e| e.to_string()),
+            decrypt: |key, message| C::decrypt_bytes(Key::<C>::from_slice(key), message).map_err(|e| e.to_string()),
+            open: |envelope, key| envelope.open::<C>(Key::<C>::from_slice(key)),
+            inspect_key: |key| C::inspect_key(Key::<C>::from_slice(key)),
+        }
+    }
+
+    pub fn key_size(&self) -> usize {
+        (self.key_size)()
+    }
+
+    fn check_key(&self, key: &[u8]) -> Result<(), RegistryError> {
+        if key.len() == self.key_size() {
+            Ok(())
+        } else {
+            Err(RegistryError::KeyLength {
+                expected: self.key_size(),
+                found: key.len(),
+            })
+        }
+    }
+
+    /// [`check_key`](Self::check_key), and no errors in the key's inspection
+    fn check_valid_key(&self, key: &[u8]) -> Result<(), RegistryError> {
+        match self.inspect_key(key)? {
+            Some(inspection) if !inspection.is_valid() => Err(RegistryError::InvalidKey(inspection.errors.join("; "))),
+            _ => Ok(()),
+        }
+    }
+
+    /// a fresh key from the thread-local generator
+    pub fn gen_keys(&self) -> Vec<u8> {
+        (self.gen_keys)()
+    }
+
+    /// the key `--seed` would give
+    pub fn gen_keys_from(&self, seed: Seed) -> Vec<u8> {
+        (self.gen_keys_from)(seed)
+    }
+
+    /// [`ChallengeCipher::inspect_key`] for this cipher
+    pub fn inspect_key(&self, key: &[u8]) -> Result<Option<KeyInspection>, RegistryError> {
+        self.check_key(key)?;
+        Ok((self.inspect_key)(key))
+    }
+
+    pub fn encrypt(&self, key: &[u8], message: Bytes) -> Result<Bytes, RegistryError> {
+        self.check_valid_key(key)?;
+        (self.encrypt)(key, message).map_err(RegistryError::Encrypt)
+    }
+
+    pub fn decrypt(&self, key: &[u8], message: Bytes) -> Result<Bytes, RegistryError> {
+        self.check_valid_key(key)?;
+        (self.decrypt)(key, message).map_err(RegistryError::Decrypt)
+    }
+
+    /// [`Envelope::open`] for this cipher
+    pub fn open(&self, envelope: &Envelope, key: &[u8]) -> Result<Bytes, EnvelopeError> {
+        self.check_key(key).map_err(|e| match e {
+            RegistryError::KeyLength { expected, found } => EnvelopeError::KeyLength { expected, found },
+            RegistryError::InvalidKey(e) | RegistryError::Encrypt(e) | RegistryError::Decrypt(e) => EnvelopeError::Cipher(e),
+        })?;
+        (self.open)(envelope, key)
+    }
+}
+
+/// all the ciphers, in the order of their binaries
+pub static CIPHERS: [RegisteredCipher; 11] = [
+    RegisteredCipher::of::<crate::cipher1::cipher::HillCaesarCipher>(),
+    RegisteredCipher::of::<crate::cipher2::cipher::ChaCha20Cipher>(),
+    RegisteredCipher::of::<crate::cipher3::cipher::HillGf256Cipher>(),
+    RegisteredCipher::of::<crate::cipher4::cipher::Salsa20Cipher>(),
+    RegisteredCipher::of::<crate::cipher4::cipher::Salsa2012Cipher>(),
+    RegisteredCipher::of::<caesar::CaesarCipher>(),
+    RegisteredCipher::of::<affine::AffineCipher>(),
+    RegisteredCipher::of::<vigenere::VigenereCipher>(),
+    RegisteredCipher::of::<substitution::SubstitutionCipher>(),
+    RegisteredCipher::of::<playfair::PlayfairCipher>(),
+    RegisteredCipher::of::<transposition::TranspositionCipher>(),
+];
+
+pub fn find(name: &str) -> Option<&'static RegisteredCipher> {
+    CIPHERS.iter().find(|cipher| cipher.name == name)
+}
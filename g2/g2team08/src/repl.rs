@@ -0,0 +1,325 @@
+//! The repl subcommand: one session to explore the ciphers of the
+//! [registry](crate::registry) without a process per operation.
+//!
+//! Every result is kept in `_`, and `<name> = <command>` keeps it under a
+//! name too; `$name` stands for a value wherever a key, message or
+//! ciphertext goes. Keys and ciphertexts are shown in base64, messages as
+//! text. `!<n>` runs line `n` of the history again.
+
+use crate::bytes::Bytes;
+use crate::registry::{self, RegisteredCipher, CIPHERS};
+use crate::rng::parse_seed;
+
+use base64::prelude::*;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, BufRead, IsTerminal, Write};
+
+const HELP: &str = "\
+use <cipher>                 make <cipher> the active cipher
+ciphers                      list the ciphers, the active one marked with *
+gen [seed]                   a new key of the active cipher, from a 32-byte hex seed if given
+key <base64>                 a key of the active cipher
+encrypt <key> <message>      the ciphertext of the text <message>
+decrypt <key> <ciphertext>   the message of a base64 ciphertext
+inspect <value>              the fields of a key, or the bytes of any value
+compare <value> <value>      where two values differ and the 8-byte blocks they share
+<name> = <command>           keep the result as $name as well as $_
+vars                         list the values kept
+history                      list the lines entered, !<n> runs line n again
+help                         this text
+quit                         leave";
+
+/// what a value is, which decides how it is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// a key of the named cipher
+    Key(&'static str),
+    /// a ciphertext of the named cipher
+    Ciphertext(&'static str),
+    Text,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Value {
+    pub kind: Kind,
+    pub bytes: Bytes,
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.kind, std::str::from_utf8(&self.bytes)) {
+            (Kind::Text, Ok(text)) => write!(f, "{}", text),
+            _ => write!(f, "{}", BASE64_STANDARD.encode(&self.bytes)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplError(String);
+
+impl fmt::Display for ReplError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ReplError {}
+
+fn error<T>(message: impl fmt::Display) -> Result<T, ReplError> {
+    Err(ReplError(message.to_string()))
+}
+
+/// the outcome of one line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Output {
+    Value(Value),
+    /// a report that is not a value
+    Text(String),
+    Nothing,
+    Quit,
+}
+
+pub struct Repl {
+    cipher: &'static RegisteredCipher,
+    vars: BTreeMap<String, Value>,
+    history: Vec<String>,
+}
+
+impl Repl {
+    pub fn new(cipher: &'static RegisteredCipher) -> Self {
+        Repl {
+            cipher,
+            vars: BTreeMap::new(),
+            history: Vec::new(),
+        }
+    }
+
+    pub fn cipher(&self) -> &'static RegisteredCipher {
+        self.cipher
+    }
+
+    pub fn var(&self, name: &str) -> Option<&Value> {
+        self.vars.get(name)
+    }
+
+    /// runs one line and keeps it in the history
+    pub fn eval(&mut self, line: &str) -> Result<Output, ReplError> {
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(Output::Nothing);
+        }
+        // a rerun goes into the history as the line it runs
+        let line = match line.strip_prefix('!') {
+            Some(n) => match n.parse::<usize>().ok().and_then(|n| self.history.get(n.wrapping_sub(1))) {
+                Some(line) => line.clone(),
+                None => return error(format!("no line {} in the history", n)),
+            },
+            None => line.to_string(),
+        };
+        self.history.push(line.clone());
+
+        let (name, command) = match line.split_once('=') {
+            Some((name, command)) if is_name(name.trim()) => (Some(name.trim()), command.trim()),
+            _ => (None, line.as_str()),
+        };
+        let output = self.command(command)?;
+        if let Output::Value(value) = &output {
+            if let Some(name) = name {
+                self.vars.insert(name.to_string(), value.clone());
+            }
+            self.vars.insert(String::from("_"), value.clone());
+        } else if name.is_some() {
+            return error(format!("{} gives no value to keep", command.split_whitespace().next().unwrap_or_default()));
+        }
+        Ok(output)
+    }
+
+    fn command(&mut self, command: &str) -> Result<Output, ReplError> {
+        let (verb, rest) = command.split_once(char::is_whitespace).unwrap_or((command, ""));
+        let rest = rest.trim();
+        let arguments: Vec<&str> = rest.split_whitespace().collect();
+        match (verb, arguments.as_slice()) {
+            ("help", []) => Ok(Output::Text(HELP.to_string())),
+            ("quit" | "exit", []) => Ok(Output::Quit),
+            ("ciphers", []) => Ok(Output::Text(
+                CIPHERS
+                    .iter()
+                    .map(|cipher| {
+                        let active = if cipher.name == self.cipher.name { "*" } else { " " };
+                        format!("{} {} ({}-byte keys)", active, cipher.name, cipher.key_size())
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            )),
+            ("use", [name]) => match registry::find(name) {
+                Some(cipher) => {
+                    self.cipher = cipher;
+                    Ok(Output::Nothing)
+                }
+                None => error(format!("no cipher named {:?}, see ciphers", name)),
+            },
+            ("gen", []) => Ok(Output::Value(self.key(self.cipher.gen_keys().into()))),
+            ("gen", [seed]) => {
+                let seed = parse_seed(seed).map_err(|e| ReplError(e.to_string()))?;
+                Ok(Output::Value(self.key(self.cipher.gen_keys_from(seed).into())))
+            }
+            ("key", [key]) => {
+                let key = self.argument(key, |bytes| Value { kind: Kind::Key(""), bytes })?;
+                self.checked_key(&key)?;
+                Ok(Output::Value(self.key(key.bytes)))
+            }
+            ("encrypt", [key, ..]) => {
+                let key = self.key_argument(key)?;
+                let message = rest[key_end(rest)..].trim_start();
+                let message = match message.strip_prefix('$') {
+                    Some(_) => self.lookup(message)?.bytes.clone(),
+                    None => Bytes::copy_from_slice(message.as_bytes()),
+                };
+                let ciphertext = self.cipher.encrypt(&key, message).map_err(|e| ReplError(e.to_string()))?;
+                Ok(Output::Value(Value {
+                    kind: Kind::Ciphertext(self.cipher.name),
+                    bytes: ciphertext,
+                }))
+            }
+            ("decrypt", [key, ciphertext]) => {
+                let key = self.key_argument(key)?;
+                let ciphertext = self.argument(ciphertext, |bytes| Value { kind: Kind::Ciphertext(""), bytes })?;
+                let message = self.cipher.decrypt(&key, ciphertext.bytes).map_err(|e| ReplError(e.to_string()))?;
+                Ok(Output::Value(Value { kind: Kind::Text, bytes: message }))
+            }
+            ("inspect", [value]) => self.inspect(value).map(Output::Text),
+            ("compare", [a, b]) => {
+                let (a, b) = (self.lookup(a)?, self.lookup(b)?);
+                Ok(Output::Text(compare(&a.bytes, &b.bytes)))
+            }
+            ("vars", []) => Ok(Output::Text(
+                self.vars
+                    .iter()
+                    .map(|(name, value)| format!("${} {:?}, {} bytes: {}", name, value.kind, value.bytes.len(), value))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            )),
+            ("history", []) => Ok(Output::Text(
+                self.history.iter().enumerate().map(|(i, line)| format!("{:>4}  {}", i + 1, line)).collect::<Vec<_>>().join("\n"),
+            )),
+            ("help" | "quit" | "exit" | "ciphers" | "use" | "gen" | "key" | "encrypt" | "decrypt" | "inspect" | "compare" | "vars" | "history", _) => {
+                error(format!("wrong arguments to {}, see help", verb))
+            }
+            _ => error(format!("unknown command {:?}, see help", verb)),
+        }
+    }
+
+    fn key(&self, bytes: Bytes) -> Value {
+        Value { kind: Kind::Key(self.cipher.name), bytes }
+    }
+
+    fn lookup(&self, argument: &str) -> Result<&Value, ReplError> {
+        let name = argument.strip_prefix('$').unwrap_or(argument);
+        self.vars.get(name).ok_or_else(|| ReplError(format!("no value ${}", name)))
+    }
+
+    /// `$name`, or a base64 literal made into a value by `literal`
+    fn argument(&self, argument: &str, literal: impl FnOnce(Bytes) -> Value) -> Result<Value, ReplError> {
+        if argument.starts_with('$') {
+            return self.lookup(argument).cloned();
+        }
+        let bytes = BASE64_STANDARD.decode(argument).map_err(|e| ReplError(format!("{}: {}", argument, e)))?;
+        Ok(literal(bytes.into()))
+    }
+
+    /// whether `key` can be a key of the active cipher
+    fn checked_key(&self, key: &Value) -> Result<(), ReplError> {
+        match key.kind {
+            Kind::Key(name) if !name.is_empty() && name != self.cipher.name => {
+                error(format!("that is a {} key, the active cipher is {}", name, self.cipher.name))
+            }
+            _ if key.bytes.len() != self.cipher.key_size() => error(format!(
+                "{} takes {}-byte keys, that one has {} bytes",
+                self.cipher.name,
+                self.cipher.key_size(),
+                key.bytes.len()
+            )),
+            _ => match self.cipher.inspect_key(&key.bytes) {
+                Ok(Some(inspection)) if !inspection.is_valid() => error(inspection.errors.join("; ")),
+                _ => Ok(()),
+            },
+        }
+    }
+
+    fn key_argument(&self, argument: &str) -> Result<Bytes, ReplError> {
+        let key = self.argument(argument, |bytes| Value { kind: Kind::Key(""), bytes })?;
+        self.checked_key(&key)?;
+        Ok(key.bytes)
+    }
+
+    fn inspect(&self, argument: &str) -> Result<String, ReplError> {
+        let value = self.lookup(argument)?;
+        if let Kind::Key(name) = value.kind {
+            let cipher = registry::find(name).unwrap_or(self.cipher);
+            if let Ok(Some(inspection)) = cipher.inspect_key(&value.bytes) {
+                return Ok(format!("{} key\n{}", cipher.name, inspection.to_string().trim_end()));
+            }
+        }
+        let hex: String = value.bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        Ok(format!("{:?}, {} bytes\nhex: {}\nbase64: {}", value.kind, value.bytes.len(), hex, BASE64_STANDARD.encode(&value.bytes)))
+    }
+}
+
+fn is_name(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// the length of the first word of `rest`, the key of encrypt
+fn key_end(rest: &str) -> usize {
+    rest.find(char::is_whitespace).unwrap_or(rest.len())
+}
+
+/// the lengths, the differing bytes and the 8-byte blocks at the same offset
+/// in both, as a codebook cipher gives for equal plaintext blocks
+fn compare(a: &[u8], b: &[u8]) -> String {
+    let common = a.len().min(b.len());
+    let differing = (0..common).filter(|&i| a[i] != b[i]).count() + a.len().max(b.len()) - common;
+    let first = (0..common).find(|&i| a[i] != b[i]).or((a.len() != b.len()).then_some(common));
+    let blocks = a.chunks_exact(8).zip(b.chunks_exact(8)).filter(|(x, y)| x == y).count();
+    let mut lines = vec![format!("lengths {} and {}, {} bytes differ", a.len(), b.len(), differing)];
+    match first {
+        Some(i) => lines.push(format!("first difference at byte {}", i)),
+        None => lines.push(String::from("equal")),
+    }
+    lines.push(format!("{} of {} 8-byte blocks equal", blocks, common / 8));
+    lines.join("\n")
+}
+
+/// the session itself, with a prompt when `input` is a terminal
+pub fn run(cipher: &'static RegisteredCipher, input: impl BufRead, mut output: impl Write, prompt: bool) -> io::Result<()> {
+    let mut repl = Repl::new(cipher);
+    let mut lines = input.lines();
+    loop {
+        if prompt {
+            write!(output, "{}> ", repl.cipher().name)?;
+            output.flush()?;
+        }
+        let Some(line) = lines.next() else {
+            break;
+        };
+        match repl.eval(&line?) {
+            Ok(Output::Value(value)) => writeln!(output, "{}", value)?,
+            Ok(Output::Text(text)) => writeln!(output, "{}", text)?,
+            Ok(Output::Nothing) => {}
+            Ok(Output::Quit) => break,
+            Err(e) => writeln!(output, "error: {}", e)?,
+        }
+    }
+    Ok(())
+}
+
+/// the repl subcommand on standard input and output
+pub fn main(cipher: &'static RegisteredCipher) {
+    let prompt = io::stdin().is_terminal();
+    if let Err(e) = run(cipher, io::stdin().lock(), io::stdout().lock(), prompt) {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,196 @@
+//! Results of the generate/encrypt/decrypt commands, printed either as the
+//! plain lines `test.py` expects or as one JSON object per command.
+
+use crate::bytes::Bytes;
+use crate::cipher::{Key, KeySizeUser};
+use crate::keycheck::KeyInspection;
+use crate::traits::{CHEATER, TRY_HARDER};
+
+use base64::prelude::*;
+use serde::Serialize;
+use std::{fmt, fs, path::PathBuf, process};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// decryption of the challenge ciphertext was refused
+    Cheater,
+    /// the decrypted message is not a legal message
+    TryHarder,
+    InvalidKey,
+    KeyFile,
+    /// the message does not fit in what the key can encrypt
+    EncryptFailed,
+    DecryptFailed,
+    /// the envelope is malformed or was sealed for another cipher or key
+    Envelope,
+    /// a line of `batch` is not a legal message or not base64
+    InvalidInput,
+    /// the input or output of a stream could not be read or written
+    Io,
+    /// the key agent could not be reached or refused the request
+    Agent,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Failure {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl Failure {
+    pub fn new(kind: ErrorKind, message: impl fmt::Display) -> Self {
+        Failure {
+            kind,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Failure {}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Report {
+    pub cipher: &'static str,
+    pub command: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ciphertext: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plaintext: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inspection: Option<KeyInspection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<Failure>,
+}
+
+impl Report {
+    pub fn new(cipher: &'static str, command: &'static str) -> Self {
+        Report {
+            cipher,
+            command,
+            key: None,
+            key_id: None,
+            ciphertext: None,
+            plaintext: None,
+            inspection: None,
+            error: None,
+        }
+    }
+
+    pub fn failed(mut self, failure: Failure) -> Self {
+        self.error = Some(failure);
+        self
+    }
+
+    /// whether the report counts as a failure for the exit status, which the
+    /// two refusals `test.py` reads from stdout do not in text mode
+    pub fn failed_status(&self, json: bool) -> bool {
+        match &self.error {
+            Some(Failure { kind: ErrorKind::Cheater | ErrorKind::TryHarder, .. }) => json,
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    /// the result line of generate, encrypt and decrypt in text mode
+    fn output(&self) -> &str {
+        let line = match self.command {
+            "generate" => &self.key,
+            "encrypt" => &self.ciphertext,
+            _ => &self.plaintext,
+        };
+        line.as_deref().unwrap_or_default()
+    }
+
+    /// the report on one line, errors included, so that `batch` prints one
+    /// line per input line
+    pub fn line(&self, json: bool) -> String {
+        if json {
+            return serde_json::to_string(self).unwrap();
+        }
+        match &self.error {
+            Some(Failure { kind: ErrorKind::Cheater, .. }) => CHEATER.to_string(),
+            Some(Failure { kind: ErrorKind::TryHarder, .. }) => TRY_HARDER.to_string(),
+            Some(failure) => format!("error: {}", failure),
+            None => self.output().to_string(),
+        }
+    }
+
+    /// prints the report and leaves with a non-zero status on failure,
+    /// except for the two refusals `test.py` reads from stdout in text mode
+    pub fn emit(&self, json: bool) {
+        if json {
+            println!("{}", serde_json::to_string(self).unwrap());
+            if self.error.is_some() {
+                process::exit(1);
+            }
+            return;
+        }
+        match &self.error {
+            Some(Failure { kind: ErrorKind::Cheater, .. }) => println!("{}", CHEATER),
+            Some(Failure { kind: ErrorKind::TryHarder, .. }) => println!("{}", TRY_HARDER),
+            Some(failure) => {
+                eprintln!("error: {}", failure);
+                process::exit(1);
+            }
+            None if self.command == "inspect-key" => {
+                print!("{}", self.inspection.as_ref().map(ToString::to_string).unwrap_or_default());
+            }
+            None if self.command == "validate-key" => {
+                println!("valid");
+                for warning in self.inspection.iter().flat_map(|inspection| &inspection.warnings) {
+                    println!("warning: {}", warning);
+                }
+            }
+            None => println!("{}", self.output()),
+        }
+    }
+}
+
+/// short non-secret name of a key, `default` for the challenge key
+pub fn key_id(key: Option<&[u8]>) -> String {
+    match key {
+        // FNV-1a
+        Some(key) => {
+            let hash = key.iter().fold(0xcbf29ce484222325u64, |hash, &b| {
+                (hash ^ b as u64).wrapping_mul(0x100000001b3)
+            });
+            format!("{:016x}", hash)
+        }
+        None => String::from("default"),
+    }
+}
+
+/// decodes the key given on the command line, `--key` first, then `--key-file`
+pub fn load_key<T: KeySizeUser>(key: Option<Bytes>, key_file: Option<PathBuf>) -> Result<Option<Key<T>>, Failure> {
+    let encoded = match (key, key_file) {
+        (Some(key), _) => key,
+        (None, Some(path)) => fs::read_to_string(&path)
+            .map_err(|e| Failure::new(ErrorKind::KeyFile, format!("{}: {}", path.display(), e)))?
+            .trim()
+            .as_bytes()
+            .to_vec()
+            .into(),
+        (None, None) => return Ok(None),
+    };
+    let key = BASE64_STANDARD
+        .decode(encoded)
+        .map_err(|e| Failure::new(ErrorKind::InvalidKey, e))?;
+    if key.len() != T::key_size() {
+        return Err(Failure::new(
+            ErrorKind::InvalidKey,
+            format!("expected a {}-byte key, got {} bytes", T::key_size(), key.len()),
+        ));
+    }
+    Ok(Some(Key::<T>::clone_from_slice(&key)))
+}
@@ -0,0 +1,153 @@
+//! A ChaCha20 generator on [`chacha20_block`], with the output of
+//! `rand_chacha::ChaCha20Rng`: the seed is the key, the 64-bit block counter
+//! takes state words 12 and 13 and the 64-bit stream words 14 and 15, so the
+//! high half of the counter and the stream make up the IETF nonce.
+//!
+//! Like rand's block generators it hands out the keystream as little-endian
+//! words from a buffer of four blocks, and `fill_bytes` uses up whole words.
+
+use super::{CryptoRng, RngCore, SeedableRng};
+use crate::cipher2::cipher::chacha20_block;
+
+use core::fmt;
+use zeroize::Zeroize;
+
+const BLOCK_WORDS: usize = 16;
+const BUF_BLOCKS: u64 = 4;
+const BUF_WORDS: usize = BLOCK_WORDS * BUF_BLOCKS as usize;
+
+#[derive(Clone)]
+pub struct ChaCha20Rng {
+    key: [u8; 32],
+    stream: u64,
+    /// the block after the last one in `buffer`
+    block: u64,
+    buffer: [u32; BUF_WORDS],
+    /// the next word of `buffer` to hand out, `BUF_WORDS` when it is used up
+    index: usize,
+}
+
+impl ChaCha20Rng {
+    /// generates the four blocks from `self.block` on and hands them out from word `index`
+    fn refill(&mut self, index: usize) {
+        for (i, words) in self.buffer.chunks_exact_mut(BLOCK_WORDS).enumerate() {
+            let counter = self.block.wrapping_add(i as u64);
+            let mut nonce = [0u8; 12];
+            nonce[..4].copy_from_slice(&((counter >> 32) as u32).to_le_bytes());
+            nonce[4..].copy_from_slice(&self.stream.to_le_bytes());
+            let block = chacha20_block(&self.key, &nonce, counter as u32);
+            for (word, bytes) in words.iter_mut().zip(block.chunks_exact(4)) {
+                *word = u32::from_le_bytes(bytes.try_into().unwrap());
+            }
+        }
+        self.block = self.block.wrapping_add(BUF_BLOCKS);
+        self.index = index;
+    }
+
+    /// the offset from the start of the stream in 32-bit words, a 68-bit number
+    pub fn get_word_pos(&self) -> u128 {
+        let buffer_start = self.block.wrapping_sub(BUF_BLOCKS);
+        let block = buffer_start.wrapping_add((self.index / BLOCK_WORDS) as u64);
+        block as u128 * BLOCK_WORDS as u128 + (self.index % BLOCK_WORDS) as u128
+    }
+
+    /// moves to `word_offset` words from the start of the stream; the
+    /// generator cycles, so bits above the 68th are ignored
+    pub fn set_word_pos(&mut self, word_offset: u128) {
+        self.block = (word_offset / BLOCK_WORDS as u128) as u64;
+        self.refill((word_offset % BLOCK_WORDS as u128) as usize);
+    }
+
+    /// switches to another of the 2^64 streams of the seed, at the same word position
+    pub fn set_stream(&mut self, stream: u64) {
+        self.stream = stream;
+        if self.index != BUF_WORDS {
+            self.set_word_pos(self.get_word_pos());
+        }
+    }
+
+    pub fn get_stream(&self) -> u64 {
+        self.stream
+    }
+
+    pub fn get_seed(&self) -> [u8; 32] {
+        self.key
+    }
+
+    /// fast-key-erasure reseeding: the next 32 bytes of the stream become the
+    /// key and the rest of the buffer is wiped, so the state no longer tells
+    /// anything about the output so far; the new key starts at word 0 of the
+    /// same stream
+    pub fn reseed(&mut self) {
+        let mut key = [0u8; 32];
+        self.fill_bytes(&mut key);
+        self.key = key;
+        key.zeroize();
+        self.buffer.zeroize();
+        self.block = 0;
+        self.index = BUF_WORDS;
+    }
+}
+
+impl SeedableRng for ChaCha20Rng {
+    type Seed = [u8; 32];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        // an empty buffer that ends where block 0 starts, so the word position is 0
+        ChaCha20Rng {
+            key: seed,
+            stream: 0,
+            block: 0,
+            buffer: [0; BUF_WORDS],
+            index: BUF_WORDS,
+        }
+    }
+}
+
+impl RngCore for ChaCha20Rng {
+    fn next_u32(&mut self) -> u32 {
+        if self.index >= BUF_WORDS {
+            self.refill(0);
+        }
+        self.index += 1;
+        self.buffer[self.index - 1]
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let low = self.next_u32() as u64;
+        let high = self.next_u32() as u64;
+        high << 32 | low
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(4) {
+            let word = self.next_u32().to_le_bytes();
+            chunk.copy_from_slice(&word[..chunk.len()]);
+        }
+    }
+}
+
+impl CryptoRng for ChaCha20Rng {}
+
+/// the same seed, stream and word position give the same output
+impl PartialEq for ChaCha20Rng {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key && self.stream == other.stream && self.get_word_pos() == other.get_word_pos()
+    }
+}
+
+impl Eq for ChaCha20Rng {}
+
+/// the key and the buffer are secret
+impl fmt::Debug for ChaCha20Rng {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChaCha20Rng").field("stream", &self.stream).finish_non_exhaustive()
+    }
+}
+
+impl Drop for ChaCha20Rng {
+    fn drop(&mut self) {
+        self.key.zeroize();
+        self.buffer.zeroize();
+    }
+}
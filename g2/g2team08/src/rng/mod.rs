@@ -0,0 +1,55 @@
+//! The random number generators used for keys and messages.
+//!
+//! `cipher` and `rand` both declare `CryptoRng` and `RngCore` traits, from
+//! rand_core 0.6 and 0.9 respectively. Everything in this crate takes rand's,
+//! re-exported here, and never the ones of `cipher`.
+
+pub mod chacha;
+
+pub use chacha::ChaCha20Rng;
+pub use rand::rand_core::{CryptoRng, RngCore, SeedableRng};
+
+use alloc::vec::Vec;
+use core::{error::Error, fmt};
+
+pub const SEED_SIZE: usize = 32;
+
+pub type Seed = [u8; SEED_SIZE];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseSeedError {
+    InvalidDigit,
+    /// number of bytes given instead of `SEED_SIZE`
+    WrongLength(usize),
+}
+
+impl fmt::Display for ParseSeedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseSeedError::InvalidDigit => write!(f, "seed is not hexadecimal"),
+            ParseSeedError::WrongLength(len) => {
+                write!(f, "expected a {}-byte seed ({} hex digits), got {} bytes", SEED_SIZE, 2 * SEED_SIZE, len)
+            }
+        }
+    }
+}
+
+impl Error for ParseSeedError {}
+
+/// a seed written as hex digits, two per byte
+pub fn parse_seed(hex: &str) -> Result<Seed, ParseSeedError> {
+    let hex = hex.trim();
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) || !hex.len().is_multiple_of(2) {
+        return Err(ParseSeedError::InvalidDigit);
+    }
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| ParseSeedError::InvalidDigit))
+        .collect::<Result<Vec<u8>, _>>()?;
+    bytes.try_into().map_err(|bytes: Vec<u8>| ParseSeedError::WrongLength(bytes.len()))
+}
+
+/// a CSPRNG whose output only depends on `seed`, for reproducible keys
+pub fn seeded(seed: Seed) -> ChaCha20Rng {
+    ChaCha20Rng::from_seed(seed)
+}
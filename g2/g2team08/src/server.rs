@@ -0,0 +1,340 @@
+//! A line-based encryption/decryption oracle for class exercises.
+//!
+//! Every request is one line and gets exactly one reply line:
+//!
+//! * `GENERATE` -> `OK <base64 key>`
+//! * `ENCRYPT <message> [<base64 key>]` -> `OK <base64 ciphertext>`
+//! * `DECRYPT <base64 ciphertext> [<base64 key>]` -> `OK <message>`
+//! * `QUIT` closes the connection
+//!
+//! Failures are reported as `ERR <reason>`. The challenge ciphertext is
+//! protected by the same rule as [`ChallengeCipher::execute`].
+
+use crate::bytes::Bytes;
+use crate::cipher::Key;
+use crate::keycheck::load_valid_key;
+use crate::report::key_id;
+use crate::traits::{ChallengeCipher, CHEATER, TRY_HARDER};
+
+use base64::prelude::*;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Endpoint {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OracleConfig {
+    /// maximum number of generate/encrypt/decrypt queries per connection
+    pub budget: Option<usize>,
+    pub audit_log: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Query {
+    Generate,
+    Encrypt { message: Bytes, key: Option<Bytes> },
+    Decrypt { encrypted_message: Bytes, key: Option<Bytes> },
+    Quit,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseQueryError(String);
+
+impl fmt::Display for ParseQueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ParseQueryError {}
+
+impl FromStr for Query {
+    type Err = ParseQueryError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut words = line.split_whitespace();
+        let verb = words.next().unwrap_or_default().to_ascii_uppercase();
+        let argument = words.next();
+        let key = words.next().map(|key| Bytes::copy_from_slice(key.as_bytes()));
+        if words.next().is_some() {
+            return Err(ParseQueryError("too many arguments".into()));
+        }
+        let missing = || ParseQueryError(format!("{} needs an argument", verb));
+        match verb.as_str() {
+            "GENERATE" if argument.is_none() => Ok(Query::Generate),
+            "QUIT" if argument.is_none() => Ok(Query::Quit),
+            "ENCRYPT" => Ok(Query::Encrypt {
+                message: crate::cli::parse_message(argument.ok_or_else(missing)?)
+                    .map_err(|e| ParseQueryError(e.to_string()))?,
+                key,
+            }),
+            "DECRYPT" => Ok(Query::Decrypt {
+                encrypted_message: crate::cli::parse_base64(argument.ok_or_else(missing)?)
+                    .map_err(|e| ParseQueryError(e.to_string()))?,
+                key,
+            }),
+            "GENERATE" | "QUIT" => Err(ParseQueryError(format!("{} takes no argument", verb))),
+            _ => Err(ParseQueryError(format!("unknown request '{}'", verb))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reply {
+    Ok(String),
+    Err(String),
+}
+
+impl fmt::Display for Reply {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reply::Ok(value) => write!(f, "OK {}", value),
+            Reply::Err(reason) => write!(f, "ERR {}", reason),
+        }
+    }
+}
+
+fn decode_key<C: ChallengeCipher>(key: Option<Bytes>) -> Result<Key<C>, Reply> {
+    match load_valid_key::<C>(key, None) {
+        Ok(key) => Ok(key.unwrap_or_else(|| C::secret().key)),
+        Err(failure) => Err(Reply::Err(format!("invalid key: {}", failure))),
+    }
+}
+
+/// answers one query the way the command line interface would
+pub fn answer<C: ChallengeCipher>(query: Query) -> Reply {
+    let result = match query {
+        Query::Generate => Ok(BASE64_STANDARD.encode(C::gen_keys(rand::rng()))),
+        Query::Encrypt { message, key } => decode_key::<C>(key).and_then(|key| {
+            C::try_encrypt(&key, message).map(|ciphertext| BASE64_STANDARD.encode(ciphertext)).map_err(|e| Reply::Err(e.to_string()))
+        }),
+        Query::Decrypt {
+            encrypted_message,
+            key,
+        } => {
+            if C::forbids_decryption(&encrypted_message, key.is_some()) {
+                return Reply::Err(CHEATER.into());
+            }
+            decode_key::<C>(key).and_then(|key| {
+                let message = C::decrypt_bytes(&key, encrypted_message)
+                    .map_err(|e| Reply::Err(e.to_string()))?;
+                let message =
+                    String::from_utf8(message.into()).map_err(|_| Reply::Err(TRY_HARDER.into()))?;
+                crate::cli::verified_message(&message).map_err(|_| Reply::Err(TRY_HARDER.into()))?;
+                Ok(message)
+            })
+        }
+        Query::Quit => Ok(String::from("bye")),
+    };
+    match result {
+        Ok(value) => Reply::Ok(value),
+        Err(reply) => reply,
+    }
+}
+
+struct AuditLog(Option<Mutex<File>>);
+
+impl AuditLog {
+    fn open(path: Option<&PathBuf>) -> io::Result<Self> {
+        let file = match path {
+            Some(path) => Some(Mutex::new(
+                OpenOptions::new().create(true).append(true).open(path)?,
+            )),
+            None => None,
+        };
+        Ok(AuditLog(file))
+    }
+
+    /// logs the [`summary`] of a request, never its message, ciphertext or key
+    fn record(&self, connection: u64, query: usize, summary: &str, reply: &Reply) {
+        let Some(file) = &self.0 else { return };
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|t| t.as_secs())
+            .unwrap_or_default();
+        let outcome = match reply {
+            Reply::Ok(_) => "ok",
+            Reply::Err(reason) if reason == CHEATER => "cheater",
+            Reply::Err(_) => "error",
+        };
+        let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+        // the log must not take the oracle down with it
+        let _ = writeln!(
+            file,
+            "{} conn={} query={} outcome={} {}",
+            timestamp, connection, query, outcome, summary
+        );
+    }
+}
+
+/// what the audit log keeps of the request `line`, parsed as `query`; a line
+/// that does not parse may be anything, so only its length is kept
+fn summary(line: &str, query: &Result<Query, ParseQueryError>) -> String {
+    let id = |key: &Option<Bytes>| match key {
+        Some(key) => key_id(Some(&BASE64_STANDARD.decode(key).unwrap_or_else(|_| key.to_vec()))),
+        None => key_id(None),
+    };
+    match query {
+        Ok(Query::Generate) => String::from("request=GENERATE"),
+        Ok(Query::Quit) => String::from("request=QUIT"),
+        Ok(Query::Encrypt { message, key }) => format!("request=ENCRYPT len={} key={}", message.len(), id(key)),
+        Ok(Query::Decrypt { encrypted_message, key }) => {
+            format!("request=DECRYPT len={} key={}", encrypted_message.len(), id(key))
+        }
+        Err(_) => format!("request=INVALID len={}", line.len()),
+    }
+}
+
+struct Oracle {
+    config: OracleConfig,
+    audit: AuditLog,
+    connections: AtomicU64,
+}
+
+impl Oracle {
+    fn new(config: OracleConfig) -> io::Result<Arc<Self>> {
+        let audit = AuditLog::open(config.audit_log.as_ref())?;
+        Ok(Arc::new(Oracle {
+            config,
+            audit,
+            connections: AtomicU64::new(0),
+        }))
+    }
+
+    fn handle<C: ChallengeCipher>(&self, reader: impl io::Read, mut writer: impl Write) -> io::Result<()> {
+        let connection = self.connections.fetch_add(1, Ordering::Relaxed);
+        let mut queries = 0;
+        for line in BufReader::new(reader).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let query = line.parse::<Query>();
+            if query == Ok(Query::Quit) {
+                break;
+            }
+            let summary = summary(&line, &query);
+            if self.config.budget.is_some_and(|budget| queries >= budget) {
+                let reply = Reply::Err("query budget exhausted".into());
+                self.audit.record(connection, queries, &summary, &reply);
+                writeln!(writer, "{}", reply)?;
+                break;
+            }
+            queries += 1;
+            let reply = match query {
+                Ok(query) => answer::<C>(query),
+                Err(e) => Reply::Err(e.to_string()),
+            };
+            self.audit.record(connection, queries, &summary, &reply);
+            writeln!(writer, "{}", reply)?;
+            writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// a stream the oracle can read requests from and write replies to at once
+trait Connection: io::Read + Write + Send + Sized + 'static {
+    fn try_clone(&self) -> io::Result<Self>;
+}
+
+impl Connection for TcpStream {
+    fn try_clone(&self) -> io::Result<Self> {
+        TcpStream::try_clone(self)
+    }
+}
+
+impl Connection for UnixStream {
+    fn try_clone(&self) -> io::Result<Self> {
+        UnixStream::try_clone(self)
+    }
+}
+
+/// serves every connection of `incoming` on a thread of its own until accepting fails
+fn serve_connections<C: ChallengeCipher + 'static, S: Connection>(
+    incoming: impl Iterator<Item = io::Result<S>>,
+    config: OracleConfig,
+) -> io::Result<()> {
+    let oracle = Oracle::new(config)?;
+    for stream in incoming {
+        let stream = stream?;
+        let oracle = Arc::clone(&oracle);
+        thread::spawn(move || {
+            let reader = stream.try_clone()?;
+            oracle.handle::<C>(reader, stream)
+        });
+    }
+    Ok(())
+}
+
+/// serves oracle connections accepted by `listener` until it fails
+pub fn serve_tcp<C: ChallengeCipher + 'static>(listener: TcpListener, config: OracleConfig) -> io::Result<()> {
+    serve_connections::<C, _>(listener.incoming(), config)
+}
+
+pub fn serve_unix<C: ChallengeCipher + 'static>(listener: UnixListener, config: OracleConfig) -> io::Result<()> {
+    serve_connections::<C, _>(listener.incoming(), config)
+}
+
+pub fn serve<C: ChallengeCipher + 'static>(endpoint: &Endpoint, config: OracleConfig) -> io::Result<()> {
+    match endpoint {
+        Endpoint::Tcp(addr) => {
+            if !addr.ip().is_loopback() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "the oracle only listens on localhost",
+                ));
+            }
+            serve_tcp::<C>(TcpListener::bind(addr)?, config)
+        }
+        Endpoint::Unix(path) => serve_unix::<C>(UnixListener::bind(path)?, config),
+    }
+}
+
+fn exchange(reader: impl io::Read, mut writer: impl Write, requests: &[String]) -> io::Result<Vec<String>> {
+    let mut replies = BufReader::new(reader).lines();
+    let mut result = vec![];
+    for request in requests {
+        // the oracle hangs up once the budget is spent
+        let closed = |e: &io::Error| {
+            matches!(e.kind(), io::ErrorKind::BrokenPipe | io::ErrorKind::ConnectionReset)
+        };
+        match writeln!(writer, "{}", request).and_then(|_| writer.flush()) {
+            Err(e) if closed(&e) => break,
+            sent => sent?,
+        }
+        match replies.next() {
+            Some(Err(e)) if closed(&e) => break,
+            Some(reply) => result.push(reply?),
+            None => break,
+        }
+    }
+    Ok(result)
+}
+
+/// sends each request to the oracle and collects the replies
+pub fn request(endpoint: &Endpoint, requests: &[String]) -> io::Result<Vec<String>> {
+    match endpoint {
+        Endpoint::Tcp(addr) => {
+            let stream = TcpStream::connect(addr)?;
+            exchange(stream.try_clone()?, stream, requests)
+        }
+        Endpoint::Unix(path) => {
+            let stream = UnixStream::connect(path)?;
+            exchange(stream.try_clone()?, stream, requests)
+        }
+    }
+}
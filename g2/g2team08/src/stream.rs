@@ -0,0 +1,120 @@
+//! Encryption and decryption of inputs of any length in constant memory, for
+//! the `--input` option of encrypt and decrypt.
+//!
+//! The input is read in chunks of about [`CHUNK_SIZE`] bytes and each chunk
+//! goes through the [`Pieces`] of the cipher as soon as the next one shows
+//! whether it is the last. The output is byte for byte what `encrypt_bytes`
+//! or `decrypt_bytes` give for the whole input, except that Hill decryption
+//! only strips the zero padding from the last chunk. Ciphers without
+//! `Pieces`, whose output depends on the whole message, read all of it first.
+
+use crate::cipher::Key;
+use crate::traits::ChallengeCipher;
+
+use std::fmt;
+use std::io::{self, Read, Write};
+
+/// bytes read at a time, rounded down to a multiple of the cipher's block
+pub const CHUNK_SIZE: usize = 1 << 16;
+
+/// the output for the piece of the input starting `offset` bytes in, `last` for the final one
+pub type PieceFn = Box<dyn FnMut(u64, &[u8], bool) -> Result<Vec<u8>, String>>;
+
+/// how a cipher goes through a long input piece by piece
+pub struct Pieces {
+    /// every piece but the last is a multiple of `block` bytes
+    pub block: usize,
+    pub apply: PieceFn,
+}
+
+impl Pieces {
+    /// for ciphers that encrypt every `block` bytes on their own, the piece at
+    /// `offset` under `key_at(key, offset)`, which is none past the end of a
+    /// keystream
+    ///
+    /// Only the last piece is padded; the zeros that decryption strips from
+    /// the other pieces belong to the message and are put back.
+    pub fn blockwise<C: ChallengeCipher>(key: &Key<C>, block: usize, decrypt: bool, key_at: fn(&Key<C>, u64) -> Option<Key<C>>) -> Self {
+        let key = key.clone();
+        Pieces {
+            block,
+            apply: Box::new(move |offset, piece, last| {
+                let key = key_at(&key, offset).ok_or("the message runs past the end of the keystream")?;
+                if !decrypt {
+                    return C::try_encrypt(&key, piece.to_vec().into()).map(Vec::from).map_err(|e| e.to_string());
+                }
+                let mut message: Vec<u8> = C::decrypt_bytes(&key, piece.to_vec().into())
+                    .map_err(|e| e.to_string())?
+                    .into();
+                if !last {
+                    message.resize(piece.len(), 0);
+                }
+                Ok(message)
+            }),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum StreamError {
+    Io(io::Error),
+    Cipher(String),
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamError::Io(e) => write!(f, "{}", e),
+            StreamError::Cipher(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {}
+
+impl From<io::Error> for StreamError {
+    fn from(e: io::Error) -> Self {
+        StreamError::Io(e)
+    }
+}
+
+/// reads `len` bytes, fewer only at the end of the input
+fn read_chunk(input: &mut impl Read, len: usize) -> io::Result<Vec<u8>> {
+    let mut chunk = Vec::with_capacity(len);
+    input.take(len as u64).read_to_end(&mut chunk)?;
+    Ok(chunk)
+}
+
+/// encrypts, or decrypts if `decrypt`, all of `input` under `key` into `output`;
+/// returns the number of bytes read
+pub fn run<C: ChallengeCipher>(key: &Key<C>, decrypt: bool, mut input: impl Read, mut output: impl Write) -> Result<u64, StreamError> {
+    let Some(mut pieces) = C::pieces(key, decrypt) else {
+        let mut message = Vec::new();
+        let len = input.read_to_end(&mut message)? as u64;
+        let result = if decrypt {
+            C::decrypt_bytes(key, message.into()).map_err(|e| StreamError::Cipher(e.to_string()))?
+        } else {
+            C::try_encrypt(key, message.into()).map_err(|e| StreamError::Cipher(e.to_string()))?
+        };
+        output.write_all(&result)?;
+        output.flush()?;
+        return Ok(len);
+    };
+
+    let chunk_size = (CHUNK_SIZE / pieces.block).max(1) * pieces.block;
+    let mut offset = 0;
+    let mut chunk = read_chunk(&mut input, chunk_size)?;
+    loop {
+        let next = if chunk.len() == chunk_size { read_chunk(&mut input, chunk_size)? } else { Vec::new() };
+        let last = next.is_empty();
+        let result = (pieces.apply)(offset, &chunk, last).map_err(StreamError::Cipher)?;
+        output.write_all(&result)?;
+        offset += chunk.len() as u64;
+        if last {
+            break;
+        }
+        chunk = next;
+    }
+    output.flush()?;
+    Ok(offset)
+}
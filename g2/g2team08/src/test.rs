@@ -1,36 +1,38 @@
 use crate::bytes::{Bytes, BytesMut};
-use crate::cipher::{rand_core::CryptoRngCore, Key};
+use crate::cipher::Key;
+use crate::rng::CryptoRng;
 use crate::traits::{DecryptBytes, EncryptBytes};
 
 // Important Note:
 // `cipher` crate and `rand` crate both declare `CryptoRng` and `RngCore` traits.
-// This causes LOTS OF TROUBLE
+// This causes LOTS OF TROUBLE, so only the ones of `crate::rng` are used here
 
-pub fn random_message<R: CryptoRngCore>(mut rng: impl FnMut() -> R, len: usize) -> Bytes {
+pub fn random_message<R: CryptoRng>(mut rng: impl FnMut() -> R, len: usize) -> Bytes {
     let mut message = BytesMut::zeroed(len);
     rng().fill_bytes(&mut message);
     message.into()
 }
 
-pub fn check_composition_identity<T: EncryptBytes + DecryptBytes, R: CryptoRngCore>(
+pub fn check_composition_identity<T: EncryptBytes + DecryptBytes, R: CryptoRng>(
     mut rng: impl FnMut() -> R,
 ) {
-    let key = T::generate_key(rng());
+    let key = T::gen_keys(rng());
+    let key = Key::<T>::from_slice(&key);
     let message = random_message(rng, 100);
-    let encrypted_message = T::encrypt_bytes(&key, message.clone());
-    assert_eq!(message, T::decrypt_bytes(&key, encrypted_message).unwrap());
+    let encrypted_message = T::encrypt_bytes(key, message.clone());
+    assert_eq!(message, T::decrypt_bytes(key, encrypted_message).unwrap());
 }
 
-pub fn check_key_generation_and_identity<T: EncryptBytes + DecryptBytes, R: CryptoRngCore>(
+pub fn check_key_generation_and_identity<T: EncryptBytes + DecryptBytes, R: CryptoRng>(
     mut rng: impl FnMut() -> R,
     rounds: usize,
 ) {
     use std::collections::HashSet;
 
-    let keys: HashSet<Key<T>> = HashSet::new();
+    let mut keys: HashSet<Vec<u8>> = HashSet::new();
     for _ in 0..rounds {
-        let key = T::generate_key(rng());
-        assert!(!keys.contains(&key));
+        let key = T::gen_keys(rng());
+        assert!(keys.insert(key));
         check_composition_identity::<T, _>(&mut rng);
     }
 }
@@ -0,0 +1,329 @@
+//! Timing leakage tests in the manner of dudect (Reparaz, Balasch and
+//! Verbauwhede, "Dude, is my code constant time?"): an operation runs on
+//! inputs of two classes, one fixed and one random, interleaved at random,
+//! and Welch's t-test compares the timings of the two classes, over all of
+//! them and under a few percentiles that cut off the tail of interrupts and
+//! cache misses. Constant-time code keeps |t| small; above [`THRESHOLD`] the
+//! timing tells the classes apart.
+//!
+//! Hill+Caesar's key handling shows what the targets can and cannot see.
+//! Under [`Target::Key`] most random byte strings are singular keys, which
+//! `matrix_inv` gives up on right after the determinant, so they run several
+//! times faster; that only tells what the `SingularKey` error already does.
+//! Under [`Target::ValidKey`] every key is invertible: the cofactor expansion
+//! does the same work for every key and takes milliseconds, which drowns the
+//! few steps more or less that `modinv`'s loop takes on the determinant.
+//! [`Target::Modinv`] times that inversion on its own, on the determinants
+//! of valid keys, where the leak shows.
+
+use crate::bytes::Bytes;
+use crate::cipher::Key;
+use crate::rng::{seeded, ChaCha20Rng, RngCore, Seed};
+use crate::traits::ChallengeCipher;
+
+use clap::ValueEnum;
+use rand::Rng;
+use std::hint::black_box;
+use std::time::Instant;
+
+/// the |t| above which the classes are told apart, as in dudect
+pub const THRESHOLD: f64 = 4.5;
+
+/// length of the messages and ciphertexts of the fixed and random classes
+const MESSAGE_SIZE: usize = 64;
+
+/// percentiles under which the timings are tested again, besides all of them
+const CROPS: [f64; 4] = [0.5, 0.75, 0.9, 0.99];
+
+/// keys of gen_keys whose determinants the random class of [`Target::Modinv`] draws from
+const DETERMINANTS: usize = 32;
+
+/// inversions per sample of [`Target::Modinv`], one is below the clock's resolution
+const INVERSIONS: usize = 200;
+
+/// what the timing subcommand measures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Target {
+    /// encrypt_bytes under one key, of zeros or of random messages
+    Encrypt,
+    /// decrypt_bytes under one key, of one ciphertext or of random ones
+    Decrypt,
+    /// decrypt_bytes of one ciphertext under one key or under random bytes as keys
+    Key,
+    /// decrypt_bytes of one ciphertext under one key or under random keys of gen_keys,
+    /// so that only usable keys are compared
+    ValidKey,
+    /// the inversion of the key's determinant, of 1 or of the determinants of
+    /// random keys of gen_keys; only for ciphers with a determinant
+    Modinv,
+}
+
+/// the determinant of a cipher's keys and its inverse, see
+/// [`ChallengeCipher::determinant_inverse`]
+pub struct DeterminantInverse<C: ChallengeCipher> {
+    pub determinant: fn(&Key<C>) -> i32,
+    pub invert: fn(i32) -> Option<i32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Class {
+    Fixed,
+    Random,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Nanos,
+    /// time stamp counter ticks, on x86-64 only
+    Cycles,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sample {
+    pub class: Class,
+    pub nanos: u64,
+    pub cycles: Option<u64>,
+}
+
+impl Sample {
+    fn value(&self, unit: Unit) -> Option<f64> {
+        match unit {
+            Unit::Nanos => Some(self.nanos as f64),
+            Unit::Cycles => self.cycles.map(|cycles| cycles as f64),
+        }
+    }
+}
+
+fn cycles() -> Option<u64> {
+    #[cfg(target_arch = "x86_64")]
+    {
+        // SAFETY: every x86-64 processor has rdtsc
+        Some(unsafe { core::arch::x86_64::_rdtsc() })
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        None
+    }
+}
+
+/// times `operation` on `samples` inputs made by `input`, with classes drawn
+/// from `seed`; the inputs are all made before the first measurement
+pub fn measure<I>(
+    samples: usize,
+    seed: Seed,
+    mut input: impl FnMut(Class, &mut ChaCha20Rng) -> I,
+    mut operation: impl FnMut(&I),
+) -> Vec<Sample> {
+    let mut rng = seeded(seed);
+    let inputs: Vec<(Class, I)> = (0..samples)
+        .map(|_| {
+            let class = if rng.next_u32() & 1 == 0 { Class::Fixed } else { Class::Random };
+            (class, input(class, &mut rng))
+        })
+        .collect();
+    inputs
+        .iter()
+        .map(|(class, input)| {
+            let (start, start_cycles) = (Instant::now(), cycles());
+            operation(black_box(input));
+            let end_cycles = cycles();
+            let nanos = start.elapsed().as_nanos() as u64;
+            Sample {
+                class: *class,
+                nanos,
+                cycles: start_cycles.zip(end_cycles).map(|(start, end)| end.wrapping_sub(start)),
+            }
+        })
+        .collect()
+}
+
+fn mean_and_variance(values: &[f64]) -> (f64, f64) {
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let variance = values.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / (n - 1.0);
+    (mean, variance)
+}
+
+/// Welch's t statistic of the means of `a` and `b`, 0 when either has fewer than two values
+pub fn welch_t(a: &[f64], b: &[f64]) -> f64 {
+    if a.len() < 2 || b.len() < 2 {
+        return 0.0;
+    }
+    let (mean_a, variance_a) = mean_and_variance(a);
+    let (mean_b, variance_b) = mean_and_variance(b);
+    let error = (variance_a / a.len() as f64 + variance_b / b.len() as f64).sqrt();
+    if error == 0.0 {
+        return if mean_a == mean_b { 0.0 } else { f64::INFINITY.copysign(mean_a - mean_b) };
+    }
+    (mean_a - mean_b) / error
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Verdict {
+    pub unit: Unit,
+    pub fixed: usize,
+    pub random: usize,
+    pub mean_fixed: f64,
+    pub mean_random: f64,
+    /// the t of largest magnitude over all the crops
+    pub t: f64,
+    /// the percentile of the crop that gave it, `None` for all the timings
+    pub crop: Option<f64>,
+}
+
+impl Verdict {
+    pub fn leaks(&self) -> bool {
+        self.t.abs() > THRESHOLD
+    }
+}
+
+/// Welch's t-test of the timings in `unit`, `None` without them
+pub fn analyze(samples: &[Sample], unit: Unit) -> Option<Verdict> {
+    let values: Vec<(Class, f64)> = samples.iter().map(|s| s.value(unit).map(|v| (s.class, v))).collect::<Option<_>>()?;
+    let mut sorted: Vec<f64> = values.iter().map(|&(_, v)| v).collect();
+    sorted.sort_by(f64::total_cmp);
+    let split = |limit: f64| -> (Vec<f64>, Vec<f64>) {
+        let kept = values.iter().filter(|&&(_, v)| v <= limit);
+        let fixed = kept.clone().filter(|&&(class, _)| class == Class::Fixed).map(|&(_, v)| v).collect();
+        let random = kept.filter(|&&(class, _)| class == Class::Random).map(|&(_, v)| v).collect();
+        (fixed, random)
+    };
+    let (fixed, random) = split(f64::INFINITY);
+    let mean = |values: &[f64]| values.iter().sum::<f64>() / values.len().max(1) as f64;
+    let mut verdict = Verdict {
+        unit,
+        fixed: fixed.len(),
+        random: random.len(),
+        mean_fixed: mean(&fixed),
+        mean_random: mean(&random),
+        t: welch_t(&fixed, &random),
+        crop: None,
+    };
+    for percentile in CROPS {
+        let Some(&limit) = sorted.get((percentile * sorted.len() as f64) as usize) else {
+            continue;
+        };
+        let (fixed, random) = split(limit);
+        let t = welch_t(&fixed, &random);
+        if t.abs() > verdict.t.abs() {
+            verdict.t = t;
+            verdict.crop = Some(percentile);
+        }
+    }
+    Some(verdict)
+}
+
+fn random_bytes(rng: &mut ChaCha20Rng, len: usize) -> Bytes {
+    let mut bytes = vec![0u8; len];
+    rng.fill_bytes(&mut bytes);
+    bytes.into()
+}
+
+/// times `target` of `C` on `samples` inputs, all drawn from `seed`; no
+/// timings for [`Target::Modinv`] when `C` has no determinant
+pub fn measure_cipher<C: ChallengeCipher>(target: Target, samples: usize, seed: Seed) -> Vec<Sample> {
+    let mut rng = seeded(seed);
+    rng.set_stream(1);
+    let key = C::gen_keys(&mut rng);
+    let key = Key::<C>::from_slice(&key);
+    // every input of either class has a buffer of its own, or the fixed one
+    // would be the only one always in the cache
+    match target {
+        Target::Encrypt => measure(
+            samples,
+            seed,
+            |class, rng| match class {
+                Class::Fixed => Bytes::from(vec![0; MESSAGE_SIZE]),
+                Class::Random => random_bytes(rng, MESSAGE_SIZE),
+            },
+            |message| drop(black_box(C::encrypt_bytes(key, message.clone()))),
+        ),
+        Target::Decrypt => {
+            let ciphertext = C::encrypt_bytes(key, Bytes::from(vec![0; MESSAGE_SIZE]));
+            measure(
+                samples,
+                seed,
+                |class, rng| match class {
+                    Class::Fixed => Bytes::copy_from_slice(&ciphertext),
+                    Class::Random => random_bytes(rng, ciphertext.len()),
+                },
+                |ciphertext| drop(black_box(C::decrypt_bytes(key, ciphertext.clone()))),
+            )
+        }
+        Target::Key => {
+            let ciphertext = random_bytes(&mut rng, MESSAGE_SIZE);
+            measure(
+                samples,
+                seed,
+                |class, rng| match class {
+                    Class::Fixed => key.clone(),
+                    // any bytes, not only the keys gen_keys would give
+                    Class::Random => Key::<C>::clone_from_slice(&random_bytes(rng, C::key_size())),
+                },
+                |key| drop(black_box(C::decrypt_bytes(key, ciphertext.clone()))),
+            )
+        }
+        Target::ValidKey => {
+            let ciphertext = random_bytes(&mut rng, MESSAGE_SIZE);
+            measure(
+                samples,
+                seed,
+                |class, rng| match class {
+                    Class::Fixed => key.clone(),
+                    Class::Random => Key::<C>::clone_from_slice(&C::gen_keys(rng)),
+                },
+                |key| drop(black_box(C::decrypt_bytes(key, ciphertext.clone()))),
+            )
+        }
+        Target::Modinv => {
+            let Some(inverse) = C::determinant_inverse() else {
+                return Vec::new();
+            };
+            // the determinant takes far longer than its inverse, so only a few
+            // keys are drawn and their determinants shared among the samples
+            let determinants: Vec<i32> = (0..DETERMINANTS)
+                .map(|_| (inverse.determinant)(Key::<C>::from_slice(&C::gen_keys(&mut rng))))
+                .collect();
+            measure(
+                samples,
+                seed,
+                |class, rng| match class {
+                    Class::Fixed => 1,
+                    Class::Random => determinants[rng.random_range(0..determinants.len())],
+                },
+                |&det| {
+                    for _ in 0..INVERSIONS {
+                        black_box((inverse.invert)(black_box(det)));
+                    }
+                },
+            )
+        }
+    }
+}
+
+/// prints the verdict of `samples` timings of `target`, in nanoseconds and cycles
+pub fn run<C: ChallengeCipher>(target: Target, samples: usize, seed: Seed) {
+    if target == Target::Modinv && C::determinant_inverse().is_none() {
+        eprintln!("error: {} has no determinant to invert", C::NAME);
+        std::process::exit(1);
+    }
+    let timings = measure_cipher::<C>(target, samples, seed);
+    for unit in [Unit::Nanos, Unit::Cycles] {
+        let Some(verdict) = analyze(&timings, unit) else {
+            println!("{:?}: not available", unit);
+            continue;
+        };
+        let crop = verdict.crop.map_or(String::from("all timings"), |p| format!("timings under the {}th percentile", p * 100.0));
+        println!(
+            "{:?}: fixed {:.0} over {}, random {:.0} over {}, t = {:.2} on {}: {}",
+            unit,
+            verdict.mean_fixed,
+            verdict.fixed,
+            verdict.mean_random,
+            verdict.random,
+            verdict.t,
+            crop,
+            if verdict.leaks() { "LEAK" } else { "no leak found" }
+        );
+    }
+}
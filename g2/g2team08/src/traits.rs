@@ -1,23 +1,13 @@
 use crate::bytes::Bytes;
+use crate::cipher::generic_array::{ArrayLength, GenericArray};
 use crate::cipher::{Key, KeyInit};
-use crate::cli::Command;
-use rand::rand_core::{CryptoRng};
+use crate::rng::CryptoRng;
 
-use base64::{prelude::*};
-use std::{error::Error, fs};
+use alloc::{vec, vec::Vec};
+use core::error::Error;
 
-#[derive(Debug, Clone)]
-pub struct Secret<T: DecryptBytes> {
-    pub key: Key<T>,
-    pub encrypted_message: Bytes,
-}
-
-impl<T: DecryptBytes> Secret<T> {
-    pub fn secret_message(&self) -> String {
-        let message = T::decrypt_bytes(&self.key, self.encrypted_message.clone()).unwrap();
-        String::from_utf8(message.into()).unwrap()
-    }
-}
+#[cfg(feature = "cli")]
+pub use crate::challenge::{ChallengeCipher, Secret, CHEATER, TRY_HARDER};
 
 pub trait EncryptBytes: KeyInit {
     fn encrypt_bytes(key: &Key<Self>, message: Bytes) -> Bytes;
@@ -30,66 +20,24 @@ pub trait DecryptBytes: KeyInit {
     fn decrypt_bytes(key: &Key<Self>, message: Bytes) -> Result<Bytes, Self::DecryptError>;
 }
 
-pub trait ChallengeCipher: EncryptBytes + DecryptBytes {
-    fn secret() -> Secret<Self>;
+/// message authentication codes; the tag of a message is a fixed-size
+/// checksum that only holders of the key can compute
+pub trait MacBytes: KeyInit {
+    type TagSize: ArrayLength<u8>;
+
+    /// whether a key may authenticate only one message, as with Poly1305
+    const ONE_TIME_KEY: bool = false;
+
+    fn mac_bytes(key: &Key<Self>, message: &[u8]) -> GenericArray<u8, Self::TagSize>;
+
+    /// whether `tag` is the tag of `message`, compared in constant time
+    fn verify_bytes(key: &Key<Self>, message: &[u8], tag: &[u8]) -> bool {
+        crate::mac::ct_eq(&Self::mac_bytes(key, message), tag)
+    }
 
-    /// feel free to override this implementation
-    /// this one has side-channel vulnerabilities
-    fn execute(cmd: Command, rng: impl CryptoRng) {
-        match cmd {
-            Command::Generate => {
-                println!("{}", BASE64_STANDARD.encode(Self::gen_keys(rng)))
-            }
-            Command::Encrypt {
-                secret_message,
-                key_file: path,
-                key,
-            } => {
-                let key_content = match (key, path) {
-                    (Some(key), _) => {
-                        Some(BASE64_STANDARD.decode(key).unwrap())
-                    }
-                    (None, Some(path)) => {
-                        let file_content = fs::read_to_string(&path).unwrap();
-                        Some(BASE64_STANDARD.decode(file_content.trim()).unwrap())
-                    }
-                    (None, None) => None, 
-                }   .map(|k| Key::<Self>::clone_from_slice(&k))
-                    .unwrap_or(Self::secret().key);
-                let encrypted_message = Self::encrypt_bytes(&key_content, secret_message.clone());
-                println!("{}", BASE64_STANDARD.encode(encrypted_message));
-            }
-            Command::Decrypt {
-                encrypted_message,
-                key_file: path,
-                key, 
-            } => {
-                if encrypted_message == Self::secret().encrypted_message && key.is_none() {
-                    println!("cheater: it is forbidden to decrypt the challenge ciphertext");
-                    return;
-                } 
-                let key_content = match (key, path) {
-                    (Some(key), _) => {
-                        Some(BASE64_STANDARD.decode(key).unwrap())
-                    }
-                    (None, Some(path)) => {
-                        let file_content = fs::read_to_string(&path).unwrap();
-                        Some(BASE64_STANDARD.decode(file_content.trim()).unwrap())
-                    }
-                    (None, None) => None, 
-                }   .map(|k| Key::<Self>::clone_from_slice(&k))
-                    .unwrap_or(Self::secret().key);
-                let secret_message = Self::decrypt_bytes(&key_content, encrypted_message.clone()).unwrap();
-                let secret_message = match String::from_utf8(secret_message.into()) {
-                    Ok(s) => s,
-                    Err(_) => {
-                        println!("You should try harder :(");
-                        return;
-                    }
-                };
-                crate::cli::verified_message(&secret_message).unwrap();
-                println!("{}", secret_message);
-            }
-        }
+    fn gen_keys(mut rng: impl CryptoRng) -> Vec<u8> {
+        let mut key = vec![0u8; Self::key_size()];
+        rng.fill_bytes(&mut key);
+        key
     }
 }
@@ -0,0 +1,260 @@
+//! Test vectors in the JSON format of Project Wycheproof, for exchanging
+//! them with other teams: `export-vectors` writes the vectors of a cipher and
+//! `check-vectors` runs files of them, ours for any cipher of the
+//! [registry](crate::registry) and Wycheproof's ChaCha20-Poly1305 ones.
+//!
+//! Bytes are hex. `result` is `valid` when the key must turn `msg` into `ct`
+//! (and `tag`) and back, `invalid` when decryption must fail or give
+//! something else, and `acceptable` when either is fine.
+
+use crate::cipher::Key;
+use crate::cli::ALPHABET;
+use crate::envelope::Mode;
+use crate::keycheck::hex;
+use crate::mac::aead::{self, chacha20_poly1305_open, chacha20_poly1305_seal};
+use crate::registry::{self, RegisteredCipher};
+use crate::rng::{seeded, Seed};
+use crate::traits::ChallengeCipher;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// message lengths of the exported vectors in turn: empty, single bytes and
+/// the edges of Hill and keystream blocks
+const LENGTHS: [usize; 8] = [0, 1, 7, 8, 9, 63, 64, 65];
+
+/// the Wycheproof name of the AEAD, the other algorithms are cipher names
+pub const CHACHA20_POLY1305: &str = "CHACHA20-POLY1305";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VectorFile {
+    pub algorithm: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generator_version: Option<String>,
+    pub number_of_tests: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub header: Vec<String>,
+    pub test_groups: Vec<TestGroup>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestGroup {
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// in bits, like every size of Wycheproof
+    pub key_size: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iv_size: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag_size: Option<usize>,
+    pub tests: Vec<TestCase>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestCase {
+    pub tc_id: u64,
+    #[serde(default)]
+    pub comment: String,
+    pub key: String,
+    /// the nonce; a key without it is the whole key of the cipher
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iv: Option<String>,
+    /// initial block counter after a key given apart from its `iv`, 0 by
+    /// default; a field of g2 only, Wycheproof has none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub counter: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aad: Option<String>,
+    pub msg: String,
+    pub ct: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    pub result: Expected,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub flags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Expected {
+    Valid,
+    Invalid,
+    Acceptable,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VectorError {
+    UnknownAlgorithm(String),
+}
+
+impl fmt::Display for VectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VectorError::UnknownAlgorithm(name) => write!(f, "no cipher named {:?}", name),
+        }
+    }
+}
+
+impl std::error::Error for VectorError {}
+
+/// `count` vectors of `C` under keys drawn from `seed`, the same for the same arguments
+pub fn export<C: ChallengeCipher>(count: usize, seed: Seed) -> VectorFile {
+    let mut rng = seeded(seed);
+    let mut iv_size = None;
+    let tests = (0..count)
+        .map(|i| {
+            let key = C::gen_keys(&mut rng);
+            let key = Key::<C>::from_slice(&key);
+            let len = LENGTHS[i % LENGTHS.len()];
+            let message: Vec<u8> = (0..len).map(|_| ALPHABET[rng.random_range(0..ALPHABET.len())]).collect();
+            let ciphertext = C::encrypt_bytes(key, message.clone().into());
+            let iv = match C::envelope_mode(key) {
+                (Mode::Stream, iv) => Some(iv),
+                (Mode::Codebook, _) => None,
+            };
+            iv_size = iv.as_ref().map(|iv| 8 * iv.len());
+            TestCase {
+                tc_id: i as u64 + 1,
+                comment: format!("message of {} bytes", len),
+                key: hex(key),
+                iv: iv.map(|iv| hex(&iv)),
+                counter: None,
+                aad: None,
+                msg: hex(&message),
+                ct: hex(&ciphertext),
+                tag: None,
+                result: Expected::Valid,
+                flags: Vec::new(),
+            }
+        })
+        .collect();
+    VectorFile {
+        algorithm: C::NAME.to_string(),
+        generator_version: Some(format!("g2 {}", env!("CARGO_PKG_VERSION"))),
+        number_of_tests: count,
+        header: vec!["keys from the ChaCha20 generator of g2::rng, messages over the alphabet of g2::cli".to_string()],
+        test_groups: vec![TestGroup {
+            kind: "G2CipherTest".to_string(),
+            key_size: 8 * C::key_size(),
+            iv_size,
+            tag_size: None,
+            tests,
+        }],
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub tc_id: u64,
+    pub comment: String,
+    /// why the test failed
+    pub failure: Option<String>,
+}
+
+enum Algorithm {
+    ChaCha20Poly1305,
+    Cipher(&'static RegisteredCipher),
+}
+
+fn unhex(field: &str, hex: &str) -> Result<Vec<u8>, String> {
+    if !hex.is_ascii() || !hex.len().is_multiple_of(2) {
+        return Err(format!("{} is not hex", field));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| format!("{} is not hex", field)))
+        .collect()
+}
+
+fn optional(field: &str, hex: Option<&String>) -> Result<Vec<u8>, String> {
+    hex.map_or(Ok(Vec::new()), |hex| unhex(field, hex))
+}
+
+/// what the implementation does with a test
+struct Run {
+    /// the ciphertext and tag of the message under the key
+    sealed: Vec<u8>,
+    tag: Vec<u8>,
+    /// the decryption of the ciphertext of the test
+    opened: Result<Vec<u8>, String>,
+}
+
+fn run(algorithm: &Algorithm, case: &TestCase) -> Result<Run, String> {
+    let key = unhex("key", &case.key)?;
+    let iv = optional("iv", case.iv.as_ref())?;
+    let message = unhex("msg", &case.msg)?;
+    let ciphertext = unhex("ct", &case.ct)?;
+    match algorithm {
+        Algorithm::ChaCha20Poly1305 => {
+            let key: [u8; aead::KEY_SIZE] = key[..].try_into().map_err(|_| format!("key of {} bytes", key.len()))?;
+            let nonce: [u8; aead::NONCE_SIZE] = iv[..].try_into().map_err(|_| format!("iv of {} bytes", iv.len()))?;
+            let aad = optional("aad", case.aad.as_ref())?;
+            let tag = optional("tag", case.tag.as_ref())?;
+            let (sealed, sealed_tag) = chacha20_poly1305_seal(&key, &nonce, &aad, &message);
+            Ok(Run {
+                sealed,
+                tag: sealed_tag.to_vec(),
+                opened: chacha20_poly1305_open(&key, &nonce, &aad, &ciphertext, &tag).map_err(|e| e.to_string()),
+            })
+        }
+        Algorithm::Cipher(cipher) => {
+            // a key given apart from its nonce, as Wycheproof does, is
+            // followed by the nonce and the initial counter
+            let mut key = key;
+            if key.len() < cipher.key_size() && case.iv.is_some() {
+                key.extend_from_slice(&iv);
+                let counter = case.counter.unwrap_or(0).to_le_bytes();
+                let rest = cipher.key_size().saturating_sub(key.len()).min(counter.len());
+                key.extend_from_slice(&counter[..rest]);
+            }
+            Ok(Run {
+                sealed: cipher.encrypt(&key, message.into()).map_err(|e| e.to_string())?.into(),
+                tag: Vec::new(),
+                opened: cipher.decrypt(&key, ciphertext.into()).map(Vec::from).map_err(|e| e.to_string()),
+            })
+        }
+    }
+}
+
+fn check_case(algorithm: &Algorithm, case: &TestCase) -> Option<String> {
+    // whether the ciphertext and tag match, and whether decryption gives the message
+    let result = run(algorithm, case).and_then(|run| {
+        let expected_tag = optional("tag", case.tag.as_ref())?;
+        let message = unhex("msg", &case.msg)?;
+        Ok((hex(&run.sealed) == case.ct.to_lowercase() && run.tag == expected_tag, run.opened.map(|opened| opened == message)))
+    });
+    match (case.result, result) {
+        (Expected::Acceptable, _) => None,
+        (Expected::Valid, Ok((true, Ok(true)))) => None,
+        (Expected::Valid, Ok((false, _))) => Some("the ciphertext or tag differs".to_string()),
+        (Expected::Valid, Ok((true, Ok(false)))) => Some("decrypts to another message".to_string()),
+        (Expected::Valid, Ok((true, Err(e)))) | (Expected::Valid, Err(e)) => Some(e),
+        (Expected::Invalid, Ok((_, Ok(true)))) => Some("accepted".to_string()),
+        (Expected::Invalid, _) => None,
+    }
+}
+
+/// the outcome of every test of `file`, in order
+pub fn check(file: &VectorFile) -> Result<Vec<Outcome>, VectorError> {
+    let algorithm = if file.algorithm.eq_ignore_ascii_case(CHACHA20_POLY1305) {
+        Algorithm::ChaCha20Poly1305
+    } else {
+        let cipher = registry::find(&file.algorithm.to_lowercase())
+            .ok_or_else(|| VectorError::UnknownAlgorithm(file.algorithm.clone()))?;
+        Algorithm::Cipher(cipher)
+    };
+    Ok(file
+        .test_groups
+        .iter()
+        .flat_map(|group| &group.tests)
+        .map(|case| Outcome {
+            tc_id: case.tc_id,
+            comment: case.comment.clone(),
+            failure: check_case(&algorithm, case),
+        })
+        .collect())
+}
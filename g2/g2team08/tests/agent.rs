@@ -0,0 +1,123 @@
+#![cfg(feature = "cli")]
+
+use g2::agent::{Agent, Request, SOCKET_ENV};
+use g2::server::Reply;
+
+use base64::prelude::*;
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Output, Stdio};
+use std::time::Duration;
+
+fn ask(agent: &Agent, line: &str) -> Reply {
+    match line.parse::<Request>() {
+        Ok(request) => agent.answer(request),
+        Err(e) => Reply::Err(e.to_string()),
+    }
+}
+
+fn ok(reply: Reply) -> String {
+    match reply {
+        Reply::Ok(value) => value,
+        Reply::Err(reason) => panic!("ERR {}", reason),
+    }
+}
+
+#[test]
+fn agent_answers_by_key_id() {
+    let agent = Agent::new();
+    let key = BASE64_STANDARD.encode([7u8; 48]);
+    let id = ok(ask(&agent, &format!("ADD chacha20 {}", key)));
+    assert_eq!(id.len(), 16);
+    assert_eq!(ok(ask(&agent, "LIST")), format!("{}:chacha20", id));
+
+    let message = BASE64_STANDARD.encode("Hello");
+    let ciphertext = ok(ask(&agent, &format!("ENCRYPT chacha20 {} {}", id, message)));
+    assert_eq!(ok(ask(&agent, &format!("DECRYPT chacha20 {} {}", id, ciphertext))), message);
+
+    // the key belongs to one cipher, and forgotten keys are gone
+    assert!(matches!(ask(&agent, &format!("ENCRYPT salsa20 {} {}", id, message)), Reply::Err(e) if e.contains("chacha20 key")));
+    assert_eq!(ok(ask(&agent, &format!("REMOVE {}", id))), id);
+    assert_eq!(ask(&agent, &format!("ENCRYPT chacha20 {} {}", id, message)), Reply::Err(format!("no key {}", id)));
+    assert_eq!(ok(ask(&agent, "LIST")), "");
+}
+
+#[test]
+fn agent_rejects_bad_requests() {
+    let agent = Agent::new();
+    for line in ["ADD chacha20 AAAA", "ADD rot13 AAAA", "ADD chacha20 !!", "LIST all", "PING", "REMOVE"] {
+        assert!(matches!(ask(&agent, line), Reply::Err(_)), "{}", line);
+    }
+}
+
+fn run(bin: &str, args: &[&str], socket: Option<&Path>, stdin: &[u8]) -> Output {
+    let mut command = Command::new(bin);
+    command.args(args).env_remove(SOCKET_ENV);
+    if let Some(socket) = socket {
+        command.env(SOCKET_ENV, socket);
+    }
+    let mut child = command.stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn().unwrap();
+    child.stdin.take().unwrap().write_all(stdin).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout.clone()).unwrap().trim().to_string()
+}
+
+struct Running(Child, PathBuf);
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+        let _ = std::fs::remove_file(&self.1);
+    }
+}
+
+#[test]
+fn cipher_binaries_use_the_agent() {
+    let g2 = env!("CARGO_BIN_EXE_g2");
+    let cipher1 = env!("CARGO_BIN_EXE_cipher1");
+    let socket = std::env::temp_dir().join(format!("g2-agent-{}.sock", std::process::id()));
+    let child = Command::new(g2).args(["agent", "--socket", socket.to_str().unwrap()]).spawn().unwrap();
+    let _agent = Running(child, socket.clone());
+    while !socket.exists() {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(std::fs::metadata(&socket).unwrap().permissions().mode() & 0o777, 0o600);
+
+    let key = stdout(&run(cipher1, &["generate"], None, b""));
+    let id = stdout(&run(g2, &["add", "hill-caesar"], Some(&socket), key.as_bytes()));
+    assert_eq!(stdout(&run(g2, &["list"], Some(&socket), b"")), format!("{} hill-caesar", id));
+
+    let ciphertext = stdout(&run(cipher1, &["encrypt", "Hello", "--key-id", &id], Some(&socket), b""));
+    assert_eq!(ciphertext, stdout(&run(cipher1, &["encrypt", "Hello", "-k", &key], None, b"")));
+    assert_eq!(stdout(&run(cipher1, &["decrypt", &ciphertext, "--key-id", &id], Some(&socket), b"")), "Hello");
+
+    let output = run(cipher1, &["--json", "encrypt", "Hello", "--key-id", &id], None, b"");
+    assert!(!output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["error"]["kind"], "agent");
+
+    let output = run(cipher1, &["encrypt", "Hello", "--key-id", "0123456789abcdef"], Some(&socket), b"");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no key 0123456789abcdef"));
+
+    // without --key-id the only key held for the cipher is used
+    assert_eq!(stdout(&run(cipher1, &["encrypt", "Hello"], Some(&socket), b"")), ciphertext);
+    assert_eq!(stdout(&run(cipher1, &["decrypt", &ciphertext], Some(&socket), b"")), "Hello");
+    let other = stdout(&run(cipher1, &["generate"], None, b""));
+    let other_id = stdout(&run(g2, &["add", "hill-caesar"], Some(&socket), other.as_bytes()));
+    let output = run(cipher1, &["encrypt", "Hello"], Some(&socket), b"");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("choose one with --key-id"));
+
+    stdout(&run(g2, &["remove", &id], Some(&socket), b""));
+    stdout(&run(g2, &["remove", &other_id], Some(&socket), b""));
+    assert_eq!(stdout(&run(g2, &["list"], Some(&socket), b"")), "");
+    // and the default key when the agent holds none
+    assert_eq!(stdout(&run(cipher1, &["encrypt", "Hello"], Some(&socket), b"")), stdout(&run(cipher1, &["encrypt", "Hello"], None, b"")));
+    // a second agent does not take over the socket
+    assert!(!run(g2, &["agent"], Some(&socket), b"").status.success());
+}
@@ -0,0 +1,94 @@
+#![cfg(feature = "cli")]
+
+use g2::analysis::solvers::{column_coincidence, solve_caesar, solve_substitution, solve_vigenere};
+use g2::analysis::stats::{english, friedman, index_of_coincidence, kasiski, symbols};
+use g2::bytes::Bytes;
+use g2::cipher::Key;
+use g2::classical::caesar::CaesarCipher;
+use g2::classical::substitution::SubstitutionCipher;
+use g2::classical::vigenere::VigenereCipher;
+use g2::classical::N;
+use g2::rand::{rngs::StdRng, SeedableRng};
+use g2::traits::{ChallengeCipher, EncryptBytes};
+
+const TEXT: &str = "ItwasthebestoftimesitwastheworstoftimesitwastheageofwisdomitwastheageoffoolishnessitwastheepochofbeliefitwastheepochofincredulityitwastheseasonofLightitwastheseasonofDarkness,itwasthespringofhope,itwasthewinterofdespair.";
+
+fn encrypt<C: EncryptBytes>(key: &[u8], message: &[u8]) -> Vec<u8> {
+    C::encrypt_bytes(Key::<C>::from_slice(key), Bytes::copy_from_slice(message)).to_vec()
+}
+
+fn plaintext<C: ChallengeCipher>() -> Vec<u8> {
+    let secret = C::secret();
+    C::decrypt_bytes(&secret.key, secret.encrypted_message).unwrap().to_vec()
+}
+
+#[test]
+fn coincidence_separates_english_from_random() {
+    let english_symbols = symbols(TEXT.as_bytes());
+    let uniform: Vec<usize> = (0..N * 4).map(|i| i % N).collect();
+    assert!(index_of_coincidence(&english_symbols) > 2.0 * english().coincidence / 3.0);
+    assert!(index_of_coincidence(&uniform) < 1.0 / N as f64);
+    assert!(friedman(&english_symbols) < 2.0);
+}
+
+#[test]
+fn kasiski_and_columns_find_vigenere_period() {
+    let mut key = vec![4u8, 10, 51, 3, 27, 60];
+    key.resize(17, 0);
+    let ct = symbols(&encrypt::<VigenereCipher>(&key, TEXT.as_bytes()));
+    let (period, _) = kasiski(&ct, 16)[0];
+    assert_eq!(period % 5, 0, "{:?}", kasiski(&ct, 16));
+    assert!(column_coincidence(&ct, 5) > column_coincidence(&ct, 4));
+    assert!(column_coincidence(&ct, 5) > column_coincidence(&ct, 3));
+}
+
+#[test]
+fn caesar_solver_recovers_every_shift() {
+    for k in [1u8, 13, 42, 68] {
+        let best = &solve_caesar(&encrypt::<CaesarCipher>(&[k], TEXT.as_bytes()))[0];
+        assert_eq!(best.key, vec![k]);
+        assert_eq!(best.plaintext, TEXT.as_bytes());
+    }
+}
+
+#[test]
+fn vigenere_solver_recovers_challenge() {
+    let secret = VigenereCipher::secret();
+    let best = &solve_vigenere(&secret.encrypted_message, 3)[0];
+    assert_eq!(best.plaintext, plaintext::<VigenereCipher>());
+    let ct = VigenereCipher::encrypt_bytes(Key::<VigenereCipher>::from_slice(&best.key), Bytes::from(best.plaintext.clone()));
+    assert_eq!(ct, secret.encrypted_message);
+}
+
+#[test]
+fn substitution_solver_recovers_most_of_challenge() {
+    let secret = SubstitutionCipher::secret();
+    let expected = plaintext::<SubstitutionCipher>();
+    let best = &solve_substitution(&secret.encrypted_message, 1, 60_000, StdRng::seed_from_u64(475))[0];
+    let correct = best.plaintext.iter().zip(&expected).filter(|(a, b)| a == b).count();
+    assert!(correct * 10 >= expected.len() * 9, "{}", String::from_utf8_lossy(&best.plaintext));
+}
+
+#[test]
+fn substitution_restarts_keep_their_best_table() {
+    let secret = SubstitutionCipher::secret();
+    let start = solve_substitution(&secret.encrypted_message, 0, 0, StdRng::seed_from_u64(1))[0].score;
+    for seed in 0..8 {
+        // a short, hot run wanders downhill, the best table it saw is still returned
+        let candidates = solve_substitution(&secret.encrypted_message, 0, 50, StdRng::seed_from_u64(seed));
+        assert!(candidates[0].score >= start, "seed {seed}");
+    }
+    // restarts from random tables are independent runs, each with its own result
+    let candidates = solve_substitution(&secret.encrypted_message, 3, 0, StdRng::seed_from_u64(2));
+    assert_eq!(candidates.len(), 4);
+}
+
+#[test]
+fn substitution_keys_without_symbols_are_cipher_keys() {
+    // with every count the same, the starting table is the same for both
+    // ciphertexts, and so must be the key it gives
+    let alphabet = solve_substitution(g2::cli::ALPHABET, 0, 0, StdRng::seed_from_u64(3));
+    let blank = solve_substitution(b"  \n", 0, 0, StdRng::seed_from_u64(3));
+    assert_eq!(blank[0].key, alphabet[0].key);
+    assert_eq!(blank[0].plaintext, b"  \n");
+}
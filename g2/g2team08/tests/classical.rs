@@ -0,0 +1,96 @@
+#![cfg(feature = "cli")]
+
+use g2::bytes::Bytes;
+use g2::cipher::Key;
+use g2::classical::affine::AffineCipher;
+use g2::classical::caesar::CaesarCipher;
+use g2::classical::playfair::PlayfairCipher;
+use g2::classical::substitution::SubstitutionCipher;
+use g2::classical::transposition::TranspositionCipher;
+use g2::classical::vigenere::VigenereCipher;
+use g2::cli::{verified_message, ALPHABET};
+use g2::keycheck::{load_valid_key, KeyCheck};
+use g2::registry;
+use g2::rand::{self, RngCore};
+use g2::traits::{ChallengeCipher, DecryptBytes, EncryptBytes};
+
+use base64::prelude::*;
+
+fn encrypt<C: EncryptBytes>(key: &[u8], message: &str) -> String {
+    let encrypted_message = C::encrypt_bytes(Key::<C>::from_slice(key), Bytes::copy_from_slice(message.as_bytes()));
+    String::from_utf8(encrypted_message.to_vec()).unwrap()
+}
+
+fn check_round_trip<C: ChallengeCipher>() {
+    let mut rng = rand::rng();
+    for len in [0, 1, 2, 3, 17, 100, 257] {
+        let key = C::gen_keys(rand::rng());
+        let key = Key::<C>::from_slice(&key);
+        let mut message = vec![0u8; len];
+        rng.fill_bytes(&mut message);
+        let message = Bytes::from(message);
+        let encrypted_message = C::encrypt_bytes(key, message.clone());
+        assert_eq!(encrypted_message.len(), len);
+        assert_eq!(C::decrypt_bytes(key, encrypted_message).unwrap(), message, "{}", C::NAME);
+    }
+    let secret = C::secret();
+    verified_message(&secret.secret_message()).unwrap();
+}
+
+#[test]
+fn alphabet_matches_verified_message() {
+    for b in 0..128u8 {
+        let c = (b as char).to_string();
+        assert_eq!(verified_message(&c).is_ok(), ALPHABET.contains(&b), "{:?}", c);
+    }
+}
+
+#[test]
+fn round_trips() {
+    check_round_trip::<CaesarCipher>();
+    check_round_trip::<AffineCipher>();
+    check_round_trip::<VigenereCipher>();
+    check_round_trip::<SubstitutionCipher>();
+    check_round_trip::<PlayfairCipher>();
+    check_round_trip::<TranspositionCipher>();
+}
+
+#[test]
+fn known_answers() {
+    assert_eq!(encrypt::<CaesarCipher>(&[3], "Attack at dawn, (z9)"), "Dwwdfn dw gdzq? B2;C");
+    // a = 2 is invertible modulo 69, a = 3 is not
+    assert_eq!(encrypt::<AffineCipher>(&[2, 1], "ABC"), "BDF");
+    assert!(AffineCipher::decrypt_bytes(Key::<AffineCipher>::from_slice(&[3, 0]), Bytes::new()).is_err());
+    let mut vigenere = vec![2, 0, 1, 2];
+    vigenere.resize(17, 0);
+    assert_eq!(encrypt::<VigenereCipher>(&vigenere, "AAA-AAAA"), "ABC-ABCA");
+    let mut transposition = vec![2, 2, 0, 1];
+    transposition.resize(17, 0);
+    assert_eq!(encrypt::<TranspositionCipher>(&transposition, "WEAREDISCOVERED"), "EESVEADCEDWRIOR");
+}
+
+#[test]
+fn playfair_rules() {
+    // identity grid: row r holds ALPHABET[23 r .. 23 r + 23]
+    let grid: Vec<u8> = (0..69).collect();
+    // same row, same column, rectangle, doubled symbol, trailing single symbol
+    assert_eq!(encrypt::<PlayfairCipher>(&grid, "AB"), "BC");
+    assert_eq!(encrypt::<PlayfairCipher>(&grid, "AX"), "Xu");
+    assert_eq!(encrypt::<PlayfairCipher>(&grid, "Ax"), "Du");
+    assert_eq!(encrypt::<PlayfairCipher>(&grid, "AA"), "YY");
+    assert_eq!(encrypt::<PlayfairCipher>(&grid, "AB-C"), "BC-a");
+}
+
+#[test]
+fn affine_keys_without_inverse_are_rejected() {
+    for a in [0u8, 3, 23, 69, 138] {
+        let key = [a, 5];
+        let inspection = AffineCipher::check(Key::<AffineCipher>::from_slice(&key));
+        assert!(!inspection.is_valid(), "a = {a} accepted");
+        let encoded = Bytes::from(BASE64_STANDARD.encode(key));
+        assert!(load_valid_key::<AffineCipher>(Some(encoded), None).is_err(), "a = {a} loaded");
+        let affine = registry::find("affine").unwrap();
+        assert!(affine.encrypt(&key, Bytes::from_static(b"ABC")).is_err(), "a = {a} encrypts");
+    }
+    assert!(AffineCipher::check(Key::<AffineCipher>::from_slice(&[2, 5])).is_valid());
+}
@@ -0,0 +1,163 @@
+#![cfg(feature = "cli")]
+
+use g2::bytes::Bytes;
+use g2::cipher::Key;
+use g2::cipher1::cipher::HillCaesarCipher;
+use g2::cipher2::cipher::ChaCha20Cipher;
+use g2::classical::vigenere::VigenereCipher;
+use g2::envelope::{fingerprint, open_any, Envelope, EnvelopeError, Mode, MAGIC, TAG_SIZE};
+use g2::rand;
+use g2::traits::{ChallengeCipher, EncryptBytes};
+
+use base64::prelude::*;
+use std::process::{Command, Output};
+
+fn run(bin: &str, args: &[&str]) -> (serde_json::Value, Output) {
+    let output = Command::new(bin).arg("--json").args(args).output().unwrap();
+    let report = serde_json::from_slice(&output.stdout).unwrap();
+    (report, output)
+}
+
+fn key<C: EncryptBytes>() -> Key<C> {
+    Key::<C>::clone_from_slice(&C::gen_keys(rand::rng()))
+}
+
+#[test]
+fn round_trip_through_bytes() {
+    let hill = key::<HillCaesarCipher>();
+    for authenticate in [false, true] {
+        let envelope = Envelope::seal::<HillCaesarCipher>(&hill, Bytes::from_static(b"Hello"), authenticate).unwrap();
+        assert_eq!(envelope.cipher_id, 1);
+        assert_eq!(envelope.mode, Mode::Codebook);
+        assert!(envelope.iv.is_empty());
+        assert_eq!(envelope.tag.is_some(), authenticate);
+        let bytes = envelope.to_bytes();
+        assert!(bytes.starts_with(MAGIC));
+        let parsed = Envelope::parse(&bytes).unwrap();
+        assert_eq!(parsed, envelope);
+        assert_eq!(parsed.open::<HillCaesarCipher>(&hill).unwrap(), &b"Hello"[..]);
+        assert_eq!(open_any(&bytes, &hill).unwrap(), ("hill-caesar", Bytes::from_static(b"Hello")));
+    }
+}
+
+#[test]
+fn stream_ciphers_record_nonce_and_counter() {
+    let chacha = key::<ChaCha20Cipher>();
+    let envelope = Envelope::seal::<ChaCha20Cipher>(&chacha, Bytes::from_static(b"Hello"), false).unwrap();
+    assert_eq!(envelope.mode, Mode::Stream);
+    assert_eq!(envelope.iv, &chacha[32..]);
+    assert_eq!(envelope.fingerprint, fingerprint(&chacha));
+    assert_eq!(open_any(&envelope.to_bytes(), &chacha).unwrap().0, "chacha20");
+
+    // the IV is checked against the key, not just carried along
+    let mut altered = envelope.clone();
+    altered.iv[0] ^= 1;
+    assert_eq!(altered.open::<ChaCha20Cipher>(&chacha), Err(EnvelopeError::WrongIv));
+    let mut altered = envelope;
+    altered.mode = Mode::Codebook;
+    assert_eq!(altered.open::<ChaCha20Cipher>(&chacha), Err(EnvelopeError::WrongIv));
+}
+
+#[test]
+fn mismatches_are_named() {
+    let hill = key::<HillCaesarCipher>();
+    let envelope = Envelope::seal::<HillCaesarCipher>(&hill, Bytes::from_static(b"Hello"), false).unwrap();
+
+    let other = key::<HillCaesarCipher>();
+    let error = envelope.open::<HillCaesarCipher>(&other).unwrap_err();
+    assert_eq!(
+        error,
+        EnvelopeError::WrongKey {
+            expected: fingerprint(&hill),
+            found: fingerprint(&other)
+        }
+    );
+    assert!(error.to_string().contains("fingerprint"));
+
+    let vigenere = key::<VigenereCipher>();
+    let error = envelope.open::<VigenereCipher>(&vigenere).unwrap_err();
+    assert_eq!(error, EnvelopeError::WrongCipher { expected: "vigenere", found: 1 });
+    assert_eq!(error.to_string(), "envelope holds a hill-caesar ciphertext, not vigenere");
+
+    assert_eq!(
+        open_any(&envelope.to_bytes(), &hill[..10]),
+        Err(EnvelopeError::KeyLength { expected: 65, found: 10 })
+    );
+}
+
+#[test]
+fn tampering_and_malformed_headers() {
+    let hill = key::<HillCaesarCipher>();
+    let bytes = Envelope::seal::<HillCaesarCipher>(&hill, Bytes::from_static(b"Hello"), true).unwrap().to_bytes();
+
+    // after the magic, version and cipher id, every bit is covered by the tag or the fingerprint
+    for i in 6..bytes.len() {
+        let mut forged = bytes.clone();
+        forged[i] ^= 0x01;
+        let opened = Envelope::parse(&forged).and_then(|envelope| envelope.open_authenticated::<HillCaesarCipher>(&hill));
+        assert!(opened.is_err(), "byte {}", i);
+    }
+    // a forger can drop the tag, only open_authenticated notices
+    let mut stripped = Envelope::parse(&bytes).unwrap();
+    stripped.tag = None;
+    assert!(stripped.open::<HillCaesarCipher>(&hill).is_ok());
+    assert_eq!(stripped.open_authenticated::<HillCaesarCipher>(&hill), Err(EnvelopeError::MissingTag));
+    let mut forged = bytes.clone();
+    let last = forged.len() - TAG_SIZE - 1;
+    forged[last] ^= 0x01;
+    assert_eq!(open_any(&forged, &hill), Err(EnvelopeError::BadTag));
+
+    assert_eq!(Envelope::parse(b"G2"), Err(EnvelopeError::BadMagic));
+    assert_eq!(Envelope::parse(&bytes[..10]), Err(EnvelopeError::Truncated));
+    let mut version = bytes.clone();
+    version[4] = 2;
+    assert_eq!(Envelope::parse(&version), Err(EnvelopeError::UnsupportedVersion(2)));
+    let mut cipher = bytes.clone();
+    cipher[5] = 99;
+    assert_eq!(Envelope::parse(&cipher), Err(EnvelopeError::UnknownCipher(99)));
+    let mut mode = bytes.clone();
+    mode[14] = 7;
+    assert_eq!(Envelope::parse(&mode), Err(EnvelopeError::UnknownMode(7)));
+    // a tag is announced but the body is shorter than it
+    assert_eq!(Envelope::parse(&bytes[..16 + 8]), Err(EnvelopeError::Truncated));
+}
+
+#[test]
+fn cli_envelopes() {
+    let cipher1 = env!("CARGO_BIN_EXE_cipher1");
+    let cipher2 = env!("CARGO_BIN_EXE_cipher2");
+    let key = BASE64_STANDARD.encode(HillCaesarCipher::gen_keys(rand::rng()));
+
+    let (encrypted, output) = run(cipher1, &["encrypt", "Hello", "--key", &key, "--envelope", "--authenticate"]);
+    assert!(output.status.success());
+    let sealed = encrypted["ciphertext"].as_str().unwrap();
+    assert!(BASE64_STANDARD.decode(sealed).unwrap().starts_with(MAGIC));
+
+    let (decrypted, output) = run(cipher1, &["decrypt", sealed, "--key", &key]);
+    assert!(output.status.success());
+    assert_eq!(decrypted["plaintext"], "Hello");
+
+    let (decrypted, output) = run(cipher1, &["decrypt", sealed, "--key", &key, "--authenticate"]);
+    assert!(output.status.success());
+    assert_eq!(decrypted["plaintext"], "Hello");
+    let (bare, _) = run(cipher1, &["encrypt", "Hello", "--key", &key, "--envelope"]);
+    let (report, output) = run(cipher1, &["decrypt", bare["ciphertext"].as_str().unwrap(), "--key", &key, "--authenticate"]);
+    assert!(!output.status.success());
+    assert_eq!(report["error"]["kind"], "envelope");
+
+    let other = BASE64_STANDARD.encode(HillCaesarCipher::gen_keys(rand::rng()));
+    let (report, output) = run(cipher1, &["decrypt", sealed, "--key", &other]);
+    assert!(!output.status.success());
+    assert_eq!(report["error"]["kind"], "envelope");
+
+    let chacha = BASE64_STANDARD.encode(ChaCha20Cipher::gen_keys(rand::rng()));
+    let (report, _) = run(cipher2, &["decrypt", sealed, "--key", &chacha]);
+    assert_eq!(report["error"]["message"], "envelope holds a hill-caesar ciphertext, not chacha20");
+
+    // the challenge stays protected inside an envelope
+    let secret = HillCaesarCipher::secret();
+    let mut envelope = Envelope::seal::<HillCaesarCipher>(&secret.key, Bytes::new(), false).unwrap();
+    envelope.ciphertext = secret.encrypted_message;
+    let (report, _) = run(cipher1, &["decrypt", &BASE64_STANDARD.encode(envelope.to_bytes())]);
+    assert_eq!(report["error"]["kind"], "cheater");
+}
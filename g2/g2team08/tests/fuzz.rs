@@ -0,0 +1,73 @@
+#![cfg(feature = "cli")]
+
+use g2::bytes::Bytes;
+use g2::cipher::Key;
+use g2::cipher1::cipher::{HillCaesarCipher, HillCaesarError};
+use g2::cipher3::cipher::{HillGf256Cipher, HillGf256Error};
+use g2::traits::DecryptBytes;
+
+use std::path::Path;
+use std::process::Command;
+
+/// runs every input that once made `target` panic, as `cargo fuzz` does with `fuzz/regressions/<name>`
+fn replay(name: &str, target: fn(&[u8])) {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz/regressions").join(name);
+    let mut count = 0;
+    for entry in std::fs::read_dir(dir).unwrap() {
+        target(&std::fs::read(entry.unwrap().path()).unwrap());
+        count += 1;
+    }
+    assert!(count > 0, "no regressions for {}", name);
+}
+
+#[test]
+fn decrypt_regressions() {
+    replay("decrypt", g2::fuzz::decrypt);
+}
+
+#[test]
+fn keys_regressions() {
+    replay("keys", g2::fuzz::keys);
+}
+
+#[test]
+fn cli_regressions() {
+    replay("cli", g2::fuzz::cli);
+}
+
+fn identity() -> [u8; 65] {
+    let mut key = [0; 65];
+    (0..8).for_each(|i| key[9 * i] = 1);
+    key
+}
+
+#[test]
+fn hill_rejects_partial_blocks() {
+    let key = identity();
+    let ciphertext = Bytes::from_static(b"abc");
+    let result = HillCaesarCipher::decrypt_bytes(Key::<HillCaesarCipher>::from_slice(&key), ciphertext.clone());
+    assert_eq!(result.unwrap_err(), HillCaesarError::Length(3));
+    let result = HillGf256Cipher::decrypt_bytes(Key::<HillGf256Cipher>::from_slice(&key), ciphertext);
+    assert_eq!(result.unwrap_err(), HillGf256Error::Length(3));
+}
+
+#[test]
+fn hill_rejects_singular_keys() {
+    let key = [0; 65];
+    let ciphertext = Bytes::from_static(&[1; 8]);
+    let result = HillCaesarCipher::decrypt_bytes(Key::<HillCaesarCipher>::from_slice(&key), ciphertext.clone());
+    assert_eq!(result.unwrap_err(), HillCaesarError::SingularKey);
+    let result = HillGf256Cipher::decrypt_bytes(Key::<HillGf256Cipher>::from_slice(&key), ciphertext);
+    assert_eq!(result.unwrap_err(), HillGf256Error::SingularKey);
+}
+
+#[test]
+fn client_without_oracle_fails_cleanly() {
+    let socket = std::env::temp_dir().join(format!("g2-no-oracle-{}", std::process::id()));
+    let output = Command::new(env!("CARGO_BIN_EXE_cipher1"))
+        .args(["client", "--unix", socket.to_str().unwrap(), "GENERATE"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("error: "));
+}
@@ -0,0 +1,77 @@
+#![cfg(feature = "cli")]
+
+use g2::cipher1::cipher::HillCaesarCipher;
+use g2::cipher2::cipher::ChaCha20Cipher;
+use g2::cipher4::cipher::Salsa20Cipher;
+use g2::games::{play, Adversary, BitFlip, Game, HillLinearity, NonceReuse, Oracles};
+use g2::rng::RngCore;
+use g2::traits::ChallengeCipher;
+
+const TRIALS: usize = 200;
+
+#[test]
+fn hill_falls_to_linearity() {
+    let estimate = play::<HillCaesarCipher>(&mut HillLinearity::default(), Game::Cpa, TRIALS, [1; 32]);
+    assert_eq!(estimate.encryptions, 3 * TRIALS);
+    assert!(estimate.advantage() > 0.9, "{}", estimate);
+}
+
+#[test]
+fn chacha_falls_to_nonce_reuse() {
+    let estimate = play::<ChaCha20Cipher>(&mut NonceReuse::default(), Game::Cpa, TRIALS, [2; 32]);
+    assert_eq!(estimate.advantage(), 1.0, "{}", estimate);
+}
+
+#[test]
+fn stream_ciphers_fall_to_bit_flips() {
+    let estimate = play::<Salsa20Cipher>(&mut BitFlip::default(), Game::Cca, TRIALS, [3; 32]);
+    assert_eq!(estimate.advantage(), 1.0, "{}", estimate);
+    assert_eq!(estimate.decryptions, TRIALS);
+
+    // without a decryption oracle it only guesses
+    let estimate = play::<ChaCha20Cipher>(&mut BitFlip::default(), Game::Cpa, TRIALS, [3; 32]);
+    assert!(estimate.advantage().abs() < 0.3, "{}", estimate);
+    assert_eq!(estimate.decryptions, 0);
+}
+
+/// guesses at random, for the baseline
+struct Coin;
+
+impl<C: ChallengeCipher> Adversary<C> for Coin {
+    fn choose(&mut self, _oracles: &mut Oracles<'_, C>) -> (Vec<u8>, Vec<u8>) {
+        (vec![0; 8], vec![1; 8])
+    }
+
+    fn guess(&mut self, oracles: &mut Oracles<'_, C>, challenge: &[u8]) -> bool {
+        // the challenge itself is never decrypted
+        assert!(oracles.decrypt(challenge).is_none());
+        oracles.rng.next_u32() & 1 == 1
+    }
+}
+
+#[test]
+fn coin_has_no_advantage() {
+    let estimate = play::<ChaCha20Cipher>(&mut Coin, Game::Cca, 1000, [4; 32]);
+    assert!(estimate.advantage().abs() < 0.15, "{}", estimate);
+    // the same seed plays the same games
+    assert_eq!(estimate, play::<ChaCha20Cipher>(&mut Coin, Game::Cca, 1000, [4; 32]));
+}
+
+/// messages of different lengths lose
+struct Unequal;
+
+impl Adversary<HillCaesarCipher> for Unequal {
+    fn choose(&mut self, _oracles: &mut Oracles<'_, HillCaesarCipher>) -> (Vec<u8>, Vec<u8>) {
+        (vec![0; 8], vec![0; 16])
+    }
+
+    fn guess(&mut self, _oracles: &mut Oracles<'_, HillCaesarCipher>, _challenge: &[u8]) -> bool {
+        unreachable!()
+    }
+}
+
+#[test]
+fn unequal_lengths_lose() {
+    let estimate = play::<HillCaesarCipher>(&mut Unequal, Game::Cpa, 10, [5; 32]);
+    assert_eq!((estimate.wins, estimate.advantage()), (0, -1.0));
+}
@@ -0,0 +1,58 @@
+#![cfg(feature = "std")]
+
+use g2::bytes::Bytes;
+use g2::cipher::Key;
+use g2::cipher3::cipher::{gf_inv, gf_mul, matrix_det, matrix_inv, HillGf256Cipher};
+use g2::rand;
+use g2::traits::{DecryptBytes, EncryptBytes};
+
+#[test]
+fn field_arithmetic() {
+    // FIPS-197, section 4.2
+    assert_eq!(gf_mul(0x57, 0x83), 0xc1);
+    assert_eq!(gf_mul(0x57, 0x13), 0xfe);
+    assert_eq!(gf_inv(0x53), Some(0xca));
+    assert_eq!(gf_inv(0), None);
+    for a in 1..=255u8 {
+        assert_eq!(gf_mul(a, gf_inv(a).unwrap()), 1);
+    }
+}
+
+#[test]
+fn matrix_inverse() {
+    let mut identity = [[0u8; 8]; 8];
+    for (i, row) in identity.iter_mut().enumerate() {
+        row[i] = 1;
+    }
+    assert_eq!(matrix_det(&identity), 1);
+
+    let mut singular = identity;
+    singular[3] = singular[5];
+    assert_eq!(matrix_det(&singular), 0);
+    assert!(matrix_inv(&singular).is_none());
+
+    // an even determinant would be rejected over Z/256, here it is just a field element
+    let mut m = identity;
+    m[0][0] = 2;
+    m[2][7] = 0x9e;
+    assert_eq!(matrix_det(&m), 2);
+    let inv = matrix_inv(&m).unwrap();
+    for i in 0..8 {
+        for j in 0..8 {
+            let dot = (0..8).fold(0, |sum, k| sum ^ gf_mul(m[i][k], inv[k][j]));
+            assert_eq!(dot, identity[i][j]);
+        }
+    }
+}
+
+#[test]
+fn round_trip() {
+    for len in [0, 1, 7, 8, 9, 100] {
+        let key = HillGf256Cipher::gen_keys(rand::rng());
+        let key = Key::<HillGf256Cipher>::from_slice(&key);
+        let message = Bytes::from(vec![b'x'; len]);
+        let encrypted_message = HillGf256Cipher::encrypt_bytes(key, message.clone());
+        assert_eq!(encrypted_message.len(), len.div_ceil(8) * 8);
+        assert_eq!(HillGf256Cipher::decrypt_bytes(key, encrypted_message).unwrap(), message);
+    }
+}
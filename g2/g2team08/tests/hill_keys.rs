@@ -0,0 +1,110 @@
+#![cfg(feature = "cli")]
+
+use g2::cipher::Key;
+use g2::cipher1::cipher::{random_invertible_matrix, random_invertible_mod2, HillCaesarCipher};
+use g2::keycheck::KeyCheck;
+use g2::rng::{seeded, ChaCha20Rng};
+use g2::traits::EncryptBytes;
+
+use std::collections::HashMap;
+
+fn rng(tag: u8) -> ChaCha20Rng {
+    seeded([tag; 32])
+}
+
+/// rank over F2 of a 0/1 matrix, by Gaussian elimination on row bit masks
+fn rank_mod2<const N: usize>(mat: &[[u8; N]; N]) -> usize {
+    let mut rows: Vec<u8> = mat.iter().map(|row| row.iter().enumerate().fold(0, |acc, (j, &x)| acc | x << j)).collect();
+    let mut rank = 0;
+    for bit in 0..N {
+        let Some(p) = (rank..N).find(|&r| rows[r] >> bit & 1 == 1) else { continue };
+        rows.swap(rank, p);
+        for r in 0..N {
+            if r != rank && rows[r] >> bit & 1 == 1 {
+                rows[r] ^= rows[rank];
+            }
+        }
+        rank += 1;
+    }
+    rank
+}
+
+/// |GL(n, F2)| = prod over i < n of (2^n - 2^i)
+fn gl2_order(n: u32) -> u64 {
+    (0..n).map(|i| (1u64 << n) - (1u64 << i)).product()
+}
+
+/// the chi-squared statistic of `counts` against equal expected counts
+fn chi_squared(counts: impl IntoIterator<Item = u64>, classes: u64, samples: u64) -> f64 {
+    let expected = samples as f64 / classes as f64;
+    counts.into_iter().map(|c| (c as f64 - expected).powi(2) / expected).sum()
+}
+
+/// upper 0.01% quantile of chi-squared with `df` degrees of freedom (Wilson-Hilferty)
+fn chi_squared_bound(df: u64) -> f64 {
+    let z = 3.719;
+    let k = df as f64;
+    k * (1.0 - 2.0 / (9.0 * k) + z * (2.0 / (9.0 * k)).sqrt()).powi(3)
+}
+
+fn check_uniform_gl2<const N: usize>(per_class: u64, tag: u8) {
+    let classes = gl2_order(N as u32);
+    let samples = classes * per_class;
+    let mut rng = rng(tag);
+    let mut counts: HashMap<[[u8; N]; N], u64> = HashMap::new();
+    for _ in 0..samples {
+        let mat = random_invertible_mod2::<N>(&mut rng);
+        assert_eq!(rank_mod2(&mat), N);
+        *counts.entry(mat).or_default() += 1;
+    }
+    assert_eq!(counts.len() as u64, classes, "every element of GL({}, F2) must occur", N);
+    let stat = chi_squared(counts.into_values(), classes, samples);
+    assert!(stat < chi_squared_bound(classes - 1), "chi-squared {} for GL({}, F2)", stat, N);
+}
+
+#[test]
+fn gl2_is_covered_uniformly() {
+    check_uniform_gl2::<2>(2_000, 2);
+    check_uniform_gl2::<3>(300, 3);
+    check_uniform_gl2::<4>(20, 4);
+}
+
+#[test]
+fn generated_keys_are_invertible() {
+    let mut rng = rng(8);
+    for _ in 0..2_000 {
+        assert_eq!(rank_mod2(&random_invertible_matrix(&mut rng).map(|row| row.map(|x| x & 1))), 8);
+    }
+    for _ in 0..50 {
+        let key = HillCaesarCipher::gen_keys(&mut rng);
+        assert_eq!(key.len(), 65);
+        assert!(HillCaesarCipher::check(Key::<HillCaesarCipher>::from_slice(&key)).is_valid());
+    }
+}
+
+#[test]
+fn entries_have_the_marginals_of_uniform_gl8() {
+    // by symmetry every entry of a uniform matrix of GL(8, F2) is odd with
+    // probability 128/255, the share of nonzero first rows with a 1 there;
+    // the high seven bits must be uniform and independent of the low one
+    let keys = 20_000u64;
+    let mut rng = rng(9);
+    let mut odd = 0u64;
+    let mut high = [[0u64; 128]; 2];
+    for _ in 0..keys {
+        for x in random_invertible_matrix(&mut rng).into_iter().flatten() {
+            odd += (x & 1) as u64;
+            high[(x & 1) as usize][(x >> 1) as usize] += 1;
+        }
+    }
+    let entries = keys * 64;
+    let p = 128.0 / 255.0;
+    let sd = (entries as f64 * p * (1.0 - p)).sqrt();
+    let z = (odd as f64 - entries as f64 * p) / sd;
+    assert!(z.abs() < 4.0, "odd entries off by {} standard deviations", z);
+    for (low, counts) in high.iter().enumerate() {
+        let total = counts.iter().sum();
+        let stat = chi_squared(counts.iter().copied(), 128, total);
+        assert!(stat < chi_squared_bound(127), "chi-squared {} for high bits of entries with low bit {}", stat, low);
+    }
+}
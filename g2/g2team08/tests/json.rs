@@ -0,0 +1,40 @@
+#![cfg(feature = "cli")]
+
+use std::process::{Command, Output};
+
+fn cipher2(args: &[&str]) -> (serde_json::Value, Output) {
+    let output = Command::new(env!("CARGO_BIN_EXE_cipher2")).args(args).output().unwrap();
+    let report = serde_json::from_slice(&output.stdout).unwrap();
+    (report, output)
+}
+
+#[test]
+fn json_round_trip() {
+    let (generated, output) = cipher2(&["--json", "generate"]);
+    assert!(output.status.success());
+    assert_eq!(generated["cipher"], "chacha20");
+    let key = generated["key"].as_str().unwrap();
+
+    let (encrypted, output) = cipher2(&["encrypt", "Hello", "--key", key, "--json"]);
+    assert!(output.status.success());
+    assert_eq!(encrypted["key_id"], generated["key_id"]);
+    let ciphertext = encrypted["ciphertext"].as_str().unwrap();
+
+    let (decrypted, output) = cipher2(&["--json", "decrypt", ciphertext, "--key", key]);
+    assert!(output.status.success());
+    assert_eq!(decrypted["plaintext"], "Hello");
+    assert!(decrypted.get("error").is_none());
+}
+
+#[test]
+fn json_failures_exit_non_zero() {
+    let challenge = std::fs::read_to_string("secrets/cipher2/ciphertext.txt").unwrap();
+    let (report, output) = cipher2(&["--json", "decrypt", challenge.trim()]);
+    assert!(!output.status.success());
+    assert_eq!(report["key_id"], "default");
+    assert_eq!(report["error"]["kind"], "cheater");
+
+    let (report, output) = cipher2(&["--json", "encrypt", "Hello", "--key", "AAAA"]);
+    assert!(!output.status.success());
+    assert_eq!(report["error"]["kind"], "invalid_key");
+}
@@ -0,0 +1,121 @@
+#![cfg(feature = "cli")]
+
+use g2::cipher::Key;
+use g2::cipher1::cipher::HillCaesarCipher;
+use g2::cipher2::cipher::ChaCha20Cipher;
+use g2::cipher3::cipher::HillGf256Cipher;
+use g2::cipher4::cipher::Salsa20Cipher;
+use g2::keycheck::{FieldValue, KeyCheck, KeyInspection};
+use g2::rand;
+use g2::traits::{ChallengeCipher, EncryptBytes};
+
+use std::process::Command;
+
+fn field<'a>(inspection: &'a KeyInspection, name: &str) -> &'a FieldValue {
+    &inspection.fields.iter().find(|field| field.name == name).unwrap().value
+}
+
+fn matrix(value: &FieldValue) -> Vec<Vec<u8>> {
+    match value {
+        FieldValue::Matrix(rows) => rows.clone(),
+        other => panic!("not a matrix: {:?}", other),
+    }
+}
+
+/// the identity matrix with `corner` in the top left, then the Caesar shift
+fn hill_key(corner: u8, caesar: u8) -> Vec<u8> {
+    let mut key = vec![0u8; 65];
+    for i in 0..8 {
+        key[i * 9] = 1;
+    }
+    key[0] = corner;
+    key[64] = caesar;
+    key
+}
+
+#[test]
+fn hill_key_with_even_determinant_is_invalid() {
+    let inspection = HillCaesarCipher::check(Key::<HillCaesarCipher>::from_slice(&hill_key(2, 5)));
+    assert_eq!(field(&inspection, "determinant mod 256"), &FieldValue::Number(2));
+    assert_eq!(field(&inspection, "invertible"), &FieldValue::Flag(false));
+    assert!(!inspection.is_valid());
+    assert!(inspection.errors[0].contains("even"));
+    assert!(inspection.fields.iter().all(|field| field.name != "inverse"));
+}
+
+#[test]
+fn hill_inverse_is_shown() {
+    let key = HillCaesarCipher::gen_keys(rand::rng());
+    let inspection = HillCaesarCipher::check(Key::<HillCaesarCipher>::from_slice(&key));
+    assert!(inspection.is_valid());
+    let (m, inv) = (matrix(field(&inspection, "matrix")), matrix(field(&inspection, "inverse")));
+    for (i, row) in m.iter().enumerate() {
+        for j in 0..8 {
+            let dot = row.iter().zip(&inv).fold(0u8, |sum, (&x, inv_row)| sum.wrapping_add(x.wrapping_mul(inv_row[j])));
+            assert_eq!(dot, (i == j) as u8);
+        }
+    }
+    assert_eq!(field(&inspection, "caesar shift"), &FieldValue::Number(key[64] as u64));
+
+    let inspection = HillCaesarCipher::check(Key::<HillCaesarCipher>::from_slice(&hill_key(1, 0)));
+    assert!(inspection.is_valid());
+    assert_eq!(inspection.warnings.len(), 2, "{:?}", inspection.warnings);
+
+    // over GF(2^8) an even determinant is fine
+    let inspection = HillGf256Cipher::check(Key::<HillGf256Cipher>::from_slice(&hill_key(2, 5)));
+    assert!(inspection.is_valid());
+    assert!(HillGf256Cipher::inspect_key(Key::<HillGf256Cipher>::from_slice(&hill_key(0, 5))).is_some_and(|i| !i.is_valid()));
+}
+
+#[test]
+fn weak_stream_keys_are_flagged() {
+    let random = ChaCha20Cipher::gen_keys(rand::rng());
+    let inspection = ChaCha20Cipher::check(Key::<ChaCha20Cipher>::from_slice(&random));
+    assert!(inspection.is_valid());
+
+    let mut key = vec![0u8; 48];
+    key[44..].copy_from_slice(&(u32::MAX - 3).to_le_bytes());
+    let inspection = ChaCha20Cipher::check(Key::<ChaCha20Cipher>::from_slice(&key));
+    assert!(inspection.is_valid());
+    assert_eq!(field(&inspection, "counter"), &FieldValue::Number(u32::MAX as u64 - 3));
+    assert_eq!(inspection.warnings.len(), 3, "{:?}", inspection.warnings);
+    assert!(inspection.warnings[0].contains("all zero"));
+    assert!(inspection.warnings[2].contains("256 bytes"));
+
+    // a 64-bit counter is far from overflow at the same value
+    let mut key = vec![7u8; 48];
+    key[40..].copy_from_slice(&(u32::MAX as u64 - 3).to_le_bytes());
+    let inspection = Salsa20Cipher::check(Key::<Salsa20Cipher>::from_slice(&key));
+    assert_eq!(inspection.warnings, ["every byte of the key is the same"]);
+    key[40..].copy_from_slice(&u64::MAX.to_le_bytes());
+    let inspection = Salsa20Cipher::check(Key::<Salsa20Cipher>::from_slice(&key));
+    assert!(inspection.warnings[1].contains("64 bytes"));
+}
+
+fn cipher1(args: &[&str]) -> (serde_json::Value, bool) {
+    let output = Command::new(env!("CARGO_BIN_EXE_cipher1")).arg("--json").args(args).output().unwrap();
+    (serde_json::from_slice(&output.stdout).unwrap(), output.status.success())
+}
+
+#[test]
+fn validate_key_exit_status() {
+    use base64::prelude::*;
+    let singular = BASE64_STANDARD.encode(hill_key(2, 5));
+    let (report, success) = cipher1(&["validate-key", "--key", &singular]);
+    assert!(!success);
+    assert_eq!(report["error"]["kind"], "invalid_key");
+    assert_eq!(report["inspection"]["fields"][1]["value"], 2);
+
+    // inspecting a broken key is not a failure
+    let (report, success) = cipher1(&["inspect-key", "--key", &singular]);
+    assert!(success);
+    assert_eq!(report["inspection"]["errors"].as_array().unwrap().len(), 1);
+
+    let (_, success) = cipher1(&["validate-key", "--key", &BASE64_STANDARD.encode(hill_key(3, 5))]);
+    assert!(success);
+
+    // the default key stays secret
+    let (report, success) = cipher1(&["inspect-key"]);
+    assert!(!success);
+    assert!(report.get("inspection").is_none());
+}
@@ -0,0 +1,202 @@
+#![cfg(feature = "std")]
+
+use g2::bytes::Bytes;
+use g2::cipher::Key;
+use g2::cipher1::cipher::HillCaesarCipher;
+use g2::cipher2::cipher::ChaCha20Cipher;
+use g2::mac::{chacha20_poly1305_open, chacha20_poly1305_seal, AeadError};
+use g2::mac::{ct_eq, hmac_sha256, poly1305, EncryptThenMac, EtmError, HmacSha256, Poly1305};
+use g2::mac::{chacha20_siv_open, chacha20_siv_seal, ChaCha20Siv};
+use g2::rand;
+use g2::traits::{DecryptBytes, EncryptBytes, MacBytes};
+
+type HillEtm = EncryptThenMac<HillCaesarCipher, HmacSha256>;
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn key32(bytes: &[u8]) -> [u8; 32] {
+    bytes.try_into().unwrap()
+}
+
+#[test]
+fn poly1305_rfc_8439() {
+    let key = [
+        0x85, 0xd6, 0xbe, 0x78, 0x57, 0x55, 0x6d, 0x33, 0x7f, 0x44, 0x52, 0xfe, 0x42, 0xd5, 0x06, 0xa8, 0x01, 0x03,
+        0x80, 0x8a, 0xfb, 0x0d, 0xb2, 0xfd, 0x4a, 0xbf, 0xf6, 0xaf, 0x41, 0x49, 0xf5, 0x1b,
+    ];
+    let tag = poly1305(&key, b"Cryptographic Forum Research Group");
+    assert_eq!(hex(&tag), "a8061dc1305136c6c22b8baf0c0127a9");
+    assert_eq!(Poly1305::mac_bytes(Key::<Poly1305>::from_slice(&key), b"Cryptographic Forum Research Group").as_slice(), tag);
+}
+
+#[test]
+fn poly1305_final_reduction() {
+    // RFC 8439 appendix A.3, vectors 6 to 9: accumulators at or just past p
+    let mut r2 = [0u8; 32];
+    r2[0] = 2;
+    assert_eq!(hex(&poly1305(&r2, &[0xff; 16])), "03000000000000000000000000000000");
+    let mut r2s = r2;
+    r2s[16..].fill(0xff);
+    let mut two = [0u8; 16];
+    two[0] = 2;
+    assert_eq!(hex(&poly1305(&r2s, &two)), "03000000000000000000000000000000");
+
+    let mut r1 = [0u8; 32];
+    r1[0] = 1;
+    let mut message = vec![0xff; 16];
+    message.push(0xf0);
+    message.extend([0xff; 15]);
+    message.push(0x11);
+    message.extend([0; 15]);
+    assert_eq!(hex(&poly1305(&r1, &message)), "05000000000000000000000000000000");
+    let mut message = vec![0xff; 16];
+    message.push(0xfb);
+    message.extend([0xfe; 15]);
+    message.extend([0x01; 16]);
+    assert_eq!(hex(&poly1305(&r1, &message)), "00000000000000000000000000000000");
+}
+
+#[test]
+fn poly1305_partial_blocks() {
+    let key = key32(&(0..32).collect::<Vec<u8>>());
+    let expected = [
+        (0, "101112131415161718191a1b1c1d1e1f"),
+        (1, "1f14191e23282d32373c41464b50555a"),
+        (15, "5d6ba854aef3623ab81aa0860c70efe4"),
+        (16, "a438fd3012dfd5343a24319fac979eff"),
+        (17, "c6ac9b188a8e6c83bbf68080665fc386"),
+        (64, "55e564fff1b743fad219c5d22daede54"),
+        (131, "ca3d17e91ac9ab72088d618c19d26e83"),
+    ];
+    for (len, tag) in expected {
+        let message: Vec<u8> = (0..len).map(|i| ((i * 7 + 3) % 256) as u8).collect();
+        assert_eq!(hex(&poly1305(&key, &message)), tag, "{} bytes", len);
+    }
+    assert_eq!(hex(&poly1305(&[0xff; 32], &[0xff; 80])), "b7dab159c89efa2ff98061493f57fa40");
+}
+
+#[test]
+fn hmac_sha256_rfc_4231() {
+    assert_eq!(
+        hex(&hmac_sha256(&[0x0b; 20], b"Hi There")),
+        "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+    );
+    assert_eq!(
+        hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+        "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+    assert_eq!(
+        hex(&hmac_sha256(&[0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First")),
+        "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+    );
+    assert_eq!(
+        hex(&hmac_sha256(&[0xaa; 64], b"exactly a block")),
+        "63c3ca86a738726129bab6fe13f888d0b9744a85b31428d2efd15612d8413bfd"
+    );
+}
+
+#[test]
+fn verify_bytes() {
+    let key = Key::<HmacSha256>::from_slice(&[7; 32]);
+    let tag = HmacSha256::mac_bytes(key, b"message");
+    assert!(HmacSha256::verify_bytes(key, b"message", &tag));
+    assert!(!HmacSha256::verify_bytes(key, b"messagE", &tag));
+    assert!(!HmacSha256::verify_bytes(key, b"message", &tag[..31]));
+    assert!(ct_eq(b"", b""));
+    assert!(!ct_eq(b"a", b"b"));
+}
+
+#[test]
+fn encrypt_then_mac_round_trip() {
+    let key = HillEtm::gen_keys(rand::rng());
+    assert_eq!(key.len(), 65 + 32);
+    let key = Key::<HillEtm>::from_slice(&key);
+    for message in ["", "a", "attack at dawn, and bring the other matrix"] {
+        let sealed = HillEtm::encrypt_bytes(key, Bytes::copy_from_slice(message.as_bytes()));
+        assert_eq!(HillEtm::decrypt_bytes(key, sealed).unwrap(), message.as_bytes());
+    }
+
+    type ChaChaEtm = EncryptThenMac<ChaCha20Cipher, HmacSha256>;
+    let key = ChaChaEtm::gen_keys(rand::rng());
+    let key = Key::<ChaChaEtm>::from_slice(&key);
+    let sealed = ChaChaEtm::encrypt_bytes(key, Bytes::from_static(b"stream"));
+    assert_eq!(sealed.len(), 6 + 32);
+    assert_eq!(ChaChaEtm::decrypt_bytes(key, sealed).unwrap(), &b"stream"[..]);
+}
+
+#[test]
+fn encrypt_then_mac_rejects_tampering() {
+    let key = HillEtm::gen_keys(rand::rng());
+    let key = Key::<HillEtm>::from_slice(&key);
+    let sealed = HillEtm::encrypt_bytes(key, Bytes::from_static(b"pay the bearer 100 dollars"));
+    for i in 0..sealed.len() {
+        let mut forged = sealed.to_vec();
+        forged[i] ^= 0x01;
+        assert!(matches!(HillEtm::decrypt_bytes(key, Bytes::from(forged)), Err(EtmError::BadTag)), "byte {}", i);
+    }
+    assert!(matches!(HillEtm::decrypt_bytes(key, sealed.slice(..31)), Err(EtmError::Truncated)));
+    assert!(matches!(HillEtm::decrypt_bytes(key, sealed.slice(1..)), Err(EtmError::BadTag)));
+
+    // same cipher key, another MAC key
+    let mut other = key.to_vec();
+    other[70] ^= 0x80;
+    assert!(matches!(HillEtm::decrypt_bytes(Key::<HillEtm>::from_slice(&other), sealed), Err(EtmError::BadTag)));
+}
+
+#[test]
+fn chacha20_poly1305_rfc_8439() {
+    // section 2.8.2
+    let key: [u8; 32] = core::array::from_fn(|i| 0x80 + i as u8);
+    let nonce = [0x07, 0, 0, 0, 0x40, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47];
+    let aad = [0x50, 0x51, 0x52, 0x53, 0xc0, 0xc1, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7];
+    let message = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
+    let (ciphertext, tag) = chacha20_poly1305_seal(&key, &nonce, &aad, message);
+    assert_eq!(hex(&ciphertext[..16]), "d31a8d34648e60db7b86afbc53ef7ec2");
+    assert_eq!(hex(&tag), "1ae10b594f09e26a7e902ecbd0600691");
+    assert_eq!(chacha20_poly1305_open(&key, &nonce, &aad, &ciphertext, &tag).unwrap(), message);
+
+    assert_eq!(chacha20_poly1305_open(&key, &nonce, &aad[1..], &ciphertext, &tag), Err(AeadError));
+    assert_eq!(chacha20_poly1305_open(&key, &nonce, &aad, &ciphertext[1..], &tag), Err(AeadError));
+    assert_eq!(chacha20_poly1305_open(&key, &nonce, &aad, &ciphertext, &tag[..15]), Err(AeadError));
+}
+
+#[test]
+fn chacha20_siv_is_deterministic() {
+    let key: [u8; 64] = core::array::from_fn(|i| i as u8);
+    let sealed = chacha20_siv_seal(&key, b"header", b"attack at dawn");
+    assert_eq!(sealed.len(), 16 + 14);
+    assert_eq!(sealed, chacha20_siv_seal(&key, b"header", b"attack at dawn"));
+    assert_eq!(chacha20_siv_open(&key, b"header", &sealed).unwrap(), b"attack at dawn");
+
+    // another message, data or split of the same bytes gives another IV
+    assert_ne!(sealed[..16], chacha20_siv_seal(&key, b"header", b"attack at dusk")[..16]);
+    assert_ne!(sealed[..16], chacha20_siv_seal(&key, b"", b"attack at dawn")[..16]);
+    assert_ne!(chacha20_siv_seal(&key, b"ab", b"c")[..16], chacha20_siv_seal(&key, b"a", b"bc")[..16]);
+
+    // the IV is the nonce and counter of plain ChaCha20 under the second half of the key
+    let mut chacha_key = key[32..].to_vec();
+    chacha_key.extend_from_slice(&sealed[..16]);
+    let ciphertext = ChaCha20Cipher::encrypt_bytes(Key::<ChaCha20Cipher>::from_slice(&chacha_key), Bytes::from_static(b"attack at dawn"));
+    assert_eq!(ciphertext, &sealed[16..]);
+}
+
+#[test]
+fn chacha20_siv_rejects_tampering() {
+    let key = ChaCha20Siv::gen_keys(rand::rng());
+    let key = Key::<ChaCha20Siv>::from_slice(&key);
+    let sealed = ChaCha20Siv::encrypt_bytes(key, Bytes::from_static(b"pay the bearer 100 dollars"));
+    assert_eq!(ChaCha20Siv::decrypt_bytes(key, sealed.clone()).unwrap(), &b"pay the bearer 100 dollars"[..]);
+    for i in 0..sealed.len() {
+        let mut forged = sealed.to_vec();
+        forged[i] ^= 0x01;
+        assert_eq!(ChaCha20Siv::decrypt_bytes(key, Bytes::from(forged)), Err(AeadError), "byte {}", i);
+    }
+    assert_eq!(ChaCha20Siv::decrypt_bytes(key, sealed.slice(..15)), Err(AeadError));
+    assert_eq!(ChaCha20Siv::decrypt_bytes(key, sealed.slice(..16)), Err(AeadError));
+    assert_eq!(ChaCha20Siv::decrypt_bytes(key, ChaCha20Siv::encrypt_bytes(key, Bytes::new())).unwrap(), Bytes::new());
+
+    let seal = chacha20_siv_seal(key.as_slice().try_into().unwrap(), b"v1", b"message");
+    assert_eq!(chacha20_siv_open(key.as_slice().try_into().unwrap(), b"v2", &seal), Err(AeadError));
+}
@@ -0,0 +1,81 @@
+use g2::bytes::Bytes;
+use g2::cipher::Key;
+use g2::cipher1::cipher::HillCaesarCipher;
+use g2::cipher2::cipher::{chacha20_block, ChaCha20Cipher};
+use g2::rng::seeded;
+use g2::traits::{DecryptBytes, EncryptBytes};
+
+use std::path::Path;
+use std::process::Command;
+
+/// the cores on their own, this test runs under every feature set
+#[test]
+fn cores_round_trip() {
+    let key = HillCaesarCipher::gen_keys(seeded([3; 32]));
+    let key = Key::<HillCaesarCipher>::from_slice(&key);
+    let ciphertext = HillCaesarCipher::encrypt_bytes(key, Bytes::from_static(b"no_std"));
+    assert_eq!(HillCaesarCipher::decrypt_bytes(key, ciphertext).unwrap(), &b"no_std"[..]);
+
+    let key = ChaCha20Cipher::gen_keys(seeded([3; 32]));
+    let keystream = ChaCha20Cipher::encrypt_bytes(Key::<ChaCha20Cipher>::from_slice(&key), Bytes::from(vec![0u8; 64]));
+    let block = chacha20_block(key[..32].try_into().unwrap(), key[32..44].try_into().unwrap(), u32::from_le_bytes(key[44..].try_into().unwrap()));
+    assert_eq!(keystream, &block[..]);
+}
+
+/// builds the library without the default features, in a target directory of
+/// its own so as not to wait on the lock of the one running this test
+#[test]
+fn core_builds_without_default_features() {
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("no-default-features");
+    for features in [&[][..], &["--features", "std"]] {
+        let status = Command::new(env!("CARGO"))
+            .args(["build", "--lib", "--offline", "--no-default-features"])
+            .args(features)
+            .arg("--manifest-path")
+            .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml"))
+            .arg("--target-dir")
+            .arg(&target_dir)
+            .status()
+            .unwrap();
+        assert!(status.success(), "cargo build --no-default-features {:?}", features);
+    }
+}
+
+/// the crates the core depends on without the default features, none of them with `std` on
+#[test]
+fn core_dependencies_leave_std_off() {
+    let output = Command::new(env!("CARGO"))
+        .args(["tree", "--offline", "--no-default-features", "--edges", "normal", "--prefix", "none", "--format", "{p} [{f}]"])
+        .arg("--manifest-path")
+        .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml"))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let tree = String::from_utf8(output.stdout).unwrap();
+    assert!(tree.lines().any(|line| line.starts_with("bytes ")), "{}", tree);
+    for line in tree.lines() {
+        let features = line.rsplit_once('[').map_or("", |(_, features)| features.trim_end_matches([']', ' ', '(', '*', ')']));
+        assert!(!features.split(',').any(|feature| feature == "std"), "{}", line);
+    }
+}
+
+/// builds the library for a target without `std`, when its standard library is installed
+#[test]
+fn core_builds_for_a_target_without_std() {
+    const TARGET: &str = "thumbv7em-none-eabi";
+    let sysroot = Command::new("rustc").args(["--print", "sysroot"]).output().unwrap();
+    let sysroot = String::from_utf8(sysroot.stdout).unwrap();
+    if !Path::new(sysroot.trim()).join("lib/rustlib").join(TARGET).exists() {
+        eprintln!("skipped: rustup target add {}", TARGET);
+        return;
+    }
+    let status = Command::new(env!("CARGO"))
+        .args(["build", "--lib", "--offline", "--no-default-features", "--target", TARGET])
+        .arg("--manifest-path")
+        .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml"))
+        .arg("--target-dir")
+        .arg(Path::new(env!("CARGO_TARGET_TMPDIR")).join("no-std-target"))
+        .status()
+        .unwrap();
+    assert!(status.success(), "cargo build --target {}", TARGET);
+}
@@ -0,0 +1,280 @@
+#![cfg(feature = "cli")]
+
+use g2::bytes::Bytes;
+use g2::cipher1::cipher::HillCaesarCipher;
+use g2::cipher2::cipher::ChaCha20Cipher;
+use g2::cipher3::cipher::HillGf256Cipher;
+use g2::classical::affine::AffineCipher;
+use g2::classical::caesar::CaesarCipher;
+use g2::classical::playfair::PlayfairCipher;
+use g2::classical::substitution::SubstitutionCipher;
+use g2::classical::transposition::TranspositionCipher;
+use g2::classical::vigenere::{self, VigenereCipher};
+use g2::classical::{index, map_symbols};
+use g2::protection::{ExactMatch, ProtectionPolicy};
+use g2::rand::Rng;
+use g2::rng::{seeded, ChaCha20Rng};
+use g2::traits::{ChallengeCipher, DecryptBytes, EncryptBytes};
+
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+fn challenge<C: ChallengeCipher>() -> (Vec<u8>, Vec<u8>) {
+    let secret = C::secret();
+    (secret.encrypted_message.to_vec(), secret.secret_message().into_bytes())
+}
+
+/// the attempt must really leak challenge plaintext and must be refused
+fn assert_bypass_blocked<C: ChallengeCipher>(name: &str, query: Vec<u8>, leaked: &[u8]) {
+    let (encrypted_message, _) = challenge::<C>();
+    assert!(!ExactMatch::forbids(&encrypted_message, &query), "{name}: not a bypass");
+    let decrypted = C::decrypt_bytes(&C::secret().key, Bytes::from(query.clone())).unwrap();
+    assert!(
+        decrypted.windows(leaked.len()).any(|w| w == leaked),
+        "{name}: does not leak the challenge"
+    );
+    assert!(C::forbids_decryption(&Bytes::from(query), false), "{name}: not refused");
+}
+
+const RANDOM_BLOCKS: [u8; 16] = [
+    0x13, 0x37, 0xc0, 0xff, 0xee, 0x42, 0x99, 0x01, 0x7e, 0x55, 0xa0, 0x0b, 0x3c, 0xd2, 0x68, 0xf4,
+];
+
+fn rng() -> ChaCha20Rng {
+    seeded([27; 32])
+}
+
+fn random_text(rng: &mut ChaCha20Rng, len: usize) -> Bytes {
+    const CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+    (0..len).map(|_| CHARS[rng.random_range(0..CHARS.len())]).collect()
+}
+
+/// honest ciphertexts under the default key are decrypted, the classical
+/// ciphers only refuse reworked challenges
+fn assert_honest_queries_allowed<C: ChallengeCipher>() {
+    let mut rng = rng();
+    for len in [1, 5, 8, 64, 100, 1000] {
+        let encrypted_message = C::encrypt_bytes(&C::secret().key, random_text(&mut rng, len));
+        assert!(!C::forbids_decryption(&encrypted_message, false), "{}: length {len} refused", C::NAME);
+    }
+}
+
+/// queries under a key of their own are never refused
+fn assert_own_key_queries_allowed<C: ChallengeCipher>() {
+    let mut rng = rng();
+    let (encrypted_message, _) = challenge::<C>();
+    assert!(!C::forbids_decryption(&Bytes::from(encrypted_message), true));
+    for len in [1, 5, 8, 64, 100, 1000] {
+        let encrypted_message = C::encrypt_bytes(&C::secret().key, random_text(&mut rng, len));
+        assert!(!C::forbids_decryption(&encrypted_message, true), "length {len} refused");
+    }
+}
+
+#[test]
+fn chacha20_bypasses_are_blocked() {
+    let (ct, pt) = challenge::<ChaCha20Cipher>();
+
+    let mut flipped = ct.clone();
+    flipped[10] ^= 1;
+    assert_bypass_blocked::<ChaCha20Cipher>("bit flip", flipped, &pt[11..]);
+
+    let truncated = ct[..ct.len() - 1].to_vec();
+    assert_bypass_blocked::<ChaCha20Cipher>("drop last byte", truncated, &pt[..pt.len() - 1]);
+
+    let prefix = ct[..16].to_vec();
+    assert_bypass_blocked::<ChaCha20Cipher>("prefix", prefix, &pt[..16]);
+
+    let mut extended = ct.clone();
+    extended.extend_from_slice(b"tail");
+    assert_bypass_blocked::<ChaCha20Cipher>("extension", extended, &pt);
+
+    let mut sparse = ct.clone();
+    for (i, b) in sparse.iter_mut().enumerate() {
+        if i % 2 == 0 {
+            *b ^= 0x20;
+        }
+    }
+    assert_bypass_blocked::<ChaCha20Cipher>("every other byte", sparse, &pt[1..2]);
+
+    // no byte agrees with the challenge, the plaintext comes back masked
+    let masked: Vec<u8> = ct.iter().map(|b| b ^ 0x01).collect();
+    let unmasked: Vec<u8> = pt.iter().map(|b| b ^ 0x01).collect();
+    assert_bypass_blocked::<ChaCha20Cipher>("masked", masked, &unmasked);
+}
+
+#[test]
+fn hill_bypasses_are_blocked() {
+    let (ct, pt) = challenge::<HillCaesarCipher>();
+
+    let mut swapped = ct.clone();
+    let (first, rest) = swapped.split_at_mut(8);
+    first.swap_with_slice(&mut rest[..8]);
+    assert_bypass_blocked::<HillCaesarCipher>("swap blocks", swapped, &pt[..8]);
+
+    let reversed: Vec<u8> = ct.chunks(8).rev().flatten().copied().collect();
+    assert_bypass_blocked::<HillCaesarCipher>("reverse blocks", reversed, &pt[8..16]);
+
+    let single = ct[16..24].to_vec();
+    assert_bypass_blocked::<HillCaesarCipher>("single block", single, &pt[16..24]);
+
+    let mut flipped = ct.clone();
+    flipped[3] ^= 0x80;
+    assert_bypass_blocked::<HillCaesarCipher>("bit flip", flipped, &pt[8..]);
+
+    let truncated = ct[..ct.len() - 8].to_vec();
+    assert_bypass_blocked::<HillCaesarCipher>("drop last block", truncated, &pt[..pt.len() - 8]);
+
+    let mut mixed = RANDOM_BLOCKS.to_vec();
+    mixed.extend_from_slice(&ct[40..48]);
+    assert_bypass_blocked::<HillCaesarCipher>("hidden among random blocks", mixed, &pt[40..48]);
+
+    // c + E(e) - E(0) decrypts to p + e, and shares no block with c
+    let key = HillCaesarCipher::secret().key;
+    let e = vec![1u8; ct.len()];
+    let (ee, e0) = (
+        HillCaesarCipher::encrypt_bytes(&key, Bytes::from(e.clone())),
+        HillCaesarCipher::encrypt_bytes(&key, Bytes::from(vec![0u8; ct.len()])),
+    );
+    let shifted: Vec<u8> = (0..ct.len()).map(|i| ct[i].wrapping_add(ee[i]).wrapping_sub(e0[i])).collect();
+    assert!(shifted.chunks(8).zip(ct.chunks(8)).all(|(q, c)| q.iter().zip(c).filter(|(q, c)| q == c).count() < 4));
+    let plus_e: Vec<u8> = pt.iter().map(|b| b.wrapping_add(1)).collect();
+    assert_bypass_blocked::<HillCaesarCipher>("c + E(e) - E(0)", shifted, &plus_e);
+}
+
+#[test]
+fn hill_gf256_bypasses_are_blocked() {
+    let (ct, pt) = challenge::<HillGf256Cipher>();
+    let key = HillGf256Cipher::secret().key;
+
+    // the shift is added mod 256, not XORed, but E(0) gives it away, and
+    // without it the cipher is linear under XOR: s + ((c - s) ^ (E(e) - s))
+    // decrypts to p ^ e
+    let s = HillGf256Cipher::encrypt_bytes(&key, Bytes::from(vec![0u8; 8]))[0];
+    let ee = HillGf256Cipher::encrypt_bytes(&key, Bytes::from(vec![1u8; ct.len()]));
+    let masked: Vec<u8> = (0..ct.len()).map(|i| (ct[i].wrapping_sub(s) ^ ee[i].wrapping_sub(s)).wrapping_add(s)).collect();
+    assert!(masked.chunks(8).zip(ct.chunks(8)).all(|(q, c)| q.iter().zip(c).filter(|(q, c)| q == c).count() < 4));
+    let xor_e: Vec<u8> = pt.iter().map(|b| b ^ 1).collect();
+    assert_bypass_blocked::<HillGf256Cipher>("s + ((c - s) ^ (E(e) - s))", masked, &xor_e);
+}
+
+#[test]
+fn own_key_queries_are_allowed() {
+    assert_own_key_queries_allowed::<ChaCha20Cipher>();
+    assert_own_key_queries_allowed::<HillCaesarCipher>();
+}
+
+/// `bytes` with every alphabet symbol moved `shift` places along the alphabet
+fn shifted(bytes: &[u8], shift: usize) -> Vec<u8> {
+    map_symbols(bytes, |x| x + shift)
+}
+
+fn reversed(bytes: &[u8]) -> Vec<u8> {
+    bytes.iter().rev().copied().collect()
+}
+
+fn prepended(bytes: &[u8]) -> Vec<u8> {
+    let mut query = b"Q".to_vec();
+    query.extend_from_slice(bytes);
+    query
+}
+
+#[test]
+fn monoalphabetic_bypasses_are_blocked() {
+    let (ct, pt) = challenge::<CaesarCipher>();
+    assert_bypass_blocked::<CaesarCipher>("prepend a symbol", prepended(&ct), &pt);
+    assert_bypass_blocked::<CaesarCipher>("reverse", reversed(&ct), &reversed(&pt));
+    assert_bypass_blocked::<CaesarCipher>("shift every symbol", shifted(&ct, 1), &shifted(&pt, 1));
+
+    let (ct, pt) = challenge::<AffineCipher>();
+    assert_bypass_blocked::<AffineCipher>("prepend a symbol", prepended(&ct), &pt);
+    assert_bypass_blocked::<AffineCipher>("reverse", reversed(&ct), &reversed(&pt));
+    // a shift of the ciphertext by a is a shift of the plaintext by 1
+    let a = AffineCipher::secret().key[0] as usize;
+    assert_bypass_blocked::<AffineCipher>("shift every symbol", shifted(&ct, a), &shifted(&pt, 1));
+
+    let (ct, pt) = challenge::<SubstitutionCipher>();
+    assert_bypass_blocked::<SubstitutionCipher>("prepend a symbol", prepended(&ct), &pt);
+    assert_bypass_blocked::<SubstitutionCipher>("reverse", reversed(&ct), &reversed(&pt));
+}
+
+#[test]
+fn vigenere_bypasses_are_blocked() {
+    let (ct, pt) = challenge::<VigenereCipher>();
+    let period = vigenere::shifts(&VigenereCipher::secret().key).len();
+    assert!(ct[..period].iter().all(|&b| index(b).is_some()));
+    assert_bypass_blocked::<VigenereCipher>("drop a period", ct[period..].to_vec(), &pt[period..]);
+    assert_bypass_blocked::<VigenereCipher>("drop two periods", ct[2 * period..].to_vec(), &pt[2 * period..]);
+    assert_bypass_blocked::<VigenereCipher>("shift every symbol", shifted(&ct, 5), &shifted(&pt, 5));
+}
+
+#[test]
+fn transposition_bypasses_are_blocked() {
+    let (ct, pt) = challenge::<TranspositionCipher>();
+    assert_bypass_blocked::<TranspositionCipher>("shift every symbol", shifted(&ct, 1), &shifted(&pt, 1));
+    // swapping two ciphertext bytes swaps two plaintext bytes, the rest is left as it is
+    let (i, j) = (0, (1..ct.len()).find(|&j| ct[j] != ct[0]).unwrap());
+    let mut swapped = ct.clone();
+    swapped.swap(i, j);
+    let decrypted = TranspositionCipher::decrypt_bytes(&TranspositionCipher::secret().key, Bytes::from(swapped.clone())).unwrap();
+    let first = (0..pt.len()).find(|&k| decrypted[k] != pt[k]).unwrap();
+    assert_bypass_blocked::<TranspositionCipher>("swap two bytes", swapped, &pt[..first]);
+}
+
+#[test]
+fn playfair_bypasses_are_blocked() {
+    let (ct, pt) = challenge::<PlayfairCipher>();
+    assert!(ct[..4].iter().all(|&b| index(b).is_some()));
+    let mut reordered = ct.clone();
+    reordered[..4].rotate_left(2);
+    assert_bypass_blocked::<PlayfairCipher>("swap two pairs", reordered, &pt[4..]);
+
+    // a pair written backwards decrypts to the plaintext pair backwards
+    let symbols: Vec<usize> = (0..ct.len()).filter(|&i| index(ct[i]).is_some()).collect();
+    let mut backwards = ct.clone();
+    for pair in symbols.chunks_exact(2) {
+        backwards.swap(pair[0], pair[1]);
+    }
+    let mut leaked = pt.clone();
+    let symbols: Vec<usize> = (0..pt.len()).filter(|&i| index(pt[i]).is_some()).collect();
+    for pair in symbols.chunks_exact(2) {
+        leaked.swap(pair[0], pair[1]);
+    }
+    assert_bypass_blocked::<PlayfairCipher>("pairs backwards", backwards, &leaked);
+}
+
+#[test]
+fn honest_classical_queries_are_allowed() {
+    assert_honest_queries_allowed::<CaesarCipher>();
+    assert_honest_queries_allowed::<AffineCipher>();
+    assert_honest_queries_allowed::<SubstitutionCipher>();
+    assert_honest_queries_allowed::<VigenereCipher>();
+    assert_honest_queries_allowed::<TranspositionCipher>();
+    assert_honest_queries_allowed::<PlayfairCipher>();
+    assert_own_key_queries_allowed::<CaesarCipher>();
+    assert_own_key_queries_allowed::<PlayfairCipher>();
+}
+
+/// a key from `--key-file` is the user's own, like one from `--key`, for
+/// decrypt and batch decrypt alike
+#[test]
+fn key_file_queries_are_allowed() {
+    for bin in [env!("CARGO_BIN_EXE_cipher1"), env!("CARGO_BIN_EXE_cipher2"), env!("CARGO_BIN_EXE_cipher3"), env!("CARGO_BIN_EXE_cipher4")] {
+        let run = |args: &[&str], stdin: &[u8]| {
+            let mut child = Command::new(bin).args(args).stdin(Stdio::piped()).stdout(Stdio::piped()).spawn().unwrap();
+            child.stdin.take().unwrap().write_all(stdin).unwrap();
+            let output = child.wait_with_output().unwrap();
+            (output.status.success(), String::from_utf8(output.stdout).unwrap().trim().to_string())
+        };
+        let key = run(&["generate"], b"").1;
+        let key_file = std::env::temp_dir().join(format!("g2-protection-{}-{}.key", std::process::id(), Path::new(bin).file_name().unwrap().display()));
+        std::fs::write(&key_file, &key).unwrap();
+        let key_file = key_file.to_str().unwrap();
+
+        let ciphertext = run(&["encrypt", "Hello", "-k", &key], b"").1;
+        assert_eq!(run(&["decrypt", &ciphertext, "--key-file", key_file], b""), (true, "Hello".to_string()), "{}", bin);
+        let batch = run(&["batch", "--decrypt", "--key-file", key_file], format!("{}\n", ciphertext).as_bytes());
+        assert_eq!(batch, (true, "Hello".to_string()), "{}", bin);
+        std::fs::remove_file(key_file).unwrap();
+    }
+}
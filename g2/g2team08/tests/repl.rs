@@ -0,0 +1,94 @@
+#![cfg(feature = "cli")]
+
+use g2::registry;
+use g2::repl::{self, Kind, Output, Repl};
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+const SEED: &str = "0101010101010101010101010101010101010101010101010101010101010101";
+
+fn value(repl: &mut Repl, line: &str) -> String {
+    match repl.eval(line) {
+        Ok(Output::Value(value)) => value.to_string(),
+        other => panic!("{}: {:?}", line, other),
+    }
+}
+
+fn text(repl: &mut Repl, line: &str) -> String {
+    match repl.eval(line) {
+        Ok(Output::Text(text)) => text,
+        other => panic!("{}: {:?}", line, other),
+    }
+}
+
+#[test]
+fn keys_values_and_history() {
+    let mut repl = Repl::new(registry::find("chacha20").unwrap());
+    let key = value(&mut repl, &format!("k = gen {}", SEED));
+    assert_eq!(repl.var("k").unwrap().kind, Kind::Key("chacha20"));
+    assert_eq!(value(&mut repl, &format!("gen {}", SEED)), key);
+
+    let ciphertext = value(&mut repl, "c = encrypt $k attack at dawn");
+    assert_eq!(repl.var("c").unwrap().kind, Kind::Ciphertext("chacha20"));
+    assert_eq!(value(&mut repl, "decrypt $k $c"), "attack at dawn");
+    assert_eq!(repl.var("_").unwrap().kind, Kind::Text);
+    // literals are base64, and a key literal is checked against the cipher
+    assert_eq!(value(&mut repl, &format!("decrypt {} {}", key, ciphertext)), "attack at dawn");
+    assert_eq!(value(&mut repl, &format!("k2 = key {}", key)), key);
+    assert!(repl.eval("key AAAA").is_err());
+
+    // the stream cipher under one key: equal prefixes, equal ciphertext
+    value(&mut repl, "d = encrypt $k attack at dusk");
+    let comparison = text(&mut repl, "compare $c $d");
+    assert!(comparison.contains("lengths 14 and 14, 3 bytes differ"), "{}", comparison);
+    assert!(comparison.contains("first difference at byte 11"), "{}", comparison);
+
+    let history = text(&mut repl, "history");
+    assert!(history.lines().nth(2).unwrap().ends_with("c = encrypt $k attack at dawn"));
+    assert_eq!(value(&mut repl, "!4"), "attack at dawn");
+    assert!(repl.eval("!99").is_err());
+}
+
+#[test]
+fn switching_ciphers() {
+    let mut repl = Repl::new(registry::find("hill-caesar").unwrap());
+    value(&mut repl, "k = gen");
+    assert!(text(&mut repl, "inspect $k").contains("invertible: yes"));
+    assert!(text(&mut repl, "ciphers").lines().any(|line| line == "* hill-caesar (65-byte keys)"));
+
+    assert_eq!(repl.eval("use salsa20"), Ok(Output::Nothing));
+    assert_eq!(repl.cipher().name, "salsa20");
+    // a key stays the key of its cipher, even after a switch
+    let e = repl.eval("encrypt $k hello").unwrap_err();
+    assert!(e.to_string().contains("hill-caesar key"), "{}", e);
+    assert!(text(&mut repl, "inspect $k").starts_with("hill-caesar key"));
+    assert!(repl.eval("use rot13").is_err());
+    assert!(repl.eval("x = vars").is_err());
+    assert!(repl.eval("frobnicate").is_err());
+    assert_eq!(repl.eval("quit"), Ok(Output::Quit));
+}
+
+#[test]
+fn session_over_standard_input() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cipher1"))
+        .arg("repl")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let session = format!("k = gen {}\nc = encrypt $k HelloWorld\ndecrypt $k $c\nbogus\nquit\nhelp\n", SEED);
+    child.stdin.take().unwrap().write_all(session.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    // no prompt off a terminal, and nothing after quit
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[2], "HelloWorld");
+    assert!(lines[3].starts_with("error: unknown command"));
+
+    let mut output = Vec::new();
+    repl::run(registry::find("caesar").unwrap(), "gen\n".as_bytes(), &mut output, true).unwrap();
+    assert!(String::from_utf8(output).unwrap().starts_with("caesar> "));
+}
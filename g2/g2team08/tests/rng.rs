@@ -0,0 +1,69 @@
+use g2::rng::{ChaCha20Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng as Reference;
+
+const SEED: [u8; 32] = [
+    0, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4, 0, 0, 0, 5, 0, 0, 0, 6, 0, 0, 0, 7, 0, 0, 0,
+];
+
+/// draws the same mix of words, double words and odd byte counts from both
+fn assert_same_output(rng: &mut ChaCha20Rng, reference: &mut Reference, draws: usize) {
+    for i in 0..draws {
+        match i % 3 {
+            0 => assert_eq!(rng.next_u32(), reference.next_u32()),
+            1 => assert_eq!(rng.next_u64(), reference.next_u64()),
+            _ => {
+                let (mut a, mut b) = (vec![0u8; i % 71], vec![0u8; i % 71]);
+                rng.fill_bytes(&mut a);
+                reference.fill_bytes(&mut b);
+                assert_eq!(a, b);
+            }
+        }
+        assert_eq!(rng.get_word_pos(), reference.get_word_pos());
+    }
+}
+
+#[test]
+fn matches_rand_chacha() {
+    let mut rng = ChaCha20Rng::from_seed(SEED);
+    let mut reference = Reference::from_seed(SEED);
+    assert_eq!(rng.get_word_pos(), 0);
+    assert_same_output(&mut rng, &mut reference, 500);
+
+    // the 64-bit counter runs over into the IETF nonce and wraps at 2^68 words
+    for word_pos in [7, 1 << 36, (1 << 68) - 64, (1 << 68) - 16, (1 << 68) - 3, (1 << 70) + 5] {
+        rng.set_word_pos(word_pos);
+        reference.set_word_pos(word_pos);
+        assert_eq!(rng.get_word_pos(), reference.get_word_pos());
+        assert_same_output(&mut rng, &mut reference, 40);
+    }
+
+    // a stream switched part way through a block keeps the word position
+    rng.set_stream(51);
+    reference.set_stream(51);
+    assert_eq!(rng.get_stream(), 51);
+    assert_same_output(&mut rng, &mut reference, 40);
+    assert_eq!(rng.get_seed(), reference.get_seed());
+}
+
+#[test]
+fn ietf_test_vector() {
+    // test vector 5 of draft-nir-cfrg-chacha20-poly1305-04, the nonce 0,0,0,0, 0,0,0,0, 0,0,0,2
+    let mut rng = ChaCha20Rng::from_seed([0; 32]);
+    rng.set_stream(2 << 56);
+    let words: Vec<u32> = (0..4).map(|_| rng.next_u32()).collect();
+    assert_eq!(words, [0x374dc6c2, 0x3736d58c, 0xb904e24a, 0xcd3f93ef]);
+}
+
+#[test]
+fn fast_key_erasure() {
+    let mut rng = ChaCha20Rng::from_seed(SEED);
+    rng.next_u32();
+    let mut key = [0u8; 32];
+    rng.clone().fill_bytes(&mut key);
+
+    rng.reseed();
+    assert_eq!(rng.get_seed(), key);
+    assert_eq!(rng.get_word_pos(), 0);
+    assert_eq!(rng, ChaCha20Rng::from_seed(key));
+    assert_same_output(&mut rng, &mut Reference::from_seed(key), 100);
+}
@@ -0,0 +1,137 @@
+#![cfg(feature = "cli")]
+
+use g2::bytes::Bytes;
+use g2::cipher::Key;
+use g2::cipher2::cipher::{chacha20_block, ChaCha20Cipher};
+use g2::cipher4::cipher::{Salsa2012Cipher, Salsa20Cipher};
+use g2::rand;
+use g2::registry::{self, RegistryError};
+use g2::stream::{self, CHUNK_SIZE};
+use g2::traits::{ChallengeCipher, EncryptBytes};
+
+use std::io::{self, Cursor};
+
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+}
+
+/// keystream of `C` for a 32-byte key and an 8-byte nonce, starting at block 0
+fn keystream<C: EncryptBytes>(key: &str, nonce: &str, len: usize) -> Vec<u8> {
+    let mut full_key = hex(key);
+    full_key.extend(hex(nonce));
+    full_key.extend([0u8; 8]);
+    C::encrypt_bytes(Key::<C>::from_slice(&full_key), Bytes::from(vec![0u8; len])).to_vec()
+}
+
+const KEY0: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+const KEY1: &str = "8000000000000000000000000000000000000000000000000000000000000000";
+const KEY_LONG: &str = "0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20";
+
+#[test]
+fn salsa20_estream_vectors() {
+    // eSTREAM verified test vectors, 256-bit keys
+    assert_eq!(
+        keystream::<Salsa20Cipher>(KEY1, "0000000000000000", 64),
+        hex("e3be8fdd8beca2e3ea8ef9475b29a6e7003951e1097a5c38d23b7a5fad9f6844\
+             b22c97559e2723c7cbbd3fe4fc8d9a0744652a83e72a9c461876af4d7ef1a117")
+    );
+    assert_eq!(
+        keystream::<Salsa20Cipher>(KEY0, "8000000000000000", 64),
+        hex("2aba3dc45b4947007b14c851cd694456b303ad59a465662803006705673d6c3e\
+             29f1d3510dfc0405463c03414e0e07e359f1f1816c68b2434a19d3eee0464873")
+    );
+    assert_eq!(
+        keystream::<Salsa20Cipher>(KEY0, "0000000000000001", 64),
+        hex("b47f96aa96786135297a3c4ec56a613d0b80095324ff43239d684c57ffe42e1c\
+             44f3cc011613db6cdc880999a1e65aed1287fcb11c839c37120765afa73e5075")
+    );
+    assert_eq!(
+        keystream::<Salsa20Cipher>(KEY_LONG, "0301040105090206", 256),
+        hex("6ebcbdbf76fccc64ab05542bee8a67cbc28fa2e141fbefbb3a2f9b221909c8d7\
+             d4295258cb539770dd24d7ac3443769ffa27a50e60644264dc8b6b612683372e\
+             085d0a12bf240b189ce2b78289862b56fdc9fcffc33bef9325a2e81b98fb3fb9\
+             aa04cf434615ceffeb985c1cb08d8440e90b1d56ddeaea16d9e15affff1f698c\
+             483c7a466af1fe062574adfd2b06a62b4d98440719ea776385c470349a7ed696\
+             9583463ed5d26b8fefccb205da0f5bfa98c77812fe756b09eacc282aa42f4baf\
+             a79633189046e2b20f35b3e0e54aa3b929e23c0f47dc7bcd4f928b2a9764be7d\
+             4b8a50f980a50b35ad8087375e0c556ecbe6a7161e8653ce9391e1e6710ed4f1")
+    );
+}
+
+#[test]
+fn salsa20_12_estream_vectors() {
+    assert_eq!(
+        keystream::<Salsa2012Cipher>(KEY1, "0000000000000000", 128),
+        hex("afe411ed1c4e07e4d0cde3b33e31ec190fa4cc796a58bafb848ead8d07d02cd2\
+             d4b6f9f30cb0b57007e3733895cc8d1060107975acaeeb689b6cf614ab64a3d6\
+             08e397d664b03a12cb31711c6a3f1528dd0031b6adc87574a2ad8e8f470f4aa1\
+             8f21c6ed7b5178a9978847dff457edd5192c7d70b7a8636da61a98eb2053c285")
+    );
+    assert_eq!(
+        keystream::<Salsa2012Cipher>(KEY_LONG, "0301040105090206", 64),
+        hex("a5a89b1a205e5dfaceac52f267d1370c9ab9360aac690910d5487b5f8f7cf6c0\
+             c6b9f5edbf3d7c1d9a94732339a6d36c712d5e91bcb752b643c6f2817c241871")
+    );
+}
+
+#[test]
+fn chacha20_block_on_shared_rounds() {
+    // RFC 8439, section 2.3.2
+    let key: [u8; 32] = core::array::from_fn(|i| i as u8);
+    let nonce: [u8; 12] = hex("000000090000004a00000000").try_into().unwrap();
+    assert_eq!(
+        chacha20_block(&key, &nonce, 1).to_vec(),
+        hex("10f1e7e4d13b5915500fdd1fa32071c4c7d1f4c733c068030422aa9ac3d46c4e\
+             d2826446079faa0914c2d705d98b02a2b5129cd1de164eb9cbd083e8a2503c4e")
+    );
+}
+
+fn check_round_trip<C: ChallengeCipher>() {
+    for len in [0, 1, 63, 64, 65, 300] {
+        let key = C::gen_keys(rand::rng());
+        let key = Key::<C>::from_slice(&key);
+        let message = Bytes::from(vec![b'x'; len]);
+        let encrypted_message = C::encrypt_bytes(key, message.clone());
+        assert_eq!(encrypted_message.len(), len);
+        assert_eq!(C::decrypt_bytes(key, encrypted_message).unwrap(), message, "{}", C::NAME);
+    }
+}
+
+#[test]
+fn round_trip() {
+    check_round_trip::<Salsa20Cipher>();
+    check_round_trip::<Salsa2012Cipher>();
+}
+
+/// the key of `C` whose counter, the last `counter_size` bytes, is `counter`
+fn key_at_counter<C: ChallengeCipher>(counter: u64, counter_size: usize) -> Key<C> {
+    let mut key = C::gen_keys(rand::rng());
+    let at = key.len() - counter_size;
+    key[at..].copy_from_slice(&counter.to_le_bytes()[..counter_size]);
+    Key::<C>::clone_from_slice(&key)
+}
+
+/// the keystream ends with the block of the last counter value instead of
+/// wrapping around to block 0
+fn check_keystream_end<C: ChallengeCipher>(max_counter: u64, counter_size: usize) {
+    let key = key_at_counter::<C>(max_counter, counter_size);
+    let last = C::try_encrypt(&key, Bytes::from(vec![0u8; 64])).unwrap();
+    assert_eq!(C::decrypt_bytes(&key, last).unwrap(), vec![0u8; 64]);
+    assert!(C::try_encrypt(&key, Bytes::from(vec![0u8; 65])).is_err(), "{}", C::NAME);
+    assert!(C::decrypt_bytes(&key, Bytes::from(vec![0u8; 65])).is_err(), "{}", C::NAME);
+    let cipher = registry::find(C::NAME).unwrap();
+    assert!(matches!(cipher.encrypt(&key, Bytes::from(vec![0u8; 65])), Err(RegistryError::Encrypt(_))));
+
+    // the first chunk of a stream takes the keystream to its end, the second has none left
+    let blocks = (CHUNK_SIZE / 64) as u64;
+    let key = key_at_counter::<C>(max_counter - blocks + 1, counter_size);
+    assert!(stream::run::<C>(&key, false, Cursor::new(vec![0u8; CHUNK_SIZE]), io::sink()).is_ok());
+    assert!(stream::run::<C>(&key, false, Cursor::new(vec![0u8; CHUNK_SIZE + 1]), io::sink()).is_err(), "{}", C::NAME);
+}
+
+#[test]
+fn keystream_ends_at_the_last_counter() {
+    check_keystream_end::<Salsa20Cipher>(u64::MAX, 8);
+    check_keystream_end::<Salsa2012Cipher>(u64::MAX, 8);
+    check_keystream_end::<ChaCha20Cipher>(u32::MAX as u64, 4);
+}
@@ -0,0 +1,102 @@
+#![cfg(feature = "cli")]
+
+use g2::cipher2::cipher::ChaCha20Cipher;
+use g2::server::{self, Endpoint, OracleConfig};
+use g2::traits::{ChallengeCipher, EncryptBytes, CHEATER};
+
+use base64::prelude::*;
+use std::net::TcpListener;
+use std::os::unix::net::UnixListener;
+use std::thread;
+
+fn spawn_oracle(config: OracleConfig) -> Endpoint {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || server::serve_tcp::<ChaCha20Cipher>(listener, config));
+    Endpoint::Tcp(addr)
+}
+
+fn requests(lines: &[&str]) -> Vec<String> {
+    lines.iter().map(|line| line.to_string()).collect()
+}
+
+#[test]
+fn oracle_round_trip() {
+    let endpoint = spawn_oracle(OracleConfig::default());
+    let replies = server::request(&endpoint, &requests(&["GENERATE", "ENCRYPT Hello"])).unwrap();
+    let key = replies[0].strip_prefix("OK ").unwrap();
+    let default_ciphertext = replies[1].strip_prefix("OK ").unwrap();
+
+    let replies = server::request(
+        &endpoint,
+        &requests(&[
+            &format!("ENCRYPT Hello {}", key),
+            &format!("DECRYPT {}", default_ciphertext),
+        ]),
+    )
+    .unwrap();
+    let ciphertext = replies[0].strip_prefix("OK ").unwrap();
+    assert_ne!(ciphertext, default_ciphertext);
+    // every ciphertext under the default key shares the challenge's keystream
+    assert_eq!(replies[1], format!("ERR {}", CHEATER));
+
+    let replies = server::request(&endpoint, &requests(&[&format!("DECRYPT {} {}", ciphertext, key)])).unwrap();
+    assert_eq!(replies, ["OK Hello"]);
+}
+
+#[test]
+fn oracle_refuses_challenge() {
+    let endpoint = spawn_oracle(OracleConfig::default());
+    let challenge = BASE64_STANDARD.encode(ChaCha20Cipher::secret().encrypted_message);
+    let replies = server::request(&endpoint, &requests(&[&format!("DECRYPT {}", challenge), "ENCRYPT a b c", "PING"])).unwrap();
+    assert_eq!(replies[0], format!("ERR {}", CHEATER));
+    assert!(replies[1].starts_with("ERR "));
+    assert!(replies[2].starts_with("ERR "));
+}
+
+#[test]
+fn oracle_enforces_budget_and_audits() {
+    let audit_log = std::env::temp_dir().join(format!("g2-oracle-audit-{}.log", std::process::id()));
+    let _ = std::fs::remove_file(&audit_log);
+    let endpoint = spawn_oracle(OracleConfig {
+        budget: Some(2),
+        audit_log: Some(audit_log.clone()),
+    });
+
+    let replies = server::request(&endpoint, &requests(&["ENCRYPT a", "ENCRYPT bb", "ENCRYPT c", "ENCRYPT d"])).unwrap();
+    assert_eq!(replies.len(), 3);
+    assert!(replies[0].starts_with("OK "));
+    assert!(replies[1].starts_with("OK "));
+    assert_eq!(replies[2], "ERR query budget exhausted");
+
+    // a new connection gets a fresh budget
+    let key = BASE64_STANDARD.encode(ChaCha20Cipher::gen_keys(g2::rand::rng()));
+    let replies = server::request(&endpoint, &requests(&[&format!("ENCRYPT secretword {}", key)])).unwrap();
+    assert!(replies[0].starts_with("OK "));
+
+    let log = std::fs::read_to_string(&audit_log).unwrap();
+    assert_eq!(log.lines().count(), 4);
+    assert!(log.lines().all(|line| line.contains("request=ENCRYPT len=")));
+    assert!(log.lines().nth(1).unwrap().ends_with("len=2 key=default"));
+    assert_eq!(log.lines().filter(|line| line.contains("outcome=error")).count(), 1);
+    // only the verb, the lengths and key ids, never the messages or keys
+    assert!(!log.contains("secretword") && !log.contains(&key));
+    std::fs::remove_file(&audit_log).unwrap();
+}
+
+#[test]
+fn oracle_serves_unix_sockets() {
+    let path = std::env::temp_dir().join(format!("g2-oracle-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path).unwrap();
+    thread::spawn(move || server::serve_unix::<ChaCha20Cipher>(listener, OracleConfig::default()));
+    let endpoint = Endpoint::Unix(path.clone());
+
+    let replies = server::request(&endpoint, &requests(&["GENERATE"])).unwrap();
+    let key = replies[0].strip_prefix("OK ").unwrap();
+    let replies = server::request(&endpoint, &requests(&[&format!("ENCRYPT Hello {}", key)])).unwrap();
+    let ciphertext = replies[0].strip_prefix("OK ").unwrap();
+    let replies = server::request(&endpoint, &requests(&[&format!("DECRYPT {} {}", ciphertext, key), "QUIT", "GENERATE"])).unwrap();
+    assert_eq!(replies, ["OK Hello"]);
+    std::fs::remove_file(&path).unwrap();
+}
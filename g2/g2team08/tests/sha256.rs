@@ -0,0 +1,31 @@
+use g2::sha256::{sha256, Sha256};
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[test]
+fn fips_180_examples() {
+    assert_eq!(hex(&sha256(b"")), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+    assert_eq!(hex(&sha256(b"abc")), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    assert_eq!(
+        hex(&sha256(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq")),
+        "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+    );
+}
+
+#[test]
+fn incremental_matches_one_shot() {
+    let message: Vec<u8> = (0..1000u32).map(|i| (i * 7 % 251) as u8).collect();
+    for split in [0, 1, 55, 56, 63, 64, 65, 500, 1000] {
+        let mut hasher = Sha256::new();
+        hasher.update(&message[..split]).update(&message[split..]);
+        assert_eq!(hasher.finalize(), sha256(&message), "{}", split);
+    }
+
+    let mut hasher = Sha256::new();
+    for _ in 0..1000 {
+        hasher.update(&[b'a'; 1000]);
+    }
+    assert_eq!(hex(&hasher.finalize()), "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0");
+}
@@ -0,0 +1,118 @@
+#![cfg(feature = "cli")]
+
+use g2::bytes::Bytes;
+use g2::cipher::Key;
+use g2::cipher1::cipher::HillCaesarCipher;
+use g2::cipher2::cipher::ChaCha20Cipher;
+use g2::cipher3::cipher::HillGf256Cipher;
+use g2::cipher4::cipher::{Salsa2012Cipher, Salsa20Cipher};
+use g2::classical::{affine::AffineCipher, caesar::CaesarCipher, playfair::PlayfairCipher};
+use g2::classical::{substitution::SubstitutionCipher, transposition::TranspositionCipher, vigenere::VigenereCipher};
+use g2::rng::seeded;
+use g2::stream::{self, CHUNK_SIZE};
+use g2::traits::ChallengeCipher;
+
+use std::io::{Cursor, Write};
+use std::process::{Command, Stdio};
+
+/// a few chunks of text with runs of zeros, which Hill decryption strips from the end only
+fn input() -> Vec<u8> {
+    let mut input = Vec::new();
+    while input.len() < 3 * CHUNK_SIZE + 1000 {
+        input.extend_from_slice(b"Streams of the g2 ciphers, ");
+        input.extend_from_slice(&[0; 37]);
+    }
+    input.extend_from_slice(b"the end");
+    input
+}
+
+fn stream_matches_whole_message<C: ChallengeCipher>() {
+    let key = C::gen_keys(seeded([4; 32]));
+    let key = Key::<C>::from_slice(&key);
+    let input = input();
+    let whole = C::encrypt_bytes(key, Bytes::from(input.clone()));
+
+    let mut ciphertext = Vec::new();
+    let read = stream::run::<C>(key, false, Cursor::new(&input), &mut ciphertext).unwrap();
+    assert_eq!(read, input.len() as u64, "{}", C::NAME);
+    assert!(ciphertext == whole, "{} streams differently", C::NAME);
+
+    let mut plaintext = Vec::new();
+    stream::run::<C>(key, true, Cursor::new(&ciphertext), &mut plaintext).unwrap();
+    assert!(plaintext == input, "{} does not round-trip", C::NAME);
+}
+
+#[test]
+fn streams_match_whole_messages() {
+    stream_matches_whole_message::<HillCaesarCipher>();
+    stream_matches_whole_message::<ChaCha20Cipher>();
+    stream_matches_whole_message::<HillGf256Cipher>();
+    stream_matches_whole_message::<Salsa20Cipher>();
+    stream_matches_whole_message::<Salsa2012Cipher>();
+    stream_matches_whole_message::<CaesarCipher>();
+    stream_matches_whole_message::<AffineCipher>();
+    stream_matches_whole_message::<VigenereCipher>();
+    stream_matches_whole_message::<SubstitutionCipher>();
+    stream_matches_whole_message::<PlayfairCipher>();
+    stream_matches_whole_message::<TranspositionCipher>();
+}
+
+fn run(bin: &str, args: &[&str], stdin: &[u8]) -> std::process::Output {
+    let mut child = Command::new(bin)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn files_and_standard_input() {
+    let bin = env!("CARGO_BIN_EXE_cipher4");
+    let key = String::from_utf8(run(bin, &["generate"], b"").stdout).unwrap();
+    let key = key.trim();
+    let dir = std::env::temp_dir().join(format!("g2-stream-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (plain, encrypted) = (dir.join("plain"), dir.join("encrypted"));
+    std::fs::write(&plain, input()).unwrap();
+
+    let args = ["encrypt", "-k", key, "--input", plain.to_str().unwrap(), "--output", encrypted.to_str().unwrap()];
+    let output = run(bin, &args, b"");
+    assert!(output.status.success() && output.stdout.is_empty());
+
+    let output = run(bin, &["decrypt", "-k", key, "--input", "-"], &std::fs::read(&encrypted).unwrap());
+    assert!(output.status.success());
+    assert!(output.stdout == input());
+
+    // the challenge key is not streamed for decryption
+    let output = run(bin, &["--json", "decrypt", "--input", "-"], b"");
+    assert!(!output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["error"]["kind"], "invalid_key");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn batch_answers_every_line() {
+    let bin = env!("CARGO_BIN_EXE_cipher2");
+    let key = String::from_utf8(run(bin, &["generate"], b"").stdout).unwrap();
+    let key = key.trim();
+
+    let output = run(bin, &["batch", "-k", key], b"Hello\r\nillegal message\nWorld\n");
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[1].starts_with("error: "));
+
+    let ciphertexts = format!("{}\n!!\n{}\n", lines[0], lines[2]);
+    let output = run(bin, &["--json", "batch", "--decrypt", "-k", key], ciphertexts.as_bytes());
+    assert!(!output.status.success());
+    let reports: Vec<serde_json::Value> = output.stdout.split(|&b| b == b'\n').filter(|line| !line.is_empty()).map(|line| serde_json::from_slice(line).unwrap()).collect();
+    assert_eq!(reports[0]["plaintext"], "Hello");
+    assert_eq!(reports[1]["error"]["kind"], "invalid_input");
+    assert_eq!(reports[2]["plaintext"], "World");
+}
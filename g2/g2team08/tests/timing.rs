@@ -0,0 +1,84 @@
+#![cfg(feature = "cli")]
+
+use g2::cipher1::cipher::HillCaesarCipher;
+use g2::cipher2::cipher::ChaCha20Cipher;
+use g2::timing::{analyze, measure, measure_cipher, welch_t, Class, Sample, Target, Unit};
+
+#[test]
+fn welch_t_of_known_samples() {
+    assert_eq!(welch_t(&[1.0, 2.0, 3.0], &[1.0, 2.0, 3.0]), 0.0);
+    // means 2 and 5, variances 1 and 4: t = -3 / sqrt(1/3 + 4/3)
+    let t = welch_t(&[1.0, 2.0, 3.0], &[3.0, 5.0, 7.0]);
+    assert!((t + 3.0 / (5.0f64 / 3.0).sqrt()).abs() < 1e-12);
+    assert_eq!(welch_t(&[1.0], &[2.0, 3.0]), 0.0);
+    assert_eq!(welch_t(&[1.0, 1.0], &[2.0, 2.0]), f64::NEG_INFINITY);
+}
+
+fn sample(class: Class, nanos: u64) -> Sample {
+    Sample { class, nanos, cycles: None }
+}
+
+#[test]
+fn crops_cut_off_the_tail() {
+    // the same timings but for one interrupt in the random class
+    let mut samples: Vec<Sample> = (0..100).flat_map(|i| [sample(Class::Fixed, 100 + i % 7), sample(Class::Random, 100 + i % 7)]).collect();
+    samples.push(sample(Class::Random, 1_000_000));
+    let verdict = analyze(&samples, Unit::Nanos).unwrap();
+    assert_eq!((verdict.fixed, verdict.random), (100, 101));
+    assert!(!verdict.leaks(), "{:?}", verdict);
+    assert!(analyze(&samples, Unit::Cycles).is_none());
+
+    let samples: Vec<Sample> = (0..100).flat_map(|i| [sample(Class::Fixed, 100 + i % 7), sample(Class::Random, 110 + i % 7)]).collect();
+    assert!(analyze(&samples, Unit::Nanos).unwrap().leaks());
+}
+
+#[test]
+fn measure_prepares_both_classes() {
+    let samples = measure(1000, [6; 32], |class, _| class, |_| ());
+    let fixed = samples.iter().filter(|s| s.class == Class::Fixed).count();
+    assert!(fixed > 400 && fixed < 600);
+    // the classes, unlike the timings, come from the seed
+    let classes = |samples: &[Sample]| samples.iter().map(|s| s.class).collect::<Vec<_>>();
+    assert_eq!(classes(&samples), classes(&measure(1000, [6; 32], |class, _| class, |_| ())));
+}
+
+#[test]
+fn hill_singular_keys_are_faster() {
+    // an inversion takes a good fraction of a second without optimizations,
+    // and singular keys skip most of it
+    let samples = measure_cipher::<HillCaesarCipher>(Target::Key, 40, [0; 32]);
+    let verdict = analyze(&samples, Unit::Nanos).unwrap();
+    assert!(verdict.leaks(), "{:?}", verdict);
+    assert!(verdict.mean_fixed > verdict.mean_random);
+}
+
+#[test]
+fn hill_valid_keys_are_measured() {
+    let samples = measure_cipher::<HillCaesarCipher>(Target::ValidKey, 16, [0; 32]);
+    let verdict = analyze(&samples, Unit::Nanos).unwrap();
+    assert_eq!(verdict.fixed + verdict.random, 16);
+    // every key is inverted, so the classes take about as long
+    assert!(verdict.mean_fixed < 2.0 * verdict.mean_random && verdict.mean_random < 2.0 * verdict.mean_fixed);
+}
+
+#[test]
+fn hill_modinv_leaks_on_valid_keys() {
+    // a determinant of 1 leaves the loop at once, those of random keys take a
+    // few steps of Euclid
+    let samples = measure_cipher::<HillCaesarCipher>(Target::Modinv, 2000, [0; 32]);
+    let verdict = analyze(&samples, Unit::Nanos).unwrap();
+    assert_eq!(verdict.fixed + verdict.random, 2000);
+    assert!(verdict.leaks(), "{:?}", verdict);
+    assert!(verdict.mean_fixed < verdict.mean_random);
+}
+
+#[test]
+fn chacha_targets_run() {
+    for target in [Target::Encrypt, Target::Decrypt, Target::Key, Target::ValidKey] {
+        let samples = measure_cipher::<ChaCha20Cipher>(target, 200, [0; 32]);
+        let verdict = analyze(&samples, Unit::Nanos).unwrap();
+        assert_eq!(verdict.fixed + verdict.random, 200);
+    }
+    // ChaCha20 has no determinant
+    assert!(measure_cipher::<ChaCha20Cipher>(Target::Modinv, 200, [0; 32]).is_empty());
+}
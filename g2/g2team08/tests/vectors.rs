@@ -0,0 +1,92 @@
+#![cfg(feature = "cli")]
+
+use g2::cipher2::cipher::ChaCha20Cipher;
+use g2::vectors::{check, export, Expected, VectorError, VectorFile};
+
+use std::path::Path;
+use std::process::Command;
+
+const BINS: [&str; 11] = [
+    "cipher1",
+    "cipher2",
+    "cipher3",
+    "cipher4",
+    "cipher4-12",
+    "caesar",
+    "affine",
+    "vigenere",
+    "substitution",
+    "playfair",
+    "transposition",
+];
+
+fn read(path: impl AsRef<Path>) -> VectorFile {
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+fn failures(file: &VectorFile) -> Vec<u64> {
+    check(file).unwrap().into_iter().filter(|outcome| outcome.failure.is_some()).map(|outcome| outcome.tc_id).collect()
+}
+
+/// the stored vectors pin every cipher: exporting them again gives the same file
+#[test]
+fn exported_vectors_are_stable() {
+    for bin in BINS {
+        let path = format!("tests/vectors/g2/{}.json", bin);
+        let output = Command::new(Path::new(env!("CARGO_BIN_EXE_cipher1")).with_file_name(bin))
+            .args(["export-vectors", "--count", "8"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), std::fs::read_to_string(&path).unwrap(), "{}", path);
+        assert_eq!(failures(&read(&path)), Vec::<u64>::new(), "{}", path);
+    }
+    assert_eq!(export::<ChaCha20Cipher>(3, [1; 32]), export::<ChaCha20Cipher>(3, [1; 32]));
+    assert_ne!(export::<ChaCha20Cipher>(3, [1; 32]), export::<ChaCha20Cipher>(3, [2; 32]));
+}
+
+#[test]
+fn local_chacha20_files() {
+    let file = read("tests/vectors/local/chacha20_poly1305.json");
+    assert_eq!(check(&file).unwrap().len(), file.number_of_tests);
+    assert_eq!(failures(&file), Vec::<u64>::new());
+    assert_eq!(failures(&read("tests/vectors/local/chacha20.json")), Vec::<u64>::new());
+
+    // a forgery marked valid, or the genuine vector marked invalid, fails
+    let mut file = file;
+    let cases = &mut file.test_groups[0].tests;
+    cases[0].result = Expected::Invalid;
+    cases[1].result = Expected::Valid;
+    assert_eq!(failures(&file), [1, 2]);
+}
+
+#[test]
+fn tampered_vectors_fail() {
+    let mut file = read("tests/vectors/g2/cipher1.json");
+    let case = &mut file.test_groups[0].tests[3];
+    case.ct.replace_range(..2, if case.ct.starts_with("00") { "01" } else { "00" });
+    let outcomes = check(&file).unwrap();
+    assert_eq!(outcomes[3].failure.as_deref(), Some("the ciphertext or tag differs"));
+
+    file.test_groups[0].tests[4].key = "not hex".to_string();
+    assert_eq!(failures(&file), [4, 5]);
+
+    file.algorithm = "enigma".to_string();
+    assert_eq!(check(&file), Err(VectorError::UnknownAlgorithm("enigma".to_string())));
+}
+
+#[test]
+fn check_vectors_reports_every_test() {
+    let output = Command::new(env!("CARGO_BIN_EXE_caesar"))
+        .args(["check-vectors", "tests/vectors/local/chacha20.json", "tests/vectors/g2/cipher4.json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().filter(|line| line.starts_with("pass #")).count(), 3 + 8);
+    assert!(stdout.contains("3 passed, 0 failed"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_caesar")).args(["check-vectors", "Cargo.toml"]).output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stdout).unwrap().starts_with("Cargo.toml: error: "));
+}
@@ -0,0 +1,106 @@
+//! Public-key encryption of arbitrary messages: a fresh g2 ChaCha20 key
+//! encrypts the payload and travels wrapped under the recipient's RSA key.
+//!
+//! Container layout, all integers big-endian:
+//!
+//! | bytes | content                                   |
+//! |-------|-------------------------------------------|
+//! | 4     | magic `H5RC`                              |
+//! | 1     | format version, 1                         |
+//! | 2     | length `k` of the wrapped key             |
+//! | k     | RSA-OAEP encryption of the ChaCha20 key   |
+//! | rest  | ChaCha20 encryption of the message        |
+//!
+//! The payload is not authenticated: anyone can flip its bits undetected.
+
+use crate::oaep::{self, OaepError};
+use crate::rsa::{PrivateKey, PublicKey};
+
+use g2::bytes::Bytes;
+use g2::cipher::{Key, KeySizeUser};
+use g2::cipher2::cipher::ChaCha20Cipher;
+use g2::rng::CryptoRng;
+use g2::traits::{DecryptBytes, EncryptBytes};
+use zeroize::Zeroize;
+
+use std::{error::Error, fmt};
+
+pub const MAGIC: &[u8; 4] = b"H5RC";
+pub const VERSION: u8 = 1;
+const HEADER_SIZE: usize = MAGIC.len() + 1 + 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HybridError {
+    Truncated,
+    BadMagic,
+    UnsupportedVersion(u8),
+    /// the RSA key cannot wrap a ChaCha20 key
+    KeyTooSmall,
+    /// the wrapped key does not open under this private key
+    Unwrap(OaepError),
+}
+
+impl fmt::Display for HybridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HybridError::Truncated => write!(f, "container is truncated"),
+            HybridError::BadMagic => write!(f, "not an h5 hybrid container"),
+            HybridError::UnsupportedVersion(v) => write!(f, "unsupported container version {}", v),
+            HybridError::KeyTooSmall => write!(
+                f,
+                "RSA modulus too small to wrap a {}-byte ChaCha20 key with OAEP",
+                ChaCha20Cipher::key_size()
+            ),
+            HybridError::Unwrap(e) => write!(f, "cannot unwrap the message key: {}", e),
+        }
+    }
+}
+
+impl Error for HybridError {}
+
+pub fn seal(recipient: &PublicKey, message: &[u8], mut rng: impl CryptoRng) -> Result<Vec<u8>, HybridError> {
+    let mut message_key = ChaCha20Cipher::gen_keys(&mut rng);
+    let wrapped = match oaep::encrypt(recipient, &message_key, &mut rng) {
+        Ok(wrapped) => wrapped,
+        Err(OaepError::MessageTooLong(_)) => return Err(HybridError::KeyTooSmall),
+        Err(e) => return Err(HybridError::Unwrap(e)),
+    };
+    let payload = ChaCha20Cipher::encrypt_bytes(Key::<ChaCha20Cipher>::from_slice(&message_key), Bytes::copy_from_slice(message));
+    message_key.zeroize();
+
+    let mut container = Vec::with_capacity(HEADER_SIZE + wrapped.len() + payload.len());
+    container.extend_from_slice(MAGIC);
+    container.push(VERSION);
+    container.extend_from_slice(&(wrapped.len() as u16).to_be_bytes());
+    container.extend_from_slice(&wrapped);
+    container.extend_from_slice(&payload);
+    Ok(container)
+}
+
+pub fn open(recipient: &PrivateKey, container: &[u8]) -> Result<Vec<u8>, HybridError> {
+    if container.len() < HEADER_SIZE {
+        return Err(HybridError::Truncated);
+    }
+    let (header, body) = container.split_at(HEADER_SIZE);
+    if &header[..MAGIC.len()] != MAGIC {
+        return Err(HybridError::BadMagic);
+    }
+    if header[MAGIC.len()] != VERSION {
+        return Err(HybridError::UnsupportedVersion(header[MAGIC.len()]));
+    }
+    let wrapped_len = u16::from_be_bytes([header[MAGIC.len() + 1], header[MAGIC.len() + 2]]) as usize;
+    if body.len() < wrapped_len {
+        return Err(HybridError::Truncated);
+    }
+    let (wrapped, payload) = body.split_at(wrapped_len);
+
+    let mut message_key = oaep::decrypt(recipient, wrapped).map_err(HybridError::Unwrap)?;
+    if message_key.len() != ChaCha20Cipher::key_size() {
+        message_key.zeroize();
+        return Err(HybridError::Unwrap(OaepError::Decryption));
+    }
+    let message = ChaCha20Cipher::decrypt_bytes(Key::<ChaCha20Cipher>::from_slice(&message_key), Bytes::copy_from_slice(payload))
+        .expect("ChaCha20 decryption cannot fail");
+    message_key.zeroize();
+    Ok(message.to_vec())
+}
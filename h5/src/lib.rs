@@ -0,0 +1,5 @@
+//! RSA, and hybrid RSA-OAEP + ChaCha20 encryption built on the g2 ciphers.
+
+pub mod hybrid;
+pub mod oaep;
+pub mod rsa;
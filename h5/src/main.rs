@@ -1,116 +1,55 @@
-use num_bigint::BigInt;
-use num_integer::Integer;
-use num_traits::{One, Zero};
-use rand::RngCore;
-
-const SECURITY_LEVELS: [(u32, u32); 6] = [
-    (1, 64), // for toy example only
-    (80, 1024),
-    (112, 2048),
-    (128, 3072),
-    (192, 7680),
-    (256, 15360),
-];
-
-fn generate_large_prime(bit_length: u32) -> BigInt {
-    let mut rng = rand::rng();
-    loop {
-        // Generate random bytes and convert to BigInt
-        let mut bytes = vec![0u8; (bit_length as usize + 7) / 8];
-        rng.fill_bytes(&mut bytes);
-        let candidate = BigInt::from_bytes_be(num_bigint::Sign::Plus, &bytes) | BigInt::one();
-        if is_prime(&candidate) {
-            return candidate;
-        }
-    }
-}
-
-/// Miller-Rabin primality test
-fn is_prime(n: &BigInt) -> bool {
-    if n <= &BigInt::one() {
-        return false;
-    }
-    if n == &BigInt::from(2u32) || n == &BigInt::from(3u32) {
-        return true;
-    }
-    if n.is_even() {
-        return false;
-    }
-
-    let mut d = n - BigInt::one();
-    let mut s = 0;
-    while d.is_even() {
-        d = d >> 1;
-        s += 1;
-    }
-
-    let bases = [
-        2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37,
-    ].iter().map(|&x| BigInt::from(x)).collect::<Vec<_>>();
-
-    'next_base: for base in bases {
-        if base >= *n {
-            continue;
-        }
-        let mut x = base.modpow(&d, n);
-        if x == BigInt::one() || x == n - BigInt::one() {
-            continue;
-        }
-        for _ in 0..s - 1 {
-            x = x.modpow(&BigInt::from(2u32), n);
-            if x == n - BigInt::one() {
-                continue 'next_base;
-            }
-        }
-        return false;
-    }
-    true
-}
+use h5::hybrid;
+use h5::rsa::{decrypt, encrypt, generate, PrivateKey, PublicKey};
 
-fn extended_gcd(a: &BigInt, b: &BigInt) -> (BigInt, BigInt, BigInt) {
-    if a.is_zero() {
-        (b.clone(), BigInt::zero(), BigInt::one())
-    } else {
-        let (g, x, y) = extended_gcd(&(b % a), a);
-        (g, y - (b / a) * x.clone(), x)
-    }
-}
-
-fn mod_inverse(a: BigInt, m: BigInt) -> Option<BigInt> {
-    let (g, x, _) = extended_gcd(&a, &m);
-    if g != BigInt::one() {
-        None
-    } else {
-        Some((x % &m + &m) % m)
-    }
+use base64::prelude::*;
+use clap::{Parser, Subcommand};
+use num_bigint::BigInt;
+use std::io::Write;
+use std::{fs, path::{Path, PathBuf}, process};
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Generate an RSA key pair into <out>.pub and <out>.key
+    Keygen {
+        #[arg(long, default_value_t = 112, help = "Security level in bits: 80, 112, 128, 192 or 256")]
+        level: u32,
+        #[arg(long, help = "Path prefix of the key files")]
+        out: PathBuf,
+    },
+    /// Encrypt a message for the owner of a public key, print the container in base64
+    Seal {
+        #[arg(long, help = "Public key file of the recipient")]
+        public_key: PathBuf,
+        message: String,
+    },
+    /// Decrypt a base64 container with a private key
+    Open {
+        #[arg(long, help = "Private key file")]
+        private_key: PathBuf,
+        container: String,
+    },
+    /// Generate toy RSA parameters and encrypt and decrypt a number with them
+    Demo,
 }
 
-pub fn generate(security_level: u32) -> (BigInt, BigInt, BigInt, BigInt, BigInt) {
-    let modulus_bits = SECURITY_LEVELS.iter()
-        .find(|&&(sl, _)| sl == security_level)
-        .map(|&(_, mb)| mb)
-        .expect("Invalid security level");
-
-    let p = generate_large_prime(modulus_bits / 2);
-    let q = generate_large_prime(modulus_bits / 2);
-    let n = &p * &q;
-    let phi = (&p - BigInt::one()) * (&q - BigInt::one());
-
-    let e = BigInt::from(65537u32);
-    let d = mod_inverse(e.clone(), phi).expect("No modular inverse exists");
-
-    (p, q, n, e, d)
+#[derive(Debug, Parser)]
+#[command(about = "RSA and hybrid RSA-OAEP + ChaCha20 encryption")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-pub fn encrypt(m: &BigInt, e: &BigInt, n: &BigInt) -> BigInt {
-    m.modpow(e, n)
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("error: {}", message);
+    process::exit(1);
 }
 
-pub fn decrypt(c: &BigInt, d: &BigInt, n: &BigInt) -> BigInt {
-    c.modpow(d, n)
+fn read_key<K: std::str::FromStr<Err: std::fmt::Display>>(path: &Path) -> K {
+    let text = fs::read_to_string(path).unwrap_or_else(|e| fail(format!("{}: {}", path.display(), e)));
+    text.parse().unwrap_or_else(|e| fail(format!("{}: {}", path.display(), e)))
 }
 
-fn main() {
+fn demo() {
     let security_level = 1; // 128
     let (p, q, n, e, d) = generate(security_level);
 
@@ -132,4 +71,36 @@ fn main() {
 
     assert_eq!(message, decrypted, "Decryption failed!");
     println!("Decryption successful!");
-}
\ No newline at end of file
+}
+
+fn main() {
+    match Cli::parse().command.unwrap_or(Command::Demo) {
+        Command::Keygen { level, out } => {
+            if h5::rsa::modulus_bits(level).is_none_or(|bits| bits < 1024) {
+                fail(format!("unsupported security level {}", level));
+            }
+            let private_key = PrivateKey::generate(level);
+            let public_path = out.with_extension("pub");
+            let private_path = out.with_extension("key");
+            fs::write(&public_path, private_key.public_key().to_string())
+                .unwrap_or_else(|e| fail(format!("{}: {}", public_path.display(), e)));
+            fs::write(&private_path, private_key.to_string())
+                .unwrap_or_else(|e| fail(format!("{}: {}", private_path.display(), e)));
+            println!("{}", public_path.display());
+            println!("{}", private_path.display());
+        }
+        Command::Seal { public_key, message } => {
+            let public_key: PublicKey = read_key(&public_key);
+            let container = hybrid::seal(&public_key, message.as_bytes(), rand::rng()).unwrap_or_else(|e| fail(e));
+            println!("{}", BASE64_STANDARD.encode(container));
+        }
+        Command::Open { private_key, container } => {
+            let private_key: PrivateKey = read_key(&private_key);
+            let container = BASE64_STANDARD.decode(container.trim()).unwrap_or_else(|e| fail(e));
+            let message = hybrid::open(&private_key, &container).unwrap_or_else(|e| fail(e));
+            let mut stdout = std::io::stdout();
+            stdout.write_all(&message).and_then(|_| writeln!(stdout)).unwrap();
+        }
+        Command::Demo => demo(),
+    }
+}
@@ -0,0 +1,112 @@
+//! RSAES-OAEP from PKCS #1 v2.2 (RFC 8017, section 7.1) with SHA-256 and
+//! MGF1-SHA-256, and an empty label.
+
+use crate::rsa::{self, PrivateKey, PublicKey};
+
+use g2::rng::CryptoRng;
+use g2::sha256::{sha256, Sha256, DIGEST_SIZE};
+
+use std::{error::Error, fmt};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OaepError {
+    /// the message does not fit in the modulus, at most this many bytes do
+    MessageTooLong(usize),
+    /// the ciphertext is malformed or was not made for this key; deliberately
+    /// says nothing more, to give no padding oracle
+    Decryption,
+}
+
+impl fmt::Display for OaepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OaepError::MessageTooLong(max) => write!(f, "message too long for the RSA key, at most {} bytes fit", max),
+            OaepError::Decryption => write!(f, "RSA-OAEP decryption error"),
+        }
+    }
+}
+
+impl Error for OaepError {}
+
+/// the longest message an OAEP ciphertext under a `k`-byte modulus can carry
+pub fn max_message_len(k: usize) -> usize {
+    k.saturating_sub(2 * DIGEST_SIZE + 2)
+}
+
+/// MGF1 with SHA-256: the hashes of `seed` followed by a 4-byte counter
+fn mgf1(seed: &[u8], len: usize) -> Vec<u8> {
+    let mut mask = Vec::with_capacity(len + DIGEST_SIZE);
+    for counter in 0u32.. {
+        if mask.len() >= len {
+            break;
+        }
+        let mut hasher = Sha256::new();
+        hasher.update(seed).update(&counter.to_be_bytes());
+        mask.extend_from_slice(&hasher.finalize());
+    }
+    mask.truncate(len);
+    mask
+}
+
+fn xor(target: &mut [u8], mask: &[u8]) {
+    for (t, m) in target.iter_mut().zip(mask) {
+        *t ^= m;
+    }
+}
+
+pub fn encrypt(key: &PublicKey, message: &[u8], mut rng: impl CryptoRng) -> Result<Vec<u8>, OaepError> {
+    let k = key.size();
+    if message.len() > max_message_len(k) {
+        return Err(OaepError::MessageTooLong(max_message_len(k)));
+    }
+
+    // DB = lHash || PS || 0x01 || M
+    let mut db = sha256(b"").to_vec();
+    db.resize(k - message.len() - DIGEST_SIZE - 2, 0);
+    db.push(0x01);
+    db.extend_from_slice(message);
+    let mut seed = [0u8; DIGEST_SIZE];
+    rng.fill_bytes(&mut seed);
+    let db_mask = mgf1(&seed, db.len());
+    xor(&mut db, &db_mask);
+    xor(&mut seed, &mgf1(&db, DIGEST_SIZE));
+
+    // EM = 0x00 || maskedSeed || maskedDB
+    let mut em = vec![0x00];
+    em.extend_from_slice(&seed);
+    em.extend_from_slice(&db);
+    let c = rsa::encrypt(&rsa::from_bytes(&em), &key.e, &key.n);
+    Ok(rsa::to_bytes(&c, k).expect("c < n"))
+}
+
+pub fn decrypt(key: &PrivateKey, ciphertext: &[u8]) -> Result<Vec<u8>, OaepError> {
+    let k = key.public_key().size();
+    let c = rsa::from_bytes(ciphertext);
+    if ciphertext.len() != k || k < 2 * DIGEST_SIZE + 2 || c >= key.n {
+        return Err(OaepError::Decryption);
+    }
+    let em = rsa::to_bytes(&rsa::decrypt(&c, &key.d, &key.n), k).ok_or(OaepError::Decryption)?;
+
+    let (y, rest) = em.split_at(1);
+    let (masked_seed, masked_db) = rest.split_at(DIGEST_SIZE);
+    let mut seed = masked_seed.to_vec();
+    xor(&mut seed, &mgf1(masked_db, DIGEST_SIZE));
+    let mut db = masked_db.to_vec();
+    xor(&mut db, &mgf1(&seed, masked_db.len()));
+
+    // the checks are folded into one flag so that failures look alike
+    let (l_hash, padded) = db.split_at(DIGEST_SIZE);
+    let mut bad = y[0] | l_hash.iter().zip(sha256(b"")).fold(0, |acc, (a, b)| acc | (a ^ b));
+    let mut separator = None;
+    for (i, &b) in padded.iter().enumerate() {
+        let first_nonzero = separator.is_none() && b != 0;
+        if first_nonzero {
+            separator = Some(i);
+            bad |= b ^ 0x01;
+        }
+    }
+    match separator {
+        Some(i) if bad == 0 => Ok(padded[i + 1..].to_vec()),
+        _ => Err(OaepError::Decryption),
+    }
+}
@@ -0,0 +1,272 @@
+use num_bigint::{BigInt, Sign};
+use num_integer::Integer;
+use num_traits::{One, Zero};
+use rand::RngCore;
+
+use std::{error::Error, fmt, str::FromStr};
+
+pub const SECURITY_LEVELS: [(u32, u32); 6] = [
+    (1, 64), // for toy example only
+    (80, 1024),
+    (112, 2048),
+    (128, 3072),
+    (192, 7680),
+    (256, 15360),
+];
+
+/// odd primes below 256, to rule out most candidates before Miller-Rabin
+const SMALL_PRIMES: [u32; 53] = [
+    3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191,
+    193, 197, 199, 211, 223, 227, 229, 233, 239, 241, 251,
+];
+
+fn generate_large_prime(bit_length: u32) -> BigInt {
+    let mut rng = rand::rng();
+    let bytes_len = (bit_length as usize).div_ceil(8);
+    loop {
+        // Generate random bytes and convert to BigInt
+        let mut bytes = vec![0u8; bytes_len];
+        rng.fill_bytes(&mut bytes);
+        let mut candidate = BigInt::from_bytes_be(Sign::Plus, &bytes) >> (bytes_len * 8 - bit_length as usize);
+        // the two top bits make the product of two such primes exactly twice as long
+        candidate |= BigInt::from(3u32) << (bit_length - 2);
+        candidate |= BigInt::one();
+        if is_prime(&candidate) {
+            return candidate;
+        }
+    }
+}
+
+/// Miller-Rabin primality test
+pub fn is_prime(n: &BigInt) -> bool {
+    if n <= &BigInt::one() {
+        return false;
+    }
+    if n == &BigInt::from(2u32) || n == &BigInt::from(3u32) {
+        return true;
+    }
+    if n.is_even() {
+        return false;
+    }
+    for p in SMALL_PRIMES {
+        if n == &BigInt::from(p) {
+            return true;
+        }
+        if (n % p).is_zero() {
+            return false;
+        }
+    }
+
+    let mut d = n - BigInt::one();
+    let mut s = 0;
+    while d.is_even() {
+        d >>= 1;
+        s += 1;
+    }
+
+    let bases = [
+        2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37,
+    ].iter().map(|&x| BigInt::from(x)).collect::<Vec<_>>();
+
+    'next_base: for base in bases {
+        if base >= *n {
+            continue;
+        }
+        let mut x = base.modpow(&d, n);
+        if x == BigInt::one() || x == n - BigInt::one() {
+            continue;
+        }
+        for _ in 0..s - 1 {
+            x = x.modpow(&BigInt::from(2u32), n);
+            if x == n - BigInt::one() {
+                continue 'next_base;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+fn extended_gcd(a: &BigInt, b: &BigInt) -> (BigInt, BigInt, BigInt) {
+    if a.is_zero() {
+        (b.clone(), BigInt::zero(), BigInt::one())
+    } else {
+        let (g, x, y) = extended_gcd(&(b % a), a);
+        (g, y - (b / a) * x.clone(), x)
+    }
+}
+
+fn mod_inverse(a: BigInt, m: BigInt) -> Option<BigInt> {
+    let (g, x, _) = extended_gcd(&a, &m);
+    if g != BigInt::one() {
+        None
+    } else {
+        Some((x % &m + &m) % m)
+    }
+}
+
+pub fn modulus_bits(security_level: u32) -> Option<u32> {
+    SECURITY_LEVELS.iter()
+        .find(|&&(sl, _)| sl == security_level)
+        .map(|&(_, mb)| mb)
+}
+
+pub fn generate(security_level: u32) -> (BigInt, BigInt, BigInt, BigInt, BigInt) {
+    let modulus_bits = modulus_bits(security_level).expect("Invalid security level");
+    let e = BigInt::from(65537u32);
+
+    // retry in the rare case that e divides p-1 or q-1, or p = q
+    loop {
+        let p = generate_large_prime(modulus_bits / 2);
+        let q = generate_large_prime(modulus_bits / 2);
+        if p == q {
+            continue;
+        }
+        let n = &p * &q;
+        let phi = (&p - BigInt::one()) * (&q - BigInt::one());
+        if let Some(d) = mod_inverse(e.clone(), phi) {
+            return (p, q, n, e, d);
+        }
+    }
+}
+
+pub fn encrypt(m: &BigInt, e: &BigInt, n: &BigInt) -> BigInt {
+    m.modpow(e, n)
+}
+
+pub fn decrypt(c: &BigInt, d: &BigInt, n: &BigInt) -> BigInt {
+    c.modpow(d, n)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicKey {
+    pub n: BigInt,
+    pub e: BigInt,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrivateKey {
+    pub n: BigInt,
+    pub e: BigInt,
+    pub d: BigInt,
+    pub p: BigInt,
+    pub q: BigInt,
+}
+
+impl PrivateKey {
+    pub fn generate(security_level: u32) -> Self {
+        let (p, q, n, e, d) = generate(security_level);
+        PrivateKey { n, e, d, p, q }
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey {
+            n: self.n.clone(),
+            e: self.e.clone(),
+        }
+    }
+}
+
+impl PublicKey {
+    /// length of the modulus in bytes, the size of every ciphertext
+    pub fn size(&self) -> usize {
+        (self.n.bits() as usize).div_ceil(8)
+    }
+}
+
+/// big-endian bytes of `x`, left-padded with zeros to `len` bytes (I2OSP)
+pub fn to_bytes(x: &BigInt, len: usize) -> Option<Vec<u8>> {
+    let (_, bytes) = x.to_bytes_be();
+    if x.sign() == Sign::Minus || bytes.len() > len {
+        return None;
+    }
+    let mut out = vec![0u8; len - bytes.len()];
+    out.extend(bytes);
+    Some(out)
+}
+
+/// the non-negative integer of big-endian `bytes` (OS2IP)
+pub fn from_bytes(bytes: &[u8]) -> BigInt {
+    BigInt::from_bytes_be(Sign::Plus, bytes)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseKeyError(pub String);
+
+impl fmt::Display for ParseKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid RSA key file: {}", self.0)
+    }
+}
+
+impl Error for ParseKeyError {}
+
+const PUBLIC_HEADER: &str = "h5 rsa public key";
+const PRIVATE_HEADER: &str = "h5 rsa private key";
+
+/// `name value` lines with hexadecimal values after a header line
+fn write_fields(f: &mut fmt::Formatter<'_>, header: &str, fields: &[(&str, &BigInt)]) -> fmt::Result {
+    writeln!(f, "{}", header)?;
+    for (name, value) in fields {
+        writeln!(f, "{} {:x}", name, value)?;
+    }
+    Ok(())
+}
+
+fn read_fields<const N: usize>(s: &str, header: &str, names: [&str; N]) -> Result<[BigInt; N], ParseKeyError> {
+    let mut lines = s.lines().map(str::trim).filter(|line| !line.is_empty());
+    if lines.next() != Some(header) {
+        return Err(ParseKeyError(format!("expected \"{}\"", header)));
+    }
+    let mut values: [Option<BigInt>; N] = std::array::from_fn(|_| None);
+    for line in lines {
+        let (name, value) = line.split_once(' ').ok_or_else(|| ParseKeyError(format!("bad line \"{}\"", line)))?;
+        let i = names.iter().position(|&n| n == name).ok_or_else(|| ParseKeyError(format!("unknown field \"{}\"", name)))?;
+        let value = BigInt::parse_bytes(value.trim().as_bytes(), 16)
+            .ok_or_else(|| ParseKeyError(format!("field \"{}\" is not hexadecimal", name)))?;
+        values[i] = Some(value);
+    }
+    let mut missing = names.iter().zip(&values).filter(|(_, v)| v.is_none()).map(|(n, _)| *n);
+    if let Some(name) = missing.next() {
+        return Err(ParseKeyError(format!("missing field \"{}\"", name)));
+    }
+    Ok(values.map(Option::unwrap))
+}
+
+impl fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_fields(f, PUBLIC_HEADER, &[("n", &self.n), ("e", &self.e)])
+    }
+}
+
+impl fmt::Display for PrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_fields(
+            f,
+            PRIVATE_HEADER,
+            &[("n", &self.n), ("e", &self.e), ("d", &self.d), ("p", &self.p), ("q", &self.q)],
+        )
+    }
+}
+
+impl FromStr for PublicKey {
+    type Err = ParseKeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let [n, e] = read_fields(s, PUBLIC_HEADER, ["n", "e"])?;
+        Ok(PublicKey { n, e })
+    }
+}
+
+impl FromStr for PrivateKey {
+    type Err = ParseKeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let [n, e, d, p, q] = read_fields(s, PRIVATE_HEADER, ["n", "e", "d", "p", "q"])?;
+        if &p * &q != n {
+            return Err(ParseKeyError(String::from("n is not p * q")));
+        }
+        Ok(PrivateKey { n, e, d, p, q })
+    }
+}
@@ -0,0 +1,90 @@
+use h5::hybrid::{self, HybridError};
+use h5::oaep::{self, OaepError};
+use h5::rsa::{PrivateKey, PublicKey};
+
+use base64::prelude::*;
+
+/// 1024-bit test key, never use it for anything else
+const PRIVATE_KEY: &str = "\
+h5 rsa private key\n\
+n c137bf5a79958db9fb15d8712d14c463a5ac2d79fd97e99f264a8cfac7b0d911b4370ba3b764f2b1983d85f40798174f70921dddd44560e81c2b629f40fbd0743746ceaef6ac9f68adfd5711f11d6fc5d4845283e28f07fbdfac02060b227dc76e7d028c45c390eeabaff7d908097499c0adda61d666b661ad93199dbf338d73\n\
+e 10001\n\
+d 4929c2db8f8e81cc1ab77aede0142d094e8293f5cd80e885cb0a0fb8264560ecc340d4b7798a501bcf79fc6f1b310c22988dbba173ef917252b85aefd2bb8782d113e2513d75889ddc0bb2b8433718e8cb44a487c7202b7adceccbaf4f4bdfeb01651bf5f389c378723329cb548ad2a2d2c126d8af45b40b806b809017751301\n\
+p cb90beeac5dddeeb78332af4c7bc8f451c7e1aa3b50af3bfdcf29d63086dc338dffc7cd95ac626a65592cceeffdde433d20384773e3eb436ef0d695227e9fb89\n\
+q f2fcab8676641057330cde008bc1f81b550491de13af7629c495a7076a68fa0830412b2280098e926332d4c5682b28eedf46369e9b24d6947f9068363756561b\n\
+";
+
+/// "Sealed by an independent RSA-OAEP implementation", sealed for PRIVATE_KEY by
+/// Python's `cryptography` (OAEP with SHA-256) around ChaCha20 from RFC 8439
+const FOREIGN_CONTAINER: &str = "SDVSQwEAgC80yFCa+bEK1zqvzHW/GV4sSIn+wYIKoZQnzZtD4Dj+duxQ3Q609JN+5Tnyerl0Aa1SEvZMnGPxfF9Kkk5SpETzq8G9o7AaLcuW4x07J3yqCXvy+bCZoDs9aUqMOBHsb6ND4OgyyOyBj/CQdu/KXkox2q7pz22GGO/tDeLvBEo5eSuM2HFcMAKnzKpoUOubnSZnJmlltJJExBKRIiRdjPrhhs4EqfxdXV7in3kCGPdW";
+
+fn private_key() -> PrivateKey {
+    PRIVATE_KEY.parse().unwrap()
+}
+
+#[test]
+fn key_files_round_trip() {
+    let private_key = private_key();
+    assert_eq!(private_key.to_string().parse::<PrivateKey>().unwrap(), private_key);
+    let public_key = private_key.public_key();
+    assert_eq!(public_key.to_string().parse::<PublicKey>().unwrap(), public_key);
+    assert_eq!(public_key.size(), 128);
+
+    assert!(public_key.to_string().parse::<PrivateKey>().is_err());
+    assert!(PRIVATE_KEY.replace("\ne ", "\nx ").parse::<PrivateKey>().is_err());
+}
+
+#[test]
+fn opens_foreign_container() {
+    let container = BASE64_STANDARD.decode(FOREIGN_CONTAINER).unwrap();
+    assert_eq!(hybrid::open(&private_key(), &container).unwrap(), b"Sealed by an independent RSA-OAEP implementation");
+}
+
+#[test]
+fn seal_and_open() {
+    let private_key = private_key();
+    for message in [&b""[..], b"x", &[0xab; 1000]] {
+        let container = hybrid::seal(&private_key.public_key(), message, rand::rng()).unwrap();
+        assert_eq!(container.len(), 7 + 128 + message.len());
+        assert_eq!(hybrid::open(&private_key, &container).unwrap(), message);
+    }
+    // fresh message key and OAEP seed every time
+    let a = hybrid::seal(&private_key.public_key(), b"same", rand::rng()).unwrap();
+    let b = hybrid::seal(&private_key.public_key(), b"same", rand::rng()).unwrap();
+    assert_ne!(a, b);
+}
+
+#[test]
+fn damaged_containers_are_rejected() {
+    let private_key = private_key();
+    let container = hybrid::seal(&private_key.public_key(), b"payload", rand::rng()).unwrap();
+
+    assert_eq!(hybrid::open(&private_key, &container[..5]), Err(HybridError::Truncated));
+    assert_eq!(hybrid::open(&private_key, &container[..100]), Err(HybridError::Truncated));
+    let mut bad = container.clone();
+    bad[0] ^= 1;
+    assert_eq!(hybrid::open(&private_key, &bad), Err(HybridError::BadMagic));
+    let mut bad = container.clone();
+    bad[4] = 2;
+    assert_eq!(hybrid::open(&private_key, &bad), Err(HybridError::UnsupportedVersion(2)));
+    let mut bad = container.clone();
+    bad[50] ^= 1;
+    assert_eq!(hybrid::open(&private_key, &bad), Err(HybridError::Unwrap(OaepError::Decryption)));
+
+    let other = PrivateKey::generate(80);
+    assert_eq!(hybrid::open(&other, &container), Err(HybridError::Unwrap(OaepError::Decryption)));
+}
+
+#[test]
+fn oaep_limits() {
+    let private_key = private_key();
+    let public_key = private_key.public_key();
+    assert_eq!(oaep::max_message_len(128), 62);
+    let wrapped = oaep::encrypt(&public_key, &[7; 62], rand::rng()).unwrap();
+    assert_eq!(oaep::decrypt(&private_key, &wrapped).unwrap(), [7; 62]);
+    assert_eq!(oaep::encrypt(&public_key, &[7; 63], rand::rng()), Err(OaepError::MessageTooLong(62)));
+
+    // the toy level cannot carry a 48-byte ChaCha20 key
+    let toy = PrivateKey::generate(1);
+    assert_eq!(hybrid::seal(&toy.public_key(), b"hi", rand::rng()), Err(HybridError::KeyTooSmall));
+}